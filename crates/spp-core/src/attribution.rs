@@ -0,0 +1,1334 @@
+//! Commit attribution: deciding whether a commit counts as human or AI work.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, Utc};
+use regex::Regex;
+
+use crate::backend::{open_backend, GitBackend, Revisions};
+use crate::config::{AppConfig, AttributionConfig, AttributionRule};
+use crate::git::{git_output, require_git_executable};
+use crate::glob::Glob;
+use crate::history::{load_commit_records, CommitRecord};
+use crate::state::{parse_ai_share, Actor, AttributionOverride, State};
+
+/// Why a commit got its actor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributionSource {
+    /// `spp attrib fix` override in `state.json`.
+    Override,
+    /// Local override of an earlier version of the commit (rebase, cherry-pick,
+    /// squash), matched by patch-id or change id.
+    InheritedOverride { from: Vec<String>, via: String },
+    /// Shared override in the `[attribution].notes_ref` git notes.
+    SharedOverride { notes_ref: String },
+    /// `SPP-AI-Share:` trailer in the commit message.
+    ShareTrailer,
+    /// `SPP-AI-Share:` line in the commit's git notes.
+    ShareNote,
+    /// A configured or built-in rule fired.
+    Rule { name: String, description: String },
+    /// No rule matched; commits default to the human.
+    Default,
+}
+
+impl fmt::Display for AttributionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Override => write!(f, "manual override (spp attrib fix)"),
+            Self::InheritedOverride { from, via } => {
+                let from = from
+                    .iter()
+                    .map(|commit| commit.get(..10).unwrap_or(commit))
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "override inherited from {} (matched by {via})",
+                    from.join(", ")
+                )
+            }
+            Self::SharedOverride { notes_ref } => write!(f, "shared override in {notes_ref}"),
+            Self::ShareTrailer => write!(f, "`{SHARE_KEY}` trailer"),
+            Self::ShareNote => write!(f, "`{SHARE_KEY}` git note"),
+            Self::Rule { name, description } => write!(f, "rule `{name}` ({description})"),
+            Self::Default => write!(f, "no rule matched (default human)"),
+        }
+    }
+}
+
+/// Trailer / git note key declaring the AI share of a commit, e.g. `SPP-AI-Share: 0.4`.
+pub const SHARE_KEY: &str = "SPP-AI-Share";
+/// Key of the actor line in shared override notes, e.g. `SPP-Actor: ai`.
+pub const ACTOR_KEY: &str = "SPP-Actor";
+
+/// Outcome of attributing one commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribution {
+    pub actor: Actor,
+    /// Fraction of the commit's added lines counted as AI work.
+    pub ai_share: f64,
+    pub source: AttributionSource,
+    /// Declarations that were ignored because they did not parse, e.g. a
+    /// malformed `SPP-AI-Share` trailer; surfaced as report notes.
+    pub notes: Vec<String>,
+}
+
+impl Attribution {
+    fn new(actor: Actor, ai_share: f64, source: AttributionSource) -> Self {
+        Self {
+            actor,
+            ai_share,
+            source,
+            notes: Vec::new(),
+        }
+    }
+
+    fn declared(ai_share: f64, source: AttributionSource) -> Self {
+        Self::new(Actor::from_ai_share(ai_share), ai_share, source)
+    }
+}
+
+/// Compiled `[attribution]` rules, evaluated in order after manual overrides.
+#[derive(Debug, Clone)]
+pub struct Attributor {
+    rules: Vec<CompiledRule>,
+    notes_ref: String,
+    match_patch_id: bool,
+    change_id_trailers: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    name: String,
+    actor: Actor,
+    ai_share: f64,
+    trailer: Option<Regex>,
+    message: Option<Regex>,
+    message_prefix: Option<String>,
+    author_email: Option<Glob>,
+    committer_email: Option<Glob>,
+    branch: Option<Glob>,
+    paths: Vec<Glob>,
+    note: Option<Regex>,
+}
+
+impl Attributor {
+    /// Compiles the configured rules followed, unless disabled, by the built-in ones.
+    pub fn new(config: &AttributionConfig) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (idx, rule) in config.rules.iter().enumerate() {
+            let name = if rule.name.trim().is_empty() {
+                format!("rules[{idx}]")
+            } else {
+                rule.name.clone()
+            };
+            rules.push(CompiledRule::compile(name, rule)?);
+        }
+        if config.builtin_rules {
+            for (name, rule) in builtin_rules(config) {
+                rules.push(CompiledRule::compile(name.to_string(), &rule)?);
+            }
+        }
+        Ok(Self {
+            rules,
+            notes_ref: config.notes_ref.clone(),
+            match_patch_id: config.match_patch_id,
+            change_id_trailers: config.change_id_trailers.clone(),
+        })
+    }
+
+    /// Attributes `commit`, reporting which override or rule decided.
+    ///
+    /// Local overrides in `state.json` (including ones inherited by patch-id or
+    /// change id) win over shared ones in the notes ref; an
+    /// `SPP-AI-Share` trailer or git note is honoured after both and before any rule.
+    pub fn classify(
+        &self,
+        git: &dyn GitBackend,
+        commit: &str,
+        state: &State,
+    ) -> Result<Attribution> {
+        let fingerprint = || commit_fingerprint(git, commit, &self.change_id_trailers);
+        self.classify_with(commit, state, fingerprint, || {
+            CommitFacts::load(git, commit, &self.notes_ref)
+        })
+    }
+
+    /// Like [`Attributor::classify`] for a commit whose metadata, notes and
+    /// (when [`Attributor::matches_patch_ids`]) patch-id were already loaded in
+    /// bulk (see [`crate::history`]), so git is only queried when a `branch`
+    /// rule needs it.
+    pub fn classify_loaded(
+        &self,
+        git: &dyn GitBackend,
+        loaded: &LoadedCommit,
+        state: &State,
+    ) -> Result<Attribution> {
+        let record = loaded.record;
+        let fingerprint = || {
+            Ok(CommitFingerprint {
+                patch_id: loaded.patch_id.map(str::to_string),
+                change_ids: change_ids_in(&record.message, &self.change_id_trailers),
+            })
+        };
+        self.classify_with(&record.commit, state, fingerprint, || {
+            Ok(CommitFacts::from_loaded(git, loaded))
+        })
+    }
+
+    /// Whether inheriting local overrides may need commit patch-ids, i.e.
+    /// `[attribution].match_patch_id` is on and some override carries one.
+    /// Callers of [`Attributor::classify_loaded`] then load
+    /// [`LoadedCommit::patch_id`] with one [`GitBackend::patch_ids`] call.
+    pub fn matches_patch_ids(&self, state: &State) -> bool {
+        self.match_patch_id
+            && state
+                .attribution_overrides
+                .values()
+                .any(|entry| entry.patch_id.is_some())
+    }
+
+    fn classify_with<'a>(
+        &self,
+        commit: &str,
+        state: &State,
+        fingerprint: impl FnOnce() -> Result<CommitFingerprint>,
+        load: impl FnOnce() -> Result<CommitFacts<'a>>,
+    ) -> Result<Attribution> {
+        if let Some(entry) = state.attribution_overrides.get(commit) {
+            return Ok(Attribution::new(
+                entry.actor,
+                entry.ai_share(),
+                AttributionSource::Override,
+            ));
+        }
+        if let Some(inherited) = self.inherited_override(commit, state, fingerprint)? {
+            return Ok(inherited);
+        }
+
+        let mut facts = load()?;
+        let mut notes = Vec::new();
+        let mut attribution = self.classify_facts(&mut facts, commit, &mut notes)?;
+        attribution.notes = notes;
+        Ok(attribution)
+    }
+
+    /// Shared note, share declarations and rules, in that order. Declarations
+    /// that do not parse are skipped with a note in `notes`, so one bad commit
+    /// message cannot block the gate.
+    fn classify_facts(
+        &self,
+        facts: &mut CommitFacts,
+        commit: &str,
+        notes: &mut Vec<String>,
+    ) -> Result<Attribution> {
+        let short = commit.get(..10).unwrap_or(commit);
+        let shared = parse_override_note(&facts.shared_note).unwrap_or_else(|err| {
+            notes.push(format!(
+                "ignored invalid {} note on commit {short}: {err}",
+                self.notes_ref
+            ));
+            None
+        });
+        if let Some(entry) = shared {
+            return Ok(Attribution::new(
+                entry.actor,
+                entry.ai_share(),
+                AttributionSource::SharedOverride {
+                    notes_ref: self.notes_ref.clone(),
+                },
+            ));
+        }
+        let trailer =
+            declared_share(facts.trailers.iter().map(String::as_str)).unwrap_or_else(|err| {
+                notes.push(format!(
+                    "ignored invalid {SHARE_KEY} trailer in commit {short}: {err}"
+                ));
+                None
+            });
+        if let Some(share) = trailer {
+            return Ok(Attribution::declared(
+                share,
+                AttributionSource::ShareTrailer,
+            ));
+        }
+        if let Some(git_notes) = facts.notes()? {
+            let declared = declared_share(git_notes.lines()).unwrap_or_else(|err| {
+                notes.push(format!(
+                    "ignored invalid {SHARE_KEY} git note on commit {short}: {err}"
+                ));
+                None
+            });
+            if let Some(share) = declared {
+                return Ok(Attribution::declared(share, AttributionSource::ShareNote));
+            }
+        }
+
+        for rule in &self.rules {
+            if rule.matches(facts)? {
+                return Ok(Attribution::new(
+                    rule.actor,
+                    rule.ai_share,
+                    AttributionSource::Rule {
+                        name: rule.name.clone(),
+                        description: rule.describe(),
+                    },
+                ));
+            }
+        }
+
+        Ok(Attribution::new(
+            Actor::Human,
+            0.0,
+            AttributionSource::Default,
+        ))
+    }
+}
+
+impl Attributor {
+    /// Local overrides of other commits whose patch-id or change id matches `commit`.
+    /// Several matches (a squash) average their AI shares.
+    fn inherited_override(
+        &self,
+        commit: &str,
+        state: &State,
+        fingerprint: impl FnOnce() -> Result<CommitFingerprint>,
+    ) -> Result<Option<Attribution>> {
+        let candidates = state
+            .attribution_overrides
+            .iter()
+            .filter(|(key, entry)| {
+                key.as_str() != commit
+                    && ((self.match_patch_id && entry.patch_id.is_some())
+                        || !entry.change_ids.is_empty())
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let fingerprint = fingerprint()?;
+        let mut matched = candidates
+            .into_iter()
+            .filter_map(|(key, entry)| {
+                let via = if self.match_patch_id
+                    && entry.patch_id.is_some()
+                    && entry.patch_id == fingerprint.patch_id
+                {
+                    "patch-id"
+                } else if entry
+                    .change_ids
+                    .iter()
+                    .any(|id| fingerprint.change_ids.contains(id))
+                {
+                    "change id"
+                } else {
+                    return None;
+                };
+                Some((key.clone(), entry, via))
+            })
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            return Ok(None);
+        }
+        matched.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let ai_share = matched
+            .iter()
+            .map(|(_, entry, _)| entry.ai_share())
+            .sum::<f64>()
+            / matched.len() as f64;
+        let actor = match matched.as_slice() {
+            [(_, entry, _)] => entry.actor,
+            _ => Actor::from_ai_share(ai_share),
+        };
+        let via = matched[0].2.to_string();
+        Ok(Some(Attribution::new(
+            actor,
+            ai_share,
+            AttributionSource::InheritedOverride {
+                from: matched.into_iter().map(|(key, _, _)| key).collect(),
+                via,
+            },
+        )))
+    }
+}
+
+/// What identifies a commit's change across rewrites.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitFingerprint {
+    /// `git patch-id --stable`; `None` for commits without a diff.
+    pub patch_id: Option<String>,
+    /// Values of `change_id_trailers` lines anywhere in the message, so squash
+    /// commits that quote the original messages still match.
+    pub change_ids: Vec<String>,
+}
+
+/// Computes the patch-id and change ids of `commit`.
+pub fn commit_fingerprint(
+    git: &dyn GitBackend,
+    commit: &str,
+    change_id_trailers: &[String],
+) -> Result<CommitFingerprint> {
+    let patch_id = git.patch_id(commit)?;
+    let change_ids = if change_id_trailers.is_empty() {
+        Vec::new()
+    } else {
+        let message = git.commit_message(commit)?;
+        change_ids_in(&message, change_id_trailers)
+    };
+    Ok(CommitFingerprint {
+        patch_id,
+        change_ids,
+    })
+}
+
+fn change_ids_in(message: &str, keys: &[String]) -> Vec<String> {
+    let mut ids = Vec::new();
+    for line in message.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if !value.is_empty()
+            && keys.iter().any(|k| k.eq_ignore_ascii_case(key.trim()))
+            && !ids.iter().any(|id| id == value)
+        {
+            ids.push(value.to_string());
+        }
+    }
+    ids
+}
+
+/// A local override whose commit is no longer reachable from any branch or tag.
+#[derive(Debug, Clone)]
+pub struct OrphanedOverride {
+    pub commit: String,
+    pub entry: AttributionOverride,
+    /// Reachable commit from the last `since_days` days that inherits the override.
+    pub successor: Option<String>,
+}
+
+/// Lists local overrides whose commit was rewritten away (or deleted), with the
+/// rewritten commit that inherits each, if one is found.
+pub fn orphaned_overrides(
+    git: &dyn GitBackend,
+    config: &AttributionConfig,
+    state: &State,
+    since_days: u32,
+) -> Result<Vec<OrphanedOverride>> {
+    let mut orphans = state
+        .attribution_overrides
+        .iter()
+        .filter(|(commit, _)| !git.is_reachable(commit))
+        .map(|(commit, entry)| OrphanedOverride {
+            commit: commit.clone(),
+            entry: entry.clone(),
+            successor: None,
+        })
+        .collect::<Vec<_>>();
+    if orphans.is_empty() {
+        return Ok(orphans);
+    }
+    orphans.sort_by(|a, b| a.commit.cmp(&b.commit));
+
+    // Newest first, so the latest rewrite wins.
+    let since = Utc::now() - Duration::days(i64::from(since_days));
+    let recent = git
+        .commit_times(&Revisions::All, since)?
+        .into_iter()
+        .map(|times| times.commit)
+        .collect::<Vec<_>>();
+    let mut by_patch_id = HashMap::new();
+    if config.match_patch_id {
+        let patch_ids = git.patch_ids(&recent)?;
+        for commit in &recent {
+            if let Some(patch_id) = patch_ids.get(commit) {
+                by_patch_id
+                    .entry(patch_id.clone())
+                    .or_insert_with(|| commit.clone());
+            }
+        }
+    }
+    let mut by_change_id = HashMap::new();
+    if !config.change_id_trailers.is_empty() {
+        let records = load_commit_records(git, &recent)?;
+        for commit in &recent {
+            let Some(record) = records.get(commit) else {
+                continue;
+            };
+            for id in change_ids_in(&record.message, &config.change_id_trailers) {
+                by_change_id.entry(id).or_insert_with(|| commit.clone());
+            }
+        }
+    }
+
+    for orphan in &mut orphans {
+        let by_patch = orphan
+            .entry
+            .patch_id
+            .as_ref()
+            .and_then(|patch_id| by_patch_id.get(patch_id));
+        let by_change = orphan
+            .entry
+            .change_ids
+            .iter()
+            .find_map(|id| by_change_id.get(id));
+        orphan.successor = by_patch.or(by_change).cloned();
+    }
+    Ok(orphans)
+}
+
+/// The historical default chain: a `Co-Authored-By: Codex` line, configured
+/// Codex author emails, then `spp:ai` / `spp:human` git notes.
+fn builtin_rules(config: &AttributionConfig) -> Vec<(&'static str, AttributionRule)> {
+    // Matched anywhere in the message, not only in the trailer block, as
+    // before configurable rules existed.
+    let mut rules = vec![(
+        "builtin:codex-trailer",
+        AttributionRule {
+            actor: Actor::Ai,
+            message: Some(r"(?i)co-authored-by:\s*codex".to_string()),
+            ..AttributionRule::default()
+        },
+    )];
+    for email in &config.codex_author_emails {
+        rules.push((
+            "builtin:codex-author-email",
+            AttributionRule {
+                actor: Actor::Ai,
+                author_email: Some(email.clone()),
+                ..AttributionRule::default()
+            },
+        ));
+    }
+    rules.push((
+        "builtin:note-ai",
+        AttributionRule {
+            actor: Actor::Ai,
+            note: Some("(?i)spp:ai".to_string()),
+            ..AttributionRule::default()
+        },
+    ));
+    rules.push((
+        "builtin:note-human",
+        AttributionRule {
+            actor: Actor::Human,
+            note: Some("(?i)spp:human".to_string()),
+            ..AttributionRule::default()
+        },
+    ));
+    rules
+}
+
+impl CompiledRule {
+    fn compile(name: String, rule: &AttributionRule) -> Result<Self> {
+        let regex = |raw: &Option<String>, field: &str| -> Result<Option<Regex>> {
+            raw.as_deref()
+                .map(|pattern| {
+                    Regex::new(pattern).with_context(|| {
+                        format!("attribution rule `{name}`: invalid {field} regex `{pattern}`")
+                    })
+                })
+                .transpose()
+        };
+        let glob = |raw: &Option<String>, field: &str| -> Result<Option<Glob>> {
+            raw.as_deref()
+                .map(|pattern| {
+                    Glob::case_insensitive(pattern)
+                        .with_context(|| format!("attribution rule `{name}`: invalid {field}"))
+                })
+                .transpose()
+        };
+
+        let compiled = Self {
+            trailer: regex(&rule.trailer, "trailer")?,
+            message: regex(&rule.message, "message")?,
+            message_prefix: rule.message_prefix.clone(),
+            author_email: glob(&rule.author_email, "author_email")?,
+            committer_email: glob(&rule.committer_email, "committer_email")?,
+            branch: glob(&rule.branch, "branch")?,
+            paths: rule
+                .paths
+                .iter()
+                .map(|pattern| {
+                    Glob::new(pattern)
+                        .with_context(|| format!("attribution rule `{name}`: invalid paths"))
+                })
+                .collect::<Result<_>>()?,
+            note: regex(&rule.note, "note")?,
+            actor: rule.actor,
+            ai_share: match rule.ai_share {
+                Some(share) if rule.actor != Actor::Mixed => {
+                    bail!("attribution rule `{name}`: ai_share {share} requires actor = \"mixed\"")
+                }
+                Some(share) if !(0.0..=1.0).contains(&share) => {
+                    bail!("attribution rule `{name}`: ai_share {share} must be between 0 and 1")
+                }
+                Some(share) => share,
+                None => rule.actor.default_ai_share(),
+            },
+            name,
+        };
+        if compiled.describe().is_empty() {
+            bail!("attribution rule `{}` has no matchers", compiled.name);
+        }
+        Ok(compiled)
+    }
+
+    fn matches(&self, facts: &mut CommitFacts) -> Result<bool> {
+        if let Some(re) = &self.trailer {
+            if !facts.trailers.iter().any(|line| re.is_match(line)) {
+                return Ok(false);
+            }
+        }
+        if let Some(re) = &self.message {
+            if !re.is_match(&facts.message) {
+                return Ok(false);
+            }
+        }
+        if let Some(prefix) = &self.message_prefix {
+            if !facts.subject.starts_with(prefix.as_str()) {
+                return Ok(false);
+            }
+        }
+        if let Some(glob) = &self.author_email {
+            if !glob.is_match(&facts.author_email) {
+                return Ok(false);
+            }
+        }
+        if let Some(glob) = &self.committer_email {
+            if !glob.is_match(&facts.committer_email) {
+                return Ok(false);
+            }
+        }
+        if let Some(glob) = &self.branch {
+            if !facts.branches()?.iter().any(|branch| glob.is_match(branch)) {
+                return Ok(false);
+            }
+        }
+        if !self.paths.is_empty() {
+            let paths = facts.paths();
+            let all_match = !paths.is_empty()
+                && paths
+                    .iter()
+                    .all(|path| self.paths.iter().any(|glob| glob.matches_path(path)));
+            if !all_match {
+                return Ok(false);
+            }
+        }
+        if let Some(re) = &self.note {
+            if !facts.notes()?.is_some_and(|notes| re.is_match(notes)) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Human-readable list of the matchers, e.g. `trailer =~ /.../, branch ~ codex/*`.
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(re) = &self.trailer {
+            parts.push(format!("trailer =~ /{}/", re.as_str()));
+        }
+        if let Some(re) = &self.message {
+            parts.push(format!("message =~ /{}/", re.as_str()));
+        }
+        if let Some(prefix) = &self.message_prefix {
+            parts.push(format!("message starts with `{prefix}`"));
+        }
+        if let Some(glob) = &self.author_email {
+            parts.push(format!("author_email ~ {}", glob.as_str()));
+        }
+        if let Some(glob) = &self.committer_email {
+            parts.push(format!("committer_email ~ {}", glob.as_str()));
+        }
+        if let Some(glob) = &self.branch {
+            parts.push(format!("branch ~ {}", glob.as_str()));
+        }
+        if !self.paths.is_empty() {
+            let globs = self.paths.iter().map(Glob::as_str).collect::<Vec<_>>();
+            parts.push(format!("paths ~ [{}]", globs.join(", ")));
+        }
+        if let Some(re) = &self.note {
+            parts.push(format!("note =~ /{}/", re.as_str()));
+        }
+        parts.join(", ")
+    }
+}
+
+/// A commit's metadata and notes loaded ahead of [`Attributor::classify_loaded`].
+#[derive(Debug, Clone, Copy)]
+pub struct LoadedCommit<'a> {
+    pub record: &'a CommitRecord,
+    /// Note in `[attribution].notes_ref`, if any.
+    pub shared_note: Option<&'a str>,
+    /// Note in the default notes ref, if any.
+    pub notes: Option<&'a str>,
+    /// `git patch-id --stable`, when loaded (see [`Attributor::matches_patch_ids`]).
+    pub patch_id: Option<&'a str>,
+}
+
+/// Commit metadata rules match against; branches and default notes are only
+/// queried when a rule needs them.
+struct CommitFacts<'a> {
+    git: &'a dyn GitBackend,
+    commit: &'a str,
+    subject: String,
+    message: String,
+    trailers: Vec<String>,
+    author_email: String,
+    committer_email: String,
+    /// Note on the commit in `[attribution].notes_ref`, empty when there is none.
+    shared_note: String,
+    branches: Option<Vec<String>>,
+    paths: Vec<String>,
+    notes: Option<Option<String>>,
+}
+
+impl<'a> CommitFacts<'a> {
+    fn load(git: &'a dyn GitBackend, commit: &'a str, notes_ref: &str) -> Result<Self> {
+        let commits = [commit.to_string()];
+        let record = git
+            .commit_records(&commits)?
+            .pop()
+            .with_context(|| format!("commit not found: {commit}"))?;
+        let shared_note = git
+            .commit_notes(Some(notes_ref), &commits)?
+            .remove(commit)
+            .unwrap_or_default();
+        Ok(Self {
+            git,
+            commit,
+            paths: record.paths(),
+            subject: record.subject,
+            message: record.message,
+            trailers: record.trailers,
+            author_email: record.author_email,
+            committer_email: record.committer_email,
+            shared_note,
+            branches: None,
+            notes: None,
+        })
+    }
+
+    fn from_loaded(git: &'a dyn GitBackend, loaded: &LoadedCommit<'a>) -> Self {
+        let record = loaded.record;
+        Self {
+            git,
+            commit: &record.commit,
+            subject: record.subject.clone(),
+            message: record.message.clone(),
+            trailers: record.trailers.clone(),
+            author_email: record.author_email.clone(),
+            committer_email: record.committer_email.clone(),
+            shared_note: loaded.shared_note.unwrap_or_default().to_string(),
+            branches: None,
+            paths: record.paths(),
+            notes: Some(loaded.notes.map(str::to_string)),
+        }
+    }
+
+    fn branches(&mut self) -> Result<&[String]> {
+        if self.branches.is_none() {
+            let refs = self.git.branches_containing(self.commit)?;
+            self.branches = Some(refs.iter().filter_map(|r| branch_name(r)).collect());
+        }
+        Ok(self.branches.as_deref().unwrap_or_default())
+    }
+
+    fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    fn notes(&mut self) -> Result<Option<&str>> {
+        if self.notes.is_none() {
+            let commits = [self.commit.to_string()];
+            self.notes = Some(self.git.commit_notes(None, &commits)?.remove(self.commit));
+        }
+        Ok(self.notes.as_ref().and_then(|notes| notes.as_deref()))
+    }
+}
+
+/// The last `SPP-AI-Share: <share>` line in `lines`, if any.
+fn declared_share<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Option<f64>> {
+    let mut declared = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if key.trim().eq_ignore_ascii_case(SHARE_KEY) {
+            declared = Some(parse_ai_share(value)?);
+        }
+    }
+    Ok(declared)
+}
+
+/// Body of a shared override note: `SPP-Actor: <actor>` and, for fractional
+/// attribution, `SPP-AI-Share: <share>`.
+pub fn format_override_note(entry: &AttributionOverride) -> String {
+    let mut note = format!("{ACTOR_KEY}: {}\n", entry.actor);
+    if let Some(share) = entry.ai_share {
+        note.push_str(&format!("{SHARE_KEY}: {share}\n"));
+    }
+    note
+}
+
+/// Parses a note written by [`format_override_note`]; `None` when it has no
+/// `SPP-Actor` or `SPP-AI-Share` line.
+pub fn parse_override_note(note: &str) -> Result<Option<AttributionOverride>> {
+    let mut actor = None;
+    let mut share = None;
+    for line in note.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.eq_ignore_ascii_case(ACTOR_KEY) {
+            actor = Some(value.parse::<Actor>()?);
+        } else if key.eq_ignore_ascii_case(SHARE_KEY) {
+            share = Some(parse_ai_share(value)?);
+        }
+    }
+    Ok(match (share, actor) {
+        (Some(share), _) => Some(AttributionOverride::from_ai_share(share)),
+        (None, Some(actor)) => Some(AttributionOverride::from_actor(actor)),
+        (None, None) => None,
+    })
+}
+
+/// Stores `entry` as the note on `commit` in `notes_ref`, replacing any previous one.
+pub fn write_override_note(
+    git: &dyn GitBackend,
+    notes_ref: &str,
+    commit: &str,
+    entry: &AttributionOverride,
+) -> Result<()> {
+    let note = format_override_note(entry);
+    git.write_note(notes_ref, commit, note.trim_end())
+}
+
+/// Saves `entry` for `commit` in `state` together with the commit's patch-id and
+/// change ids, or as a shared note in `notes_ref` (dropping any local override
+/// that would shadow it). Returns the entry as stored.
+pub fn store_override(
+    git: &dyn GitBackend,
+    config: &AttributionConfig,
+    state: &mut State,
+    to_notes: bool,
+    commit: &str,
+    mut entry: AttributionOverride,
+) -> Result<AttributionOverride> {
+    if to_notes {
+        write_override_note(git, &config.notes_ref, commit, &entry)?;
+        state.attribution_overrides.remove(commit);
+        return Ok(entry);
+    }
+
+    let fingerprint = commit_fingerprint(git, commit, &config.change_id_trailers)?;
+    entry.patch_id = fingerprint.patch_id;
+    entry.change_ids = fingerprint.change_ids;
+    state
+        .attribution_overrides
+        .insert(commit.to_string(), entry.clone());
+    Ok(entry)
+}
+
+/// Pushes `notes_ref` to `remote`. Rejected as non-fast-forward when the remote
+/// has notes this clone lacks; run [`pull_override_notes`] first.
+pub fn push_override_notes(repo_root: &Path, notes_ref: &str, remote: &str) -> Result<()> {
+    require_git_executable("`spp attrib push`")?;
+    git_output(
+        repo_root,
+        &["push", remote, &format!("{notes_ref}:{notes_ref}")],
+    )
+    .with_context(|| format!("failed to push {notes_ref}; try `spp attrib pull` first"))?;
+    Ok(())
+}
+
+/// Fetches `notes_ref` from `remote` and merges it into the local ref, keeping the
+/// remote note when both sides changed the same commit. Returns `false` when the
+/// remote has no such ref.
+pub fn pull_override_notes(repo_root: &Path, notes_ref: &str, remote: &str) -> Result<bool> {
+    require_git_executable("`spp attrib pull`")?;
+    let advertised = git_output(repo_root, &["ls-remote", remote, notes_ref])?;
+    if advertised.trim().is_empty() {
+        return Ok(false);
+    }
+    let name = notes_ref.rsplit('/').next().unwrap_or(notes_ref);
+    let tracking = format!("refs/remote-notes/{remote}/{name}");
+    git_output(
+        repo_root,
+        &["fetch", remote, &format!("+{notes_ref}:{tracking}")],
+    )?;
+    git_output(
+        repo_root,
+        &[
+            "notes", "--ref", notes_ref, "merge", "-q", "-s", "theirs", &tracking,
+        ],
+    )?;
+    Ok(true)
+}
+
+/// Branch name from a full ref: `refs/heads/x` -> `x`,
+/// `refs/remotes/origin/x` -> `x`; remote `HEAD` symrefs are skipped.
+fn branch_name(refname: &str) -> Option<String> {
+    let refname = refname.trim();
+    if let Some(local) = refname.strip_prefix("refs/heads/") {
+        return Some(local.to_string());
+    }
+    let remote = refname.strip_prefix("refs/remotes/")?;
+    let (_, branch) = remote.split_once('/')?;
+    (branch != "HEAD").then(|| branch.to_string())
+}
+
+/// Classifies `commit` with the configured rules (see [`Attributor`]).
+/// Commits matching no override or rule are attributed to the human.
+pub fn classify_actor(
+    repo_root: &Path,
+    commit: &str,
+    config: &AppConfig,
+    state: &State,
+) -> Result<Actor> {
+    let attributor = Attributor::new(&config.attribution)?;
+    let git = open_backend(repo_root, &config.git)?;
+    Ok(attributor.classify(git.as_ref(), commit, state)?.actor)
+}
+
+/// Lower-cased email of the person a commit counts toward for per-author gates:
+/// the author, or the committer when the author is a configured Codex bot.
+pub fn owner_email(author: &str, committer: &str, config: &AppConfig) -> String {
+    let author = author.trim().to_lowercase();
+    let committer = committer.trim().to_lowercase();
+    let author_is_bot = config
+        .attribution
+        .codex_author_emails
+        .iter()
+        .any(|candidate| candidate.to_lowercase() == author);
+    if author_is_bot && !committer.is_empty() {
+        return committer;
+    }
+    author
+}
+
+/// Sums the added lines of `commit`, skipping binary files.
+pub fn commit_added_lines(git: &dyn GitBackend, commit: &str) -> Result<u64> {
+    let record = git
+        .commit_records(&[commit.to_string()])?
+        .pop()
+        .with_context(|| format!("commit not found: {commit}"))?;
+    Ok(record.added_lines())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::history::load_commit_records;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn configured_rules_fire_in_order_before_builtins() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-rules")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        let copilot = commit_file(
+            root,
+            "src/lib.rs",
+            "fn a() {}\n",
+            "feat: a\n\nCo-Authored-By: GitHub Copilot <copilot@github.com>",
+        )?;
+        let generated = commit_file(root, "gen/api.rs", "// generated\n", "regen api")?;
+        let codex = commit_file(
+            root,
+            "src/lib.rs",
+            "fn b() {}\n",
+            "feat: b\n\nCo-Authored-By: Codex <codex@openai.com>",
+        )?;
+        let plain = commit_file(root, "README.md", "hi\n", "docs: readme")?;
+        let body_line = commit_file(
+            root,
+            "src/lib.rs",
+            "fn c() {}\n",
+            "feat: c\n\nCo-authored-by: Codex <codex@openai.com>\nwrote most of the parser",
+        )?;
+
+        let config = AttributionConfig {
+            rules: vec![
+                AttributionRule {
+                    name: "copilot".to_string(),
+                    actor: Actor::Mixed,
+                    trailer: Some("(?i)^co-authored-by: .*copilot".to_string()),
+                    ..AttributionRule::default()
+                },
+                AttributionRule {
+                    actor: Actor::Ai,
+                    paths: vec!["gen/**".to_string()],
+                    ..AttributionRule::default()
+                },
+            ],
+            ..AttributionConfig::default()
+        };
+        let attributor = Attributor::new(&config)?;
+        let state = State::default();
+        let classify = |commit: &str| attributor.classify(&git, commit, &state);
+
+        let first = classify(&copilot)?;
+        assert_eq!(first.actor, Actor::Mixed);
+        assert!(
+            matches!(first.source, AttributionSource::Rule { ref name, .. } if name == "copilot")
+        );
+        let second = classify(&generated)?;
+        assert_eq!(second.actor, Actor::Ai);
+        assert!(
+            matches!(second.source, AttributionSource::Rule { ref name, .. } if name == "rules[1]")
+        );
+        let third = classify(&codex)?;
+        assert!(
+            matches!(third.source, AttributionSource::Rule { ref name, .. } if name == "builtin:codex-trailer")
+        );
+        assert_eq!(classify(&plain)?.source, AttributionSource::Default);
+        // Not a trailer block, but the co-author line still counts, as it always has.
+        let fourth = classify(&body_line)?;
+        assert_eq!(fourth.actor, Actor::Ai);
+        assert!(
+            matches!(fourth.source, AttributionSource::Rule { ref name, .. } if name == "builtin:codex-trailer")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn share_trailer_and_note_take_precedence_over_rules() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-share")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        let trailer = commit_file(
+            root,
+            "a.rs",
+            "a\n",
+            "feat: a\n\nCo-Authored-By: Codex <codex@openai.com>\nSPP-AI-Share: 0.4",
+        )?;
+        let noted = commit_file(root, "b.rs", "b\n", "feat: b")?;
+        git_output(root, &["notes", "add", "-m", "SPP-AI-Share: 25%", &noted])?;
+        let bad = commit_file(
+            root,
+            "c.rs",
+            "c\n",
+            "feat: c\n\nCo-Authored-By: Codex <codex@openai.com>\nSPP-AI-Share: most",
+        )?;
+
+        let attributor = Attributor::new(&AttributionConfig::default())?;
+        let mut state = State::default();
+
+        let from_trailer = attributor.classify(&git, &trailer, &state)?;
+        assert_eq!(from_trailer.source, AttributionSource::ShareTrailer);
+        assert_eq!(from_trailer.actor, Actor::Mixed);
+        assert_eq!(from_trailer.ai_share, 0.4);
+        let from_note = attributor.classify(&git, &noted, &state)?;
+        assert_eq!(from_note.source, AttributionSource::ShareNote);
+        assert_eq!(from_note.ai_share, 0.25);
+        let malformed = attributor.classify(&git, &bad, &state)?;
+        assert_eq!(malformed.actor, Actor::Ai);
+        assert!(matches!(
+            malformed.source,
+            AttributionSource::Rule { ref name, .. } if name == "builtin:codex-trailer"
+        ));
+        assert_eq!(
+            malformed.notes,
+            vec![format!(
+                "ignored invalid SPP-AI-Share trailer in commit {}: invalid AI share `most`",
+                &bad[..10]
+            )]
+        );
+
+        state
+            .attribution_overrides
+            .insert(bad.clone(), AttributionOverride::from_ai_share(0.1));
+        assert_eq!(attributor.classify(&git, &bad, &state)?.ai_share, 0.1);
+        Ok(())
+    }
+
+    #[test]
+    fn classify_loaded_agrees_with_classify() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-loaded")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        let config = AttributionConfig {
+            rules: vec![AttributionRule {
+                actor: Actor::Ai,
+                paths: vec!["gen/**".to_string()],
+                ..AttributionRule::default()
+            }],
+            ..AttributionConfig::default()
+        };
+        let commits = vec![
+            commit_file(root, "a.rs", "a\n", "feat: a\n\nSPP-AI-Share: 0.4")?,
+            commit_file(root, "b.rs", "b\n", "feat: b")?,
+            commit_file(root, "gen/c.rs", "c\n", "chore: regenerate")?,
+            commit_file(root, "d.rs", "d\n", "feat: d")?,
+            commit_file(root, "e.rs", "e\n", "feat: e")?,
+        ];
+        git_output(root, &["notes", "add", "-m", "spp:ai", &commits[1]])?;
+        write_override_note(
+            &git,
+            &config.notes_ref,
+            &commits[3],
+            &AttributionOverride::from_ai_share(0.7),
+        )?;
+
+        let attributor = Attributor::new(&config)?;
+        let state = State::default();
+        let records = load_commit_records(&git, &commits)?;
+        let shared = git.commit_notes(Some(&config.notes_ref), &commits)?;
+        let notes = git.commit_notes(None, &commits)?;
+        for commit in &commits {
+            let loaded = LoadedCommit {
+                record: &records[commit],
+                shared_note: shared.get(commit).map(String::as_str),
+                notes: notes.get(commit).map(String::as_str),
+                patch_id: None,
+            };
+            assert_eq!(
+                attributor.classify_loaded(&git, &loaded, &state)?,
+                attributor.classify(&git, commit, &state)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shared_notes_override_applies_after_local_overrides() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-notes")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        let commit = commit_file(root, "a.rs", "a\n", "feat: a")?;
+        let config = AttributionConfig::default();
+        write_override_note(
+            &git,
+            &config.notes_ref,
+            &commit,
+            &AttributionOverride::from_ai_share(0.7),
+        )?;
+
+        let attributor = Attributor::new(&config)?;
+        let mut state = State::default();
+        let shared = attributor.classify(&git, &commit, &state)?;
+        assert_eq!(shared.ai_share, 0.7);
+        assert!(matches!(
+            shared.source,
+            AttributionSource::SharedOverride { .. }
+        ));
+
+        state.attribution_overrides.insert(
+            commit.clone(),
+            AttributionOverride::from_actor(Actor::Human),
+        );
+        let local = attributor.classify(&git, &commit, &state)?;
+        assert_eq!(local.source, AttributionSource::Override);
+        assert_eq!(local.actor, Actor::Human);
+        Ok(())
+    }
+
+    #[test]
+    fn rewritten_commits_inherit_overrides_and_orphans_are_reported() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-rewrite")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        commit_file(root, "base", "base\n", "base")?;
+        git_output(root, &["checkout", "-q", "-b", "topic"])?;
+        let original = commit_file(root, "feature", "feature\n", "feature\n\nChange-Id: I123")?;
+
+        let config = AttributionConfig::default();
+        let fingerprint = commit_fingerprint(&git, &original, &config.change_id_trailers)?;
+        assert_eq!(fingerprint.change_ids, vec!["I123".to_string()]);
+        let mut state = State::default();
+        state.attribution_overrides.insert(
+            original.clone(),
+            AttributionOverride {
+                patch_id: fingerprint.patch_id,
+                change_ids: fingerprint.change_ids,
+                ..AttributionOverride::from_ai_share(0.8)
+            },
+        );
+
+        git_output(root, &["checkout", "-q", "main"])?;
+        commit_file(root, "other", "other\n", "other")?;
+        git_output(root, &["cherry-pick", &original])?;
+        git_output(root, &["branch", "-q", "-D", "topic"])?;
+        let rebased = git_output(root, &["rev-parse", "HEAD"])?.trim().to_string();
+
+        let attributor = Attributor::new(&config)?;
+        let inherited = attributor.classify(&git, &rebased, &state)?;
+        assert!(attributor.matches_patch_ids(&state));
+        let commits = vec![original.clone(), rebased.clone()];
+        let patch_ids = git.patch_ids(&commits)?;
+        assert_eq!(patch_ids[&original], patch_ids[&rebased]);
+        let records = load_commit_records(&git, &commits)?;
+        let loaded = LoadedCommit {
+            record: &records[&rebased],
+            shared_note: None,
+            notes: None,
+            patch_id: patch_ids.get(&rebased).map(String::as_str),
+        };
+        assert_eq!(
+            attributor.classify_loaded(&git, &loaded, &state)?,
+            inherited
+        );
+        assert_eq!(inherited.ai_share, 0.8);
+        assert_eq!(
+            inherited.source,
+            AttributionSource::InheritedOverride {
+                from: vec![original.clone()],
+                via: "patch-id".to_string(),
+            }
+        );
+
+        let orphans = orphaned_overrides(&git, &config, &state, 30)?;
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].commit, original);
+        assert_eq!(orphans[0].successor.as_deref(), Some(rebased.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn store_override_fingerprints_local_entries_and_notes_replace_them() -> Result<()> {
+        let repo = init_git_repo("spp-attrib-store")?;
+        let root = repo.path();
+        let commit = commit_file(root, "a.txt", "a\n", "feat: a\n\nChange-Id: I42")?;
+        let git = SubprocessBackend::new(root);
+        let config = AttributionConfig::default();
+        let mut state = State::default();
+
+        let local = store_override(
+            &git,
+            &config,
+            &mut state,
+            false,
+            &commit,
+            AttributionOverride::from_ai_share(0.5),
+        )?;
+        assert!(local.patch_id.is_some());
+        assert_eq!(local.change_ids, vec!["I42"]);
+        assert_eq!(state.attribution_overrides[&commit], local);
+
+        let shared = store_override(
+            &git,
+            &config,
+            &mut state,
+            true,
+            &commit,
+            AttributionOverride::from_actor(Actor::Ai),
+        )?;
+        assert!(state.attribution_overrides.is_empty());
+        let note = git.commit_notes(Some(&config.notes_ref), std::slice::from_ref(&commit))?;
+        assert_eq!(parse_override_note(&note[&commit])?, Some(shared));
+        Ok(())
+    }
+
+    #[test]
+    fn override_notes_round_trip() -> Result<()> {
+        for entry in [
+            AttributionOverride::from_actor(Actor::Ai),
+            AttributionOverride::from_ai_share(0.25),
+        ] {
+            let parsed = parse_override_note(&format_override_note(&entry))?;
+            assert_eq!(parsed, Some(entry));
+        }
+        assert_eq!(parse_override_note("unrelated note")?, None);
+        assert!(parse_override_note("SPP-Actor: robot").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn override_notes_sync_between_clones_and_remote_wins_conflicts() -> Result<()> {
+        let upstream = init_git_repo("spp-notes-upstream")?;
+        let clone = init_git_repo("spp-notes-clone")?;
+        let (up, local) = (upstream.path(), clone.path());
+        let commit = commit_file(up, "a.rs", "a\n", "feat: a")?;
+        let up_path = up.to_string_lossy();
+        git_output(local, &["remote", "add", "origin", up_path.as_ref()])?;
+        git_output(local, &["pull", "-q", "origin", "main"])?;
+
+        let notes_ref = AttributionConfig::default().notes_ref;
+        let note_in = |root: &Path| -> Result<Option<AttributionOverride>> {
+            let notes = SubprocessBackend::new(root)
+                .commit_notes(Some(&notes_ref), std::slice::from_ref(&commit))?;
+            notes
+                .get(&commit)
+                .map_or(Ok(None), |note| parse_override_note(note))
+        };
+        assert!(!pull_override_notes(local, &notes_ref, "origin")?);
+
+        write_override_note(
+            &SubprocessBackend::new(up),
+            &notes_ref,
+            &commit,
+            &AttributionOverride::from_actor(Actor::Ai),
+        )?;
+        assert!(pull_override_notes(local, &notes_ref, "origin")?);
+        assert_eq!(
+            note_in(local)?,
+            Some(AttributionOverride::from_actor(Actor::Ai))
+        );
+
+        // Both sides change the same note: the pulled (remote) one is kept.
+        write_override_note(
+            &SubprocessBackend::new(up),
+            &notes_ref,
+            &commit,
+            &AttributionOverride::from_ai_share(0.25),
+        )?;
+        write_override_note(
+            &SubprocessBackend::new(local),
+            &notes_ref,
+            &commit,
+            &AttributionOverride::from_actor(Actor::Human),
+        )?;
+        assert!(push_override_notes(local, &notes_ref, "origin").is_err());
+        assert!(pull_override_notes(local, &notes_ref, "origin")?);
+        assert_eq!(
+            note_in(local)?,
+            Some(AttributionOverride::from_ai_share(0.25))
+        );
+
+        write_override_note(
+            &SubprocessBackend::new(local),
+            &notes_ref,
+            &commit,
+            &AttributionOverride::from_ai_share(0.6),
+        )?;
+        push_override_notes(local, &notes_ref, "origin")?;
+        assert_eq!(note_in(up)?, Some(AttributionOverride::from_ai_share(0.6)));
+        Ok(())
+    }
+
+    #[test]
+    fn branch_name_strips_local_and_remote_prefixes() {
+        assert_eq!(
+            branch_name("refs/heads/codex/fix"),
+            Some("codex/fix".to_string())
+        );
+        assert_eq!(
+            branch_name("refs/remotes/origin/feature/x"),
+            Some("feature/x".to_string())
+        );
+        assert_eq!(branch_name("refs/remotes/origin/HEAD"), None);
+    }
+
+    #[test]
+    fn attributor_rejects_rules_without_matchers() {
+        let config = AttributionConfig {
+            rules: vec![AttributionRule {
+                name: "empty".to_string(),
+                actor: Actor::Ai,
+                ..AttributionRule::default()
+            }],
+            ..AttributionConfig::default()
+        };
+        let err = Attributor::new(&config).expect_err("rule without matchers");
+        assert!(err.to_string().contains("`empty` has no matchers"));
+    }
+}
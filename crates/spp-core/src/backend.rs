@@ -0,0 +1,492 @@
+//! Read access to the repository behind a swappable implementation (`[git].backend`).
+//!
+//! Gate metrics, commit attribution and session logging read git through
+//! [`GitBackend`]. [`SubprocessBackend`] runs the `git` executable and is the
+//! default; with the `libgit2` cargo feature, `backend = "libgit2"` links
+//! libgit2 instead so those paths work where no `git` binary is installed.
+//! Commands that write to the repository or talk to remotes still run `git`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::config::{GitBackendKind, GitConfig};
+use crate::git::{git_output, git_output_with_stdin};
+use crate::history::CommitRecord;
+use crate::infer::added_diff_lines;
+use crate::metrics::{parse_numstat_z, FileChange};
+
+#[cfg(feature = "libgit2")]
+mod libgit2;
+
+#[cfg(feature = "libgit2")]
+pub use self::libgit2::Libgit2Backend;
+
+/// Where a history walk starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revisions {
+    /// The checked-out `HEAD`.
+    Head,
+    /// `HEAD` plus every ref except notes and the stash.
+    All,
+    /// The listed revisions.
+    Refs(Vec<String>),
+}
+
+/// Author and committer timestamps of one commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitTimes {
+    pub commit: String,
+    pub authored_at: DateTime<Utc>,
+    pub committed_at: DateTime<Utc>,
+}
+
+/// The repository queries spp needs to evaluate the gate and attribute commits.
+pub trait GitBackend {
+    /// Top-level directory of the work tree; runtime files live below it.
+    fn repo_root(&self) -> &Path;
+
+    /// Checked-out branch name, `HEAD` when detached, `"unknown"` when unresolvable.
+    fn current_branch(&self) -> String;
+
+    /// Full hash of `HEAD`, if any commit exists.
+    fn current_commit(&self) -> Option<String>;
+
+    /// Configured `user.email`, if set.
+    fn user_email(&self) -> Option<String>;
+
+    /// Full hash of the commit `rev` points at.
+    fn resolve_commit(&self, rev: &str) -> Result<String>;
+
+    /// Short name of the branch `refs/remotes/origin/HEAD` points at, if set.
+    fn origin_head(&self) -> Option<String>;
+
+    /// Non-merge commits reachable from `revisions` committed at or after
+    /// `since`, newest first.
+    fn commit_times(&self, revisions: &Revisions, since: DateTime<Utc>)
+        -> Result<Vec<CommitTimes>>;
+
+    /// Metadata and per-file stats of `commits`; unknown commits are left out.
+    fn commit_records(&self, commits: &[String]) -> Result<Vec<CommitRecord>>;
+
+    /// Note text attached to each of `commits` in `notes_ref` (the default
+    /// notes ref when `None`); commits without a note are absent.
+    fn commit_notes(
+        &self,
+        notes_ref: Option<&str>,
+        commits: &[String],
+    ) -> Result<HashMap<String, String>>;
+
+    /// Sets the note on `commit` in `notes_ref` to `note`, replacing any previous one.
+    fn write_note(&self, notes_ref: &str, commit: &str, note: &str) -> Result<()>;
+
+    /// Full names of the local and remote-tracking branches containing `commit`.
+    fn branches_containing(&self, commit: &str) -> Result<Vec<String>>;
+
+    /// Full message of `commit`.
+    fn commit_message(&self, commit: &str) -> Result<String>;
+
+    /// Non-merge commits of `range` (`from..to`, an empty side meaning `HEAD`),
+    /// oldest first.
+    fn range_commits(&self, range: &str) -> Result<Vec<String>>;
+
+    /// Added lines of `commit`'s diff against its first parent.
+    fn commit_diff_lines(&self, commit: &str) -> Result<Vec<String>>;
+
+    /// Whether any branch, remote-tracking branch or tag contains `commit`;
+    /// `false` for unknown commits.
+    fn is_reachable(&self, commit: &str) -> bool;
+
+    /// `git patch-id --stable` of `commit`; `None` when it has no diff.
+    fn patch_id(&self, commit: &str) -> Result<Option<String>>;
+
+    /// [`GitBackend::patch_id`] of each of `commits`; commits without a diff are absent.
+    fn patch_ids(&self, commits: &[String]) -> Result<HashMap<String, String>> {
+        let mut ids = HashMap::new();
+        for commit in commits {
+            if let Some(patch_id) = self.patch_id(commit)? {
+                ids.insert(commit.clone(), patch_id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Per-file stats of staged and unstaged changes to tracked files against `HEAD`.
+    fn worktree_file_changes(&self) -> Result<Vec<FileChange>>;
+
+    /// Added lines of staged and unstaged changes to tracked files against `HEAD`.
+    fn worktree_added_lines(&self) -> Result<Vec<String>>;
+
+    /// Which of `paths` are marked `linguist-generated` or `-diff` in `.gitattributes`.
+    fn generated_paths(&self, paths: &[&str]) -> Result<Vec<String>>;
+
+    /// Full hashes for `rev`: a single commit, or every non-merge commit of a
+    /// range such as `main..HEAD` / `HEAD~5..`, oldest first.
+    fn resolve_commits(&self, rev: &str) -> Result<Vec<String>> {
+        if !rev.contains("..") {
+            return Ok(vec![self.resolve_commit(rev)?]);
+        }
+        let commits = self.range_commits(rev)?;
+        if commits.is_empty() {
+            bail!("no commits in range {rev}");
+        }
+        Ok(commits)
+    }
+
+    /// The repository's default branch: the target of `origin/HEAD`, else a
+    /// local `main` or `master`, else `origin/main` or `origin/master`.
+    fn default_branch(&self) -> Result<String> {
+        if let Some(name) = self.origin_head() {
+            return Ok(name);
+        }
+        for candidate in ["main", "master", "origin/main", "origin/master"] {
+            if self.resolve_commit(candidate).is_ok() {
+                return Ok(candidate.to_string());
+            }
+        }
+        bail!("cannot determine the default branch; set `[gate].scope` to a list of refs")
+    }
+}
+
+/// Opens the configured backend for the repository at `repo_root`.
+pub fn open_backend(repo_root: &Path, config: &GitConfig) -> Result<Box<dyn GitBackend>> {
+    match config.backend {
+        GitBackendKind::Subprocess => Ok(Box::new(SubprocessBackend::new(repo_root))),
+        #[cfg(feature = "libgit2")]
+        GitBackendKind::Libgit2 => Ok(Box::new(Libgit2Backend::open(repo_root)?)),
+        #[cfg(not(feature = "libgit2"))]
+        GitBackendKind::Libgit2 => {
+            bail!("`[git].backend = \"libgit2\"` needs spp built with `--features libgit2`")
+        }
+    }
+}
+
+/// Answers every query by running the `git` executable.
+#[derive(Debug, Clone)]
+pub struct SubprocessBackend {
+    repo_root: PathBuf,
+}
+
+impl SubprocessBackend {
+    pub fn new(repo_root: &Path) -> Self {
+        Self {
+            repo_root: repo_root.to_path_buf(),
+        }
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        git_output(&self.repo_root, args)
+    }
+}
+
+impl GitBackend for SubprocessBackend {
+    fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    fn current_branch(&self) -> String {
+        crate::git::current_branch(&self.repo_root)
+    }
+
+    fn current_commit(&self) -> Option<String> {
+        crate::git::current_commit(&self.repo_root)
+    }
+
+    fn user_email(&self) -> Option<String> {
+        crate::git::current_user_email(&self.repo_root)
+    }
+
+    fn resolve_commit(&self, rev: &str) -> Result<String> {
+        crate::git::resolve_commit(&self.repo_root, rev)
+    }
+
+    fn origin_head(&self) -> Option<String> {
+        let out = self
+            .git(&[
+                "symbolic-ref",
+                "--quiet",
+                "--short",
+                "refs/remotes/origin/HEAD",
+            ])
+            .ok()?;
+        let name = out.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    fn commit_times(
+        &self,
+        revisions: &Revisions,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<CommitTimes>> {
+        let since = since.to_rfc3339();
+        let mut args = vec![
+            "log",
+            "--since",
+            &since,
+            "--no-merges",
+            "--pretty=format:%H %at %ct",
+        ];
+        match revisions {
+            Revisions::Head => {}
+            Revisions::All => args.extend([
+                "--exclude=refs/notes/*",
+                "--exclude=refs/remote-notes/*",
+                "--exclude=refs/stash",
+                "--all",
+            ]),
+            Revisions::Refs(refs) => args.extend(refs.iter().map(String::as_str)),
+        }
+        args.push("--");
+        let out = self.git(&args)?;
+
+        let mut times = Vec::new();
+        for line in out.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let mut fields = line.split(' ');
+            let commit = fields.next().unwrap_or_default().to_string();
+            let mut timestamp = || {
+                fields
+                    .next()
+                    .and_then(|raw| raw.parse::<i64>().ok())
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                    .with_context(|| format!("failed to read timestamps of {commit}"))
+            };
+            let authored_at = timestamp()?;
+            let committed_at = timestamp()?;
+            times.push(CommitTimes {
+                commit,
+                authored_at,
+                committed_at,
+            });
+        }
+        Ok(times)
+    }
+
+    fn commit_records(&self, commits: &[String]) -> Result<Vec<CommitRecord>> {
+        if commits.is_empty() {
+            return Ok(Vec::new());
+        }
+        let out = git_output_with_stdin(
+            &self.repo_root,
+            &[
+                "log",
+                "--no-walk=unsorted",
+                "--stdin",
+                "--numstat",
+                "-z",
+                "--format=%x1e%H%x1f%ae%x1f%ce%x1f%s%x1f%B%x1f%(trailers:only,unfold)%x1f",
+            ],
+            &(commits.join("\n") + "\n"),
+        )?;
+        Ok(parse_log_records(&out))
+    }
+
+    fn commit_notes(
+        &self,
+        notes_ref: Option<&str>,
+        commits: &[String],
+    ) -> Result<HashMap<String, String>> {
+        if commits.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let notes_arg = match notes_ref {
+            Some(notes_ref) => format!("--notes={notes_ref}"),
+            None => "--notes".to_string(),
+        };
+        let out = git_output_with_stdin(
+            &self.repo_root,
+            &[
+                "log",
+                "--no-walk=unsorted",
+                "--stdin",
+                &notes_arg,
+                "--format=%x1e%H%x1f%N",
+            ],
+            &(commits.join("\n") + "\n"),
+        )?;
+        Ok(out
+            .split('\x1e')
+            .filter_map(|chunk| {
+                let (commit, note) = chunk.split_once('\x1f')?;
+                let note = note.trim();
+                (!note.is_empty()).then(|| (commit.trim().to_string(), note.to_string()))
+            })
+            .collect())
+    }
+
+    fn write_note(&self, notes_ref: &str, commit: &str, note: &str) -> Result<()> {
+        self.git(&["notes", "--ref", notes_ref, "add", "-f", "-m", note, commit])?;
+        Ok(())
+    }
+
+    fn branches_containing(&self, commit: &str) -> Result<Vec<String>> {
+        let out = self.git(&[
+            "for-each-ref",
+            "--contains",
+            commit,
+            "--format=%(refname)",
+            "refs/heads",
+            "refs/remotes",
+        ])?;
+        Ok(out
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn commit_message(&self, commit: &str) -> Result<String> {
+        self.git(&["show", "-s", "--format=%B", commit])
+    }
+
+    fn range_commits(&self, range: &str) -> Result<Vec<String>> {
+        let out = self.git(&["rev-list", "--reverse", "--no-merges", range])?;
+        Ok(out
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn commit_diff_lines(&self, commit: &str) -> Result<Vec<String>> {
+        let diff = self.git(&[
+            "show",
+            "--format=",
+            "--no-color",
+            "--no-ext-diff",
+            "--unified=0",
+            commit,
+        ])?;
+        Ok(added_diff_lines(&diff).map(str::to_string).collect())
+    }
+
+    fn is_reachable(&self, commit: &str) -> bool {
+        self.git(&[
+            "for-each-ref",
+            "--count=1",
+            "--contains",
+            commit,
+            "--format=%(refname)",
+            "refs/heads",
+            "refs/remotes",
+            "refs/tags",
+        ])
+        .is_ok_and(|out| !out.trim().is_empty())
+    }
+
+    fn patch_id(&self, commit: &str) -> Result<Option<String>> {
+        Ok(crate::git::patch_ids(&self.repo_root, &["-1", commit])?
+            .into_iter()
+            .next()
+            .map(|(patch_id, _)| patch_id))
+    }
+
+    fn patch_ids(&self, commits: &[String]) -> Result<HashMap<String, String>> {
+        Ok(crate::git::patch_ids_of(&self.repo_root, commits)?
+            .into_iter()
+            .map(|(patch_id, commit)| (commit, patch_id))
+            .collect())
+    }
+
+    fn worktree_file_changes(&self) -> Result<Vec<FileChange>> {
+        let out = self.git(&["diff", "--numstat", "-z", "HEAD"])?;
+        Ok(parse_numstat_z(&out))
+    }
+
+    fn worktree_added_lines(&self) -> Result<Vec<String>> {
+        let diff = self.git(&["diff", "--no-color", "--no-ext-diff", "--unified=0", "HEAD"])?;
+        Ok(added_diff_lines(&diff).map(str::to_string).collect())
+    }
+
+    fn generated_paths(&self, paths: &[&str]) -> Result<Vec<String>> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let out = git_output_with_stdin(
+            &self.repo_root,
+            &["check-attr", "--stdin", "-z", "linguist-generated", "diff"],
+            &(paths.join("\0") + "\0"),
+        )?;
+        let fields: Vec<&str> = out.split('\0').collect();
+        let mut generated: Vec<String> = fields
+            .chunks_exact(3)
+            .filter(|record| is_generated_attr(record[1], record[2]))
+            .map(|record| record[0].to_string())
+            .collect();
+        generated.dedup();
+        Ok(generated)
+    }
+}
+
+/// Whether `attr` set to `value` (as printed by `git check-attr`) marks a
+/// file as generated.
+fn is_generated_attr(attr: &str, value: &str) -> bool {
+    match attr {
+        "linguist-generated" => value == "set" || value == "true",
+        "diff" => value == "unset",
+        _ => false,
+    }
+}
+
+fn parse_log_records(out: &str) -> Vec<CommitRecord> {
+    out.split('\x1e')
+        .filter_map(|chunk| {
+            let mut fields = chunk.splitn(7, '\x1f');
+            let commit = fields.next()?.trim().to_string();
+            if commit.is_empty() {
+                return None;
+            }
+            let mut next = || fields.next().unwrap_or_default();
+            let author_email = next().trim().to_string();
+            let committer_email = next().trim().to_string();
+            let subject = next().trim().to_string();
+            let message = next().to_string();
+            let trailers = next()
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect();
+            let files = parse_numstat_z(next());
+            Some(CommitRecord {
+                commit,
+                author_email,
+                committer_email,
+                subject,
+                message,
+                trailers,
+                files,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn resolve_commits_expands_ranges_oldest_first() -> Result<()> {
+        let repo = init_git_repo("spp-backend-range")?;
+        let root = repo.path();
+        let git = SubprocessBackend::new(root);
+        let first = commit_file(root, "a", "1\n", "one")?;
+        let second = commit_file(root, "a", "2\n", "two")?;
+        let third = commit_file(root, "a", "3\n", "three")?;
+
+        assert_eq!(git.resolve_commits("HEAD~1")?, vec![second.clone()]);
+        assert_eq!(
+            git.resolve_commits(&format!("{first}..HEAD"))?,
+            vec![second, third]
+        );
+        assert!(git.resolve_commits("HEAD..HEAD").is_err());
+        assert!(git.is_reachable(&first));
+        assert!(!git.is_reachable(&"0".repeat(40)));
+        assert_eq!(git.commit_diff_lines(&first)?, vec!["1".to_string()]);
+        Ok(())
+    }
+}
@@ -0,0 +1,577 @@
+//! [`GitBackend`] on libgit2 (`--features libgit2`), for hosts without a `git` binary.
+
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{
+    AttrCheckFlags, AttrValue, Diff, DiffFindOptions, DiffFormat, DiffOptions, Oid, Patch,
+    Repository, Sort,
+};
+
+use super::{CommitTimes, GitBackend, Revisions};
+use crate::history::CommitRecord;
+use crate::metrics::FileChange;
+
+/// Consecutive commits older than `since` after which [`GitBackend::commit_times`]
+/// stops walking the time-sorted history, like `git log --since` does after a few.
+const SINCE_SKEW_COMMITS: usize = 5;
+
+/// Answers every query in-process through libgit2.
+pub struct Libgit2Backend {
+    repo_root: PathBuf,
+    repo: Repository,
+}
+
+impl Libgit2Backend {
+    /// Opens the repository whose work tree is `repo_root`.
+    pub fn open(repo_root: &Path) -> Result<Self> {
+        let repo = Repository::open(repo_root)
+            .with_context(|| format!("failed to open git repository {}", repo_root.display()))?;
+        Ok(Self {
+            repo_root: repo_root.to_path_buf(),
+            repo,
+        })
+    }
+
+    /// Top-level work tree directory of the repository containing `dir`.
+    pub fn discover_root(dir: &Path) -> Result<PathBuf> {
+        let repo = Repository::discover(dir)
+            .map_err(|_| anyhow::anyhow!("current directory is not a git repository"))?;
+        match repo.workdir() {
+            Some(workdir) => Ok(workdir
+                .to_path_buf()
+                .canonicalize()
+                .unwrap_or_else(|_| workdir.to_path_buf())),
+            None => bail!("failed to resolve git repo root: repository is bare"),
+        }
+    }
+
+    fn find_commit(&self, commit: &str) -> Result<git2::Commit<'_>> {
+        let oid = Oid::from_str(commit).with_context(|| format!("invalid commit id {commit}"))?;
+        self.repo
+            .find_commit(oid)
+            .with_context(|| format!("commit not found: {commit}"))
+    }
+
+    /// Diff of `commit` against its first parent, with renames detected as `git show` does.
+    fn commit_diff(&self, commit: &git2::Commit<'_>) -> Result<Diff<'_>> {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let mut diff =
+            self.repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        Ok(diff)
+    }
+
+    /// Diff of `HEAD` against the index and work tree (`git diff HEAD`).
+    fn worktree_diff(&self, context_lines: Option<u32>) -> Result<Diff<'_>> {
+        let head = self.repo.head()?.peel_to_tree()?;
+        let mut options = DiffOptions::new();
+        if let Some(lines) = context_lines {
+            options.context_lines(lines);
+        }
+        let mut diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(Some(&head), Some(&mut options))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        Ok(diff)
+    }
+
+    fn notes_ref(&self, notes_ref: Option<&str>) -> Result<String> {
+        Ok(match notes_ref {
+            Some(name) if name.starts_with("refs/") => name.to_string(),
+            Some(name) => format!("refs/notes/{name}"),
+            None => self.repo.note_default_ref()?,
+        })
+    }
+}
+
+/// Per-file stats of `diff` in `--numstat` terms; binary files have no counts.
+fn file_changes(diff: &Diff<'_>) -> Result<Vec<FileChange>> {
+    let mut changes = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(diff, idx)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (added, deleted) = if delta.flags().is_binary() {
+            (None, None)
+        } else {
+            let (_, added, deleted) = patch.line_stats()?;
+            (Some(added as u64), Some(deleted as u64))
+        };
+        changes.push(FileChange {
+            path,
+            added,
+            deleted,
+        });
+    }
+    Ok(changes)
+}
+
+/// `+` lines of `diff`, without line endings.
+fn added_lines(diff: &Diff<'_>) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if line.origin() == '+' {
+            let content = String::from_utf8_lossy(line.content());
+            lines.push(content.trim_end_matches(['\r', '\n']).to_string());
+        }
+        true
+    })?;
+    Ok(lines)
+}
+
+fn timestamp(time: git2::Time) -> Result<DateTime<Utc>> {
+    Utc.timestamp_opt(time.seconds(), 0)
+        .single()
+        .context("commit timestamp out of range")
+}
+
+impl GitBackend for Libgit2Backend {
+    fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    fn current_branch(&self) -> String {
+        match self.repo.head() {
+            Ok(_) if self.repo.head_detached().unwrap_or(false) => "HEAD".to_string(),
+            Ok(head) => head.shorthand().unwrap_or("unknown").to_string(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    fn current_commit(&self) -> Option<String> {
+        let commit = self.repo.head().ok()?.peel_to_commit().ok()?;
+        Some(commit.id().to_string())
+    }
+
+    fn user_email(&self) -> Option<String> {
+        let email = self.repo.config().ok()?.get_string("user.email").ok()?;
+        let email = email.trim();
+        (!email.is_empty()).then(|| email.to_string())
+    }
+
+    fn resolve_commit(&self, rev: &str) -> Result<String> {
+        let commit = self
+            .repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("commit not found: {rev}"))?;
+        Ok(commit.id().to_string())
+    }
+
+    fn origin_head(&self) -> Option<String> {
+        let reference = self.repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+        let target = reference.symbolic_target()?;
+        Some(
+            target
+                .strip_prefix("refs/remotes/")
+                .unwrap_or(target)
+                .to_string(),
+        )
+    }
+
+    fn commit_times(
+        &self,
+        revisions: &Revisions,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<CommitTimes>> {
+        let mut tips = Vec::new();
+        match revisions {
+            Revisions::Head => tips.push(self.repo.head()?.peel_to_commit()?.id()),
+            Revisions::All => {
+                if let Ok(head) = self.repo.head().and_then(|head| head.peel_to_commit()) {
+                    tips.push(head.id());
+                }
+                for reference in self.repo.references()? {
+                    let reference = reference?;
+                    let name = reference.name().unwrap_or_default();
+                    if name.starts_with("refs/notes/")
+                        || name.starts_with("refs/remote-notes/")
+                        || name == "refs/stash"
+                    {
+                        continue;
+                    }
+                    if let Ok(commit) = reference.peel_to_commit() {
+                        tips.push(commit.id());
+                    }
+                }
+            }
+            Revisions::Refs(refs) => {
+                for name in refs {
+                    tips.push(Oid::from_str(&self.resolve_commit(name)?)?);
+                }
+            }
+        }
+
+        // Newest committer date first. A sorted libgit2 revwalk would load the
+        // whole history before yielding anything, so walk by hand and stop early.
+        let mut seen = HashSet::new();
+        let mut queue = BinaryHeap::new();
+        for oid in tips {
+            if seen.insert(oid) {
+                queue.push((self.repo.find_commit(oid)?.time().seconds(), oid));
+            }
+        }
+        let mut times = Vec::new();
+        let mut older_in_a_row = 0;
+        while let Some((_, oid)) = queue.pop() {
+            let commit = self.repo.find_commit(oid)?;
+            for parent in commit.parents() {
+                if seen.insert(parent.id()) {
+                    queue.push((parent.time().seconds(), parent.id()));
+                }
+            }
+            let committed_at = timestamp(commit.time())?;
+            if committed_at < since {
+                // Tolerate a few out-of-order commit dates before treating the
+                // rest of the history as older.
+                older_in_a_row += 1;
+                if older_in_a_row > SINCE_SKEW_COMMITS {
+                    break;
+                }
+                continue;
+            }
+            older_in_a_row = 0;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            times.push(CommitTimes {
+                commit: commit.id().to_string(),
+                authored_at: timestamp(commit.author().when())?,
+                committed_at,
+            });
+        }
+        Ok(times)
+    }
+
+    fn commit_records(&self, commits: &[String]) -> Result<Vec<CommitRecord>> {
+        let mut records = Vec::new();
+        for id in commits {
+            let Ok(commit) = self.find_commit(id) else {
+                continue;
+            };
+            let message = String::from_utf8_lossy(commit.message_bytes()).into_owned();
+            let trailers = git2::message_trailers_strs(&message)
+                .map(|trailers| {
+                    trailers
+                        .iter()
+                        .map(|(key, value)| format!("{key}: {value}"))
+                        .collect()
+                })
+                .unwrap_or_default();
+            records.push(CommitRecord {
+                commit: commit.id().to_string(),
+                author_email: commit.author().email().unwrap_or_default().to_string(),
+                committer_email: commit.committer().email().unwrap_or_default().to_string(),
+                subject: commit.summary().unwrap_or_default().trim().to_string(),
+                message: message.clone(),
+                trailers,
+                files: file_changes(&self.commit_diff(&commit)?)?,
+            });
+        }
+        Ok(records)
+    }
+
+    fn commit_notes(
+        &self,
+        notes_ref: Option<&str>,
+        commits: &[String],
+    ) -> Result<HashMap<String, String>> {
+        let notes_ref = self.notes_ref(notes_ref)?;
+        let mut notes = HashMap::new();
+        for commit in commits {
+            let Ok(oid) = Oid::from_str(commit) else {
+                continue;
+            };
+            let Ok(note) = self.repo.find_note(Some(&notes_ref), oid) else {
+                continue;
+            };
+            let text = String::from_utf8_lossy(note.message_bytes())
+                .trim()
+                .to_string();
+            if !text.is_empty() {
+                notes.insert(commit.clone(), text);
+            }
+        }
+        Ok(notes)
+    }
+
+    fn write_note(&self, notes_ref: &str, commit: &str, note: &str) -> Result<()> {
+        let notes_ref = self.notes_ref(Some(notes_ref))?;
+        let target = self.find_commit(commit)?.id();
+        let signature = self
+            .repo
+            .signature()
+            .context("failed to write a note: set user.name and user.email")?;
+        self.repo
+            .note(&signature, &signature, Some(&notes_ref), target, note, true)
+            .with_context(|| format!("failed to write note on {commit} in {notes_ref}"))?;
+        Ok(())
+    }
+
+    fn branches_containing(&self, commit: &str) -> Result<Vec<String>> {
+        let target = self.find_commit(commit)?.id();
+        let mut branches = Vec::new();
+        for glob in ["refs/heads/*", "refs/remotes/*"] {
+            for reference in self.repo.references_glob(glob)? {
+                let reference = reference?;
+                let Ok(tip) = reference.peel_to_commit() else {
+                    continue;
+                };
+                let tip = tip.id();
+                if tip == target || self.repo.graph_descendant_of(tip, target)? {
+                    branches.push(reference.name().unwrap_or_default().to_string());
+                }
+            }
+        }
+        branches.sort();
+        Ok(branches)
+    }
+
+    fn commit_message(&self, commit: &str) -> Result<String> {
+        let commit = self.find_commit(commit)?;
+        Ok(String::from_utf8_lossy(commit.message_bytes()).into_owned())
+    }
+
+    fn range_commits(&self, range: &str) -> Result<Vec<String>> {
+        let Some((from, to)) = range
+            .split_once("..")
+            .filter(|(_, to)| !to.starts_with('.'))
+        else {
+            bail!("unsupported range `{range}` (expected `from..to`)");
+        };
+        let side = |rev: &str| {
+            let rev = if rev.is_empty() { "HEAD" } else { rev };
+            Oid::from_str(&self.resolve_commit(rev)?).map_err(anyhow::Error::from)
+        };
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME | Sort::REVERSE)?;
+        walk.push(side(to)?)?;
+        walk.hide(side(from)?)?;
+        let mut commits = Vec::new();
+        for oid in walk {
+            let commit = self.repo.find_commit(oid?)?;
+            if commit.parent_count() <= 1 {
+                commits.push(commit.id().to_string());
+            }
+        }
+        Ok(commits)
+    }
+
+    fn commit_diff_lines(&self, commit: &str) -> Result<Vec<String>> {
+        let commit = self.find_commit(commit)?;
+        added_lines(&self.commit_diff(&commit)?)
+    }
+
+    fn is_reachable(&self, commit: &str) -> bool {
+        let Ok(target) = self.find_commit(commit).map(|commit| commit.id()) else {
+            return false;
+        };
+        let Ok(references) = self.repo.references() else {
+            return false;
+        };
+        references.flatten().any(|reference| {
+            let name = reference.name().unwrap_or_default();
+            let branch_or_tag = ["refs/heads/", "refs/remotes/", "refs/tags/"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+            branch_or_tag
+                && reference.peel_to_commit().is_ok_and(|tip| {
+                    tip.id() == target
+                        || self
+                            .repo
+                            .graph_descendant_of(tip.id(), target)
+                            .unwrap_or(false)
+                })
+        })
+    }
+
+    /// Matches `git patch-id --stable` for text changes; commits touching binary
+    /// files get a different id than from the `git` executable.
+    fn patch_id(&self, commit: &str) -> Result<Option<String>> {
+        let commit = self.find_commit(commit)?;
+        let diff = self.commit_diff(&commit)?;
+        if diff.deltas().len() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(diff.patchid(None)?.to_string()))
+    }
+
+    fn worktree_file_changes(&self) -> Result<Vec<FileChange>> {
+        file_changes(&self.worktree_diff(None)?)
+    }
+
+    fn worktree_added_lines(&self) -> Result<Vec<String>> {
+        added_lines(&self.worktree_diff(Some(0))?)
+    }
+
+    fn generated_paths(&self, paths: &[&str]) -> Result<Vec<String>> {
+        let mut generated = Vec::new();
+        for path in paths {
+            let attr = |name: &str| -> Result<AttrValue<'_>> {
+                let value =
+                    self.repo
+                        .get_attr(Path::new(path), name, AttrCheckFlags::FILE_THEN_INDEX)?;
+                Ok(AttrValue::from_string(value))
+            };
+            let linguist = matches!(
+                attr("linguist-generated")?,
+                AttrValue::True | AttrValue::String("true")
+            );
+            if linguist || matches!(attr("diff")?, AttrValue::False) {
+                generated.push(path.to_string());
+            }
+        }
+        Ok(generated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::git::git_output;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn libgit2_backend_agrees_with_the_git_executable() -> Result<()> {
+        let repo = init_git_repo("spp-libgit2")?;
+        let root = repo.path();
+        std::fs::write(
+            root.join(".gitattributes"),
+            "gen/** linguist-generated\n*.snap -diff\n",
+        )?;
+        git_output(root, &["add", ".gitattributes"])?;
+        let first = commit_file(root, "gen/api.rs", "a\nb\n", "feat: a\n\nSPP-AI-Share: 0.5")?;
+        git_output(root, &["mv", "gen/api.rs", "src.rs"])?;
+        std::fs::write(root.join("ui.snap"), "x\ny\n")?;
+        git_output(root, &["add", "ui.snap"])?;
+        let second = commit_file(root, "d.txt", "d\ne\n", "chore: rename")?;
+        git_output(
+            root,
+            &["notes", "--ref=spp", "add", "-m", "SPP-Actor: ai", &second],
+        )?;
+        git_output(root, &["checkout", "-q", "-b", "topic"])?;
+        std::fs::write(root.join("d.txt"), "d\nchanged\nmore\n")?;
+
+        let cli = SubprocessBackend::new(root);
+        let lib = Libgit2Backend::open(root)?;
+        let commits = vec![second.clone(), first.clone()];
+        let since = Utc::now() - chrono::Duration::days(1);
+
+        assert_eq!(lib.current_branch(), cli.current_branch());
+        assert_eq!(lib.current_commit(), cli.current_commit());
+        assert_eq!(lib.user_email(), cli.user_email());
+        assert_eq!(lib.resolve_commit("HEAD~1")?, cli.resolve_commit("HEAD~1")?);
+        assert_eq!(lib.default_branch()?, "main");
+        for revisions in [Revisions::Head, Revisions::All] {
+            let sorted = |mut times: Vec<CommitTimes>| {
+                times.sort_by(|a, b| a.commit.cmp(&b.commit));
+                times
+            };
+            assert_eq!(
+                sorted(lib.commit_times(&revisions, since)?),
+                sorted(cli.commit_times(&revisions, since)?)
+            );
+        }
+        let sorted_files = |mut records: Vec<CommitRecord>| {
+            for record in &mut records {
+                record.files.sort_by(|a, b| a.path.cmp(&b.path));
+            }
+            records
+        };
+        assert_eq!(
+            sorted_files(lib.commit_records(&commits)?),
+            sorted_files(cli.commit_records(&commits)?)
+        );
+        for notes_ref in [Some("refs/notes/spp"), None] {
+            assert_eq!(
+                lib.commit_notes(notes_ref, &commits)?,
+                cli.commit_notes(notes_ref, &commits)?
+            );
+        }
+        lib.write_note("spp", &first, "SPP-AI-Share: 25%")?;
+        cli.write_note("spp", &second, "SPP-Actor: human")?;
+        assert_eq!(
+            lib.commit_notes(Some("spp"), &commits)?,
+            cli.commit_notes(Some("refs/notes/spp"), &commits)?
+        );
+        assert_eq!(
+            lib.commit_notes(Some("spp"), &commits)?[&first],
+            "SPP-AI-Share: 25%"
+        );
+        assert_eq!(
+            lib.branches_containing(&first)?,
+            cli.branches_containing(&first)?
+        );
+        assert_eq!(
+            lib.commit_message(&first)?.trim_end(),
+            cli.commit_message(&first)?.trim_end()
+        );
+        assert_eq!(lib.patch_id(&first)?, cli.patch_id(&first)?);
+        for range in [format!("{first}..HEAD"), "HEAD~1..".to_string()] {
+            assert_eq!(lib.range_commits(&range)?, cli.range_commits(&range)?);
+        }
+        assert_eq!(
+            lib.commit_diff_lines(&first)?,
+            cli.commit_diff_lines(&first)?
+        );
+        assert_eq!(lib.is_reachable(&first), cli.is_reachable(&first));
+        assert!(!lib.is_reachable(&"0".repeat(40)));
+        assert_eq!(lib.worktree_file_changes()?, cli.worktree_file_changes()?);
+        assert_eq!(lib.worktree_added_lines()?, cli.worktree_added_lines()?);
+        assert_eq!(
+            lib.generated_paths(&["gen/api.rs", "src.rs", "ui.snap"])?,
+            cli.generated_paths(&["gen/api.rs", "src.rs", "ui.snap"])?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn commit_times_stops_after_a_few_commits_older_than_since() -> Result<()> {
+        let repo = init_git_repo("spp-libgit2-since")?;
+        let root = repo.path();
+        let commit_at = |file: &str, date: &str| -> Result<String> {
+            std::fs::write(root.join(file), file)?;
+            git_output(root, &["add", file])?;
+            let status = std::process::Command::new("git")
+                .args(["commit", "-q", "-m", file])
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .current_dir(root)
+                .status()?;
+            anyhow::ensure!(status.success(), "git commit failed");
+            Ok(git_output(root, &["rev-parse", "HEAD"])?.trim().to_string())
+        };
+        let now = Utc::now().to_rfc3339();
+        let behind_skew = commit_at("behind-skew", &now)?;
+        for idx in 0..=SINCE_SKEW_COMMITS {
+            commit_at(&format!("old{idx}"), "2020-01-01T00:00:00Z")?;
+        }
+        let recent = commit_at("recent", &now)?;
+        for idx in 0..SINCE_SKEW_COMMITS {
+            commit_at(&format!("skewed{idx}"), "2020-01-02T00:00:00Z")?;
+        }
+        let newest = commit_at("newest", &now)?;
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let times = Libgit2Backend::open(root)?.commit_times(&Revisions::Head, since)?;
+        let commits: Vec<_> = times.into_iter().map(|time| time.commit).collect();
+        assert_eq!(commits, vec![newest, recent]);
+        assert!(!commits.contains(&behind_skew));
+        Ok(())
+    }
+}
@@ -0,0 +1,690 @@
+//! Codex launch policy.
+//!
+//! Arguments passed through `spp codex` are parsed the way the Codex CLI reads
+//! them (long and short options, attached values, `-c key=value` overrides)
+//! and checked against the options spp always manages plus the per-mode
+//! `allow_*` / `deny_*` lists in `[codex.<mode>]`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::{AppConfig, CodexModeConfig, DEFAULT_DRIVE_INSTRUCTIONS};
+use crate::glob::Glob;
+use crate::state::Mode;
+
+/// Codex CLI options: long name, short alias and whether it takes a value.
+const CODEX_OPTIONS: &[(&str, Option<char>, bool)] = &[
+    ("model", Some('m'), true),
+    ("sandbox", Some('s'), true),
+    ("ask-for-approval", Some('a'), true),
+    ("config", Some('c'), true),
+    ("profile", Some('p'), true),
+    ("cd", Some('C'), true),
+    ("image", Some('i'), true),
+    ("add-dir", None, true),
+    ("full-auto", None, false),
+    ("dangerously-bypass-approvals-and-sandbox", None, false),
+    ("oss", None, false),
+    ("search", None, false),
+];
+
+/// Alternative long names Codex accepts for an option.
+const CODEX_ALIASES: &[(&str, &str)] = &[("yolo", "dangerously-bypass-approvals-and-sandbox")];
+
+/// `-c` keys that set what spp enforces through `--sandbox` / `--ask-for-approval`.
+const MANAGED_CONFIG_KEYS: &[&str] = &["sandbox_mode", "approval_policy"];
+
+/// Codex config key that carries `[codex.<mode>].instructions`.
+pub const INSTRUCTIONS_KEY: &str = "developer_instructions";
+
+/// Launch profile configured for `mode`.
+pub fn codex_mode_config<'a>(config: &'a AppConfig, mode: &Mode) -> &'a CodexModeConfig {
+    match mode {
+        Mode::Normal => &config.codex.normal,
+        Mode::Coach => &config.codex.coach,
+        Mode::Drive => &config.codex.drive,
+    }
+}
+
+/// One user-supplied Codex argument, as the Codex CLI reads it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodexArg {
+    /// An option under its long name (`-s` reads as `sandbox`), with its
+    /// value when it takes one.
+    Option {
+        name: String,
+        value: Option<String>,
+        raw: String,
+    },
+    /// A `-c key=value` / `--config key=value` override.
+    Config { key: String, raw: String },
+    /// A subcommand, the prompt, or anything after `--`.
+    Positional(String),
+}
+
+/// Parses `extra` into options, config overrides and positionals. Options
+/// Codex does not document are kept under their own name without a value.
+pub fn parse_codex_args(extra: &[String]) -> Vec<CodexArg> {
+    let mut parsed = Vec::new();
+    let mut args = extra.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            parsed.extend(args.by_ref().cloned().map(CodexArg::Positional));
+            break;
+        }
+        let (name, attached) = if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, value)) => (canonical_option(name), Some(value.to_string())),
+                None => (canonical_option(long), None),
+            }
+        } else if let Some(short) = arg.strip_prefix('-').filter(|short| !short.is_empty()) {
+            let mut chars = short.chars();
+            let flag = chars.next().unwrap_or_default();
+            let rest = chars.as_str();
+            let rest = rest.strip_prefix('=').unwrap_or(rest);
+            let name = CODEX_OPTIONS
+                .iter()
+                .find(|(_, alias, _)| *alias == Some(flag))
+                .map(|(name, _, _)| name.to_string())
+                .unwrap_or_else(|| flag.to_string());
+            (name, (!rest.is_empty()).then(|| rest.to_string()))
+        } else {
+            parsed.push(CodexArg::Positional(arg.clone()));
+            continue;
+        };
+
+        let mut raw = arg.clone();
+        let value = match attached {
+            Some(value) => Some(value),
+            None if takes_value(&name) => args.next().map(|value| {
+                raw = format!("{arg} {value}");
+                value.clone()
+            }),
+            None => None,
+        };
+        if name == "config" {
+            let key = value
+                .as_deref()
+                .map(|value| {
+                    let key = value.split_once('=').map_or(value, |(key, _)| key);
+                    key.trim().trim_matches('"')
+                })
+                .unwrap_or_default()
+                .to_string();
+            parsed.push(CodexArg::Config { key, raw });
+        } else {
+            parsed.push(CodexArg::Option { name, value, raw });
+        }
+    }
+    parsed
+}
+
+/// Long name for `name` as written after `--` or in a config list.
+fn canonical_option(name: &str) -> String {
+    let name = name.trim_start_matches('-');
+    if let Some((_, canonical)) = CODEX_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return canonical.to_string();
+    }
+    let mut chars = name.chars();
+    if let (Some(short), None) = (chars.next(), chars.next()) {
+        if let Some((long, _, _)) = CODEX_OPTIONS
+            .iter()
+            .find(|(_, alias, _)| *alias == Some(short))
+        {
+            return long.to_string();
+        }
+    }
+    name.to_string()
+}
+
+fn takes_value(name: &str) -> bool {
+    CODEX_OPTIONS
+        .iter()
+        .any(|(option, _, value)| *option == name && *value)
+}
+
+/// Why spp never passes the option `name` through, if it is one it manages.
+fn managed_option_reason(name: &str, mode: &Mode, codex_mode: &CodexModeConfig) -> Option<String> {
+    let reason = match name {
+        "sandbox" => format!(
+            "spp sets `--sandbox {}` in {} mode",
+            codex_mode.sandbox,
+            mode.as_str()
+        ),
+        "ask-for-approval" => format!(
+            "spp sets `--ask-for-approval {}` in {} mode",
+            codex_mode.approval,
+            mode.as_str()
+        ),
+        "profile" if codex_mode.profile.is_some() => format!(
+            "spp sets `--profile {}` in {} mode",
+            codex_mode.profile.as_deref().unwrap_or_default(),
+            mode.as_str()
+        ),
+        "full-auto" => "`--full-auto` is prohibited by SPP policy".to_string(),
+        "dangerously-bypass-approvals-and-sandbox" => {
+            "it turns off the sandbox and approvals spp enforces".to_string()
+        }
+        _ => return None,
+    };
+    Some(reason)
+}
+
+/// Rejects user-supplied Codex arguments that would bypass the enforced
+/// sandbox/approval flags or that `[codex.<mode>]` does not allow, naming the
+/// rule that rejected them.
+pub fn validate_codex_extra_args(
+    extra: &[String],
+    mode: &Mode,
+    codex_mode: &CodexModeConfig,
+) -> Result<()> {
+    let section = format!("[codex.{}]", mode.as_str());
+    let allow_flags: Vec<String> = codex_mode
+        .allow_flags
+        .iter()
+        .map(|name| canonical_option(name))
+        .collect();
+    let deny_flags: Vec<String> = codex_mode
+        .deny_flags
+        .iter()
+        .map(|name| canonical_option(name))
+        .collect();
+    let allow_config = compile_globs(&codex_mode.allow_config, &section, "allow_config")?;
+    let deny_config = compile_globs(&codex_mode.deny_config, &section, "deny_config")?;
+
+    for arg in parse_codex_args(extra) {
+        match arg {
+            CodexArg::Option { name, raw, .. } => {
+                if let Some(reason) = managed_option_reason(&name, mode, codex_mode) {
+                    bail!("`{raw}` is not allowed: {reason}");
+                }
+                if deny_flags.contains(&name) {
+                    bail!("`{raw}` is not allowed: `{name}` is listed in {section} deny_flags");
+                }
+                if !allow_flags.is_empty() && !allow_flags.contains(&name) {
+                    bail!(
+                        "`{raw}` is not allowed: `{name}` is not listed in {section} allow_flags"
+                    );
+                }
+            }
+            CodexArg::Config { key, raw } => {
+                if MANAGED_CONFIG_KEYS.contains(&key.as_str()) {
+                    bail!(
+                        "`{raw}` is not allowed: spp sets `--sandbox {}` and `--ask-for-approval {}` in {} mode",
+                        codex_mode.sandbox,
+                        codex_mode.approval,
+                        mode.as_str()
+                    );
+                }
+                if let Some(enforced) = enforced_config_key(&key, codex_mode) {
+                    bail!(
+                        "`{raw}` is not allowed: spp sets `{enforced}` in {} mode",
+                        mode.as_str()
+                    );
+                }
+                if let Some(glob) = deny_config.iter().find(|glob| key_within(glob, &key)) {
+                    bail!(
+                        "`{raw}` is not allowed: `{key}` matches `{}` in {section} deny_config",
+                        glob.as_str()
+                    );
+                }
+                if let Some(glob) = deny_config.iter().find(|glob| key_contains(glob, &key)) {
+                    bail!(
+                        "`{raw}` is not allowed: `{key}` is a table containing `{}` in {section} deny_config",
+                        glob.as_str()
+                    );
+                }
+                if !allow_config.is_empty()
+                    && !allow_config.iter().any(|glob| key_within(glob, &key))
+                {
+                    bail!(
+                        "`{raw}` is not allowed: `{key}` is not listed in {section} allow_config"
+                    );
+                }
+            }
+            CodexArg::Positional(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The enforced `-c` key that a user override of `key` would overlap, if any:
+/// the same key, a parent table of it, or a key inside it.
+fn enforced_config_key<'a>(key: &str, codex_mode: &'a CodexModeConfig) -> Option<&'a str> {
+    let overlaps = |enforced: &str| {
+        key == enforced
+            || enforced
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with('.'))
+            || key
+                .strip_prefix(enforced)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    let instructions = codex_mode.instructions.as_ref().map(|_| INSTRUCTIONS_KEY);
+    codex_mode
+        .config
+        .keys()
+        .map(String::as_str)
+        .chain(instructions)
+        .find(|enforced| overlaps(enforced))
+}
+
+/// Whether `glob` matches `key` or one of the tables it is nested in, so
+/// `profiles` also covers `profiles.fast.model`.
+fn key_within(glob: &Glob, key: &str) -> bool {
+    let mut table = key;
+    loop {
+        if glob.is_match(table) {
+            return true;
+        }
+        match table.rfind('.') {
+            Some(dot) => table = &table[..dot],
+            None => return false,
+        }
+    }
+}
+
+/// Whether `key` is a table holding keys `glob` matches, so that setting it
+/// whole (`-c 'profiles={...}'`) would set them too. Compared per dotted segment.
+fn key_contains(glob: &Glob, key: &str) -> bool {
+    let pattern: Vec<&str> = glob.as_str().split('.').collect();
+    let key: Vec<&str> = key.split('.').collect();
+    key.len() < pattern.len()
+        && key
+            .iter()
+            .zip(&pattern)
+            .all(|(segment, pattern)| Glob::new(pattern).is_ok_and(|glob| glob.is_match(segment)))
+}
+
+fn compile_globs(patterns: &[String], section: &str, field: &str) -> Result<Vec<Glob>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Glob::new(pattern).map_err(|err| err.context(format!("invalid {section} {field}")))
+        })
+        .collect()
+}
+
+/// `[codex.<mode>.config]` as `key=value` overrides, values in TOML syntax.
+pub fn config_overrides(codex_mode: &CodexModeConfig) -> Vec<String> {
+    codex_mode
+        .config
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+/// `[codex.<mode>].instructions` as loaded for a launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeInstructions {
+    None,
+    Text(String),
+    /// The built-in default file is not in this repository; Codex launches
+    /// without instructions.
+    MissingDefault(PathBuf),
+}
+
+impl ModeInstructions {
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Text of `[codex.<mode>].instructions`, if set. A missing file is an error
+/// unless it is the built-in default, which not every repository ships.
+pub fn load_mode_instructions(
+    repo_root: &Path,
+    codex_mode: &CodexModeConfig,
+) -> Result<ModeInstructions> {
+    let Some(path) = &codex_mode.instructions else {
+        return Ok(ModeInstructions::None);
+    };
+    let full_path = repo_root.join(path);
+    if path == DEFAULT_DRIVE_INSTRUCTIONS && !full_path.exists() {
+        return Ok(ModeInstructions::MissingDefault(full_path));
+    }
+    let text = fs::read_to_string(&full_path)
+        .with_context(|| format!("failed to read Codex instructions {}", full_path.display()))?;
+    Ok(ModeInstructions::Text(text))
+}
+
+/// `extra` with the enforced flags added before any `--`, so they are never
+/// read as positionals: `--sandbox`, `--ask-for-approval`, the mode's
+/// `--profile` and `-c` overrides, and `instructions` when given.
+pub fn build_codex_args(
+    extra: &[String],
+    codex_mode: &CodexModeConfig,
+    instructions: Option<&str>,
+) -> Vec<String> {
+    let split = extra
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(extra.len());
+    let mut codex_args = extra[..split].to_vec();
+    codex_args.extend([
+        "--sandbox".to_string(),
+        codex_mode.sandbox.clone(),
+        "--ask-for-approval".to_string(),
+        codex_mode.approval.clone(),
+    ]);
+    if let Some(profile) = &codex_mode.profile {
+        codex_args.extend(["--profile".to_string(), profile.clone()]);
+    }
+    for config_override in config_overrides(codex_mode) {
+        codex_args.extend(["-c".to_string(), config_override]);
+    }
+    if let Some(instructions) = instructions {
+        let value = toml::Value::String(instructions.to_string());
+        codex_args.extend(["-c".to_string(), format!("{INSTRUCTIONS_KEY}={value}")]);
+    }
+    codex_args.extend_from_slice(&extra[split..]);
+    codex_args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn rejection(raw: &[&str], mode: Mode, codex_mode: &CodexModeConfig) -> String {
+        validate_codex_extra_args(&args(raw), &mode, codex_mode)
+            .expect_err("arguments should be rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn parse_codex_args_reads_short_long_and_attached_forms() {
+        let parsed = parse_codex_args(&args(&[
+            "-sread-only",
+            "--model=o3",
+            "-c",
+            "sandbox_mode=\"danger-full-access\"",
+            "--config=approval_policy=never",
+            "exec",
+            "--",
+            "--yolo",
+        ]));
+        assert_eq!(
+            parsed,
+            vec![
+                CodexArg::Option {
+                    name: "sandbox".to_string(),
+                    value: Some("read-only".to_string()),
+                    raw: "-sread-only".to_string(),
+                },
+                CodexArg::Option {
+                    name: "model".to_string(),
+                    value: Some("o3".to_string()),
+                    raw: "--model=o3".to_string(),
+                },
+                CodexArg::Config {
+                    key: "sandbox_mode".to_string(),
+                    raw: "-c sandbox_mode=\"danger-full-access\"".to_string(),
+                },
+                CodexArg::Config {
+                    key: "approval_policy".to_string(),
+                    raw: "--config=approval_policy=never".to_string(),
+                },
+                CodexArg::Positional("exec".to_string()),
+                CodexArg::Positional("--yolo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn managed_options_are_rejected_in_every_spelling() {
+        let codex_mode = CodexModeConfig {
+            sandbox: "read-only".to_string(),
+            ..CodexModeConfig::default()
+        };
+        assert_eq!(
+            rejection(&["-s", "danger-full-access"], Mode::Drive, &codex_mode),
+            "`-s danger-full-access` is not allowed: spp sets `--sandbox read-only` in drive mode"
+        );
+        for raw in [
+            &["--full-auto"][..],
+            &["-a", "never"],
+            &["--ask-for-approval=never"],
+            &["--dangerously-bypass-approvals-and-sandbox"],
+            &["--yolo"],
+            &["-c", "sandbox_mode=danger-full-access"],
+            &["-capproval_policy=never"],
+            &["--add-dir", "/tmp"],
+            &["--add-dir=/tmp"],
+            &["-C", ".."],
+            &["--cd=.."],
+        ] {
+            assert!(validate_codex_extra_args(&args(raw), &Mode::Normal, &codex_mode).is_err());
+            assert!(validate_codex_extra_args(&args(raw), &Mode::Coach, &codex_mode).is_err());
+            assert!(validate_codex_extra_args(&args(raw), &Mode::Drive, &codex_mode).is_err());
+        }
+        assert_eq!(
+            rejection(&["-C", ".."], Mode::Drive, &codex_mode),
+            "`-C ..` is not allowed: `cd` is listed in [codex.drive] deny_flags"
+        );
+        assert!(rejection(&["--full-auto"], Mode::Normal, &codex_mode)
+            .contains("`--full-auto` is prohibited by SPP policy"));
+    }
+
+    #[test]
+    fn mode_lists_allow_and_deny_options_and_config_keys() {
+        let codex_mode = CodexModeConfig::default();
+        assert!(validate_codex_extra_args(
+            &args(&[
+                "-m",
+                "o3",
+                "-c",
+                "model_reasoning_effort=high",
+                "exec",
+                "fix it"
+            ]),
+            &Mode::Normal,
+            &codex_mode
+        )
+        .is_ok());
+        assert_eq!(
+            rejection(&["-p", "yolo"], Mode::Normal, &codex_mode),
+            "`-p yolo` is not allowed: `profile` is listed in [codex.normal] deny_flags"
+        );
+        assert_eq!(
+            rejection(
+                &["-c", "sandbox_workspace_write.network_access=true"],
+                Mode::Coach,
+                &codex_mode
+            ),
+            "`-c sandbox_workspace_write.network_access=true` is not allowed: \
+             `sandbox_workspace_write.network_access` matches `sandbox_workspace_write.*` \
+             in [codex.coach] deny_config"
+        );
+
+        for table in [
+            "sandbox_workspace_write={network_access=true}",
+            "profiles={fast={sandbox_mode=\"danger-full-access\"}}",
+            "profiles.fast={model=\"o3\"}",
+        ] {
+            assert!(rejection(&["-c", table], Mode::Normal, &codex_mode).contains("deny_config"));
+        }
+        assert_eq!(
+            rejection(
+                &["-c", "sandbox_workspace_write={network_access=true}"],
+                Mode::Coach,
+                &codex_mode
+            ),
+            "`-c sandbox_workspace_write={network_access=true}` is not allowed: \
+             `sandbox_workspace_write` is a table containing `sandbox_workspace_write.*` \
+             in [codex.coach] deny_config"
+        );
+
+        let strict = CodexModeConfig {
+            allow_flags: vec!["--model".to_string()],
+            allow_config: vec!["model_*".to_string()],
+            ..CodexModeConfig::default()
+        };
+        assert!(validate_codex_extra_args(
+            &args(&["-m", "o3", "-c", "model_reasoning_effort=high"]),
+            &Mode::Drive,
+            &strict
+        )
+        .is_ok());
+        assert_eq!(
+            rejection(&["--search"], Mode::Drive, &strict),
+            "`--search` is not allowed: `search` is not listed in [codex.drive] allow_flags"
+        );
+        let tables = CodexModeConfig {
+            allow_config: vec!["tools".to_string()],
+            ..CodexModeConfig::default()
+        };
+        assert!(validate_codex_extra_args(
+            &args(&["-c", "tools.web_search=true"]),
+            &Mode::Drive,
+            &tables
+        )
+        .is_ok());
+        assert_eq!(
+            rejection(&["-c", "tools.web_search=true"], Mode::Drive, &strict),
+            "`-c tools.web_search=true` is not allowed: `tools.web_search` is not listed in \
+             [codex.drive] allow_config"
+        );
+    }
+
+    #[test]
+    fn build_codex_args_places_enforced_flags_before_the_separator() {
+        let codex_mode = CodexModeConfig::default();
+        assert_eq!(
+            build_codex_args(&args(&["exec", "--", "--help me"]), &codex_mode, None),
+            args(&[
+                "exec",
+                "--sandbox",
+                "read-only",
+                "--ask-for-approval",
+                "on-request",
+                "--",
+                "--help me"
+            ])
+        );
+    }
+
+    #[test]
+    fn default_drive_mode_enforces_instructions_and_no_network() -> Result<()> {
+        let drive = AppConfig::default().codex.drive;
+        let built = build_codex_args(&[], &drive, Some("Ask, don't write."));
+        assert_eq!(
+            built,
+            args(&[
+                "--sandbox",
+                "read-only",
+                "--ask-for-approval",
+                "on-request",
+                "-c",
+                "sandbox_workspace_write.network_access=false",
+                "-c",
+                "developer_instructions=\"Ask, don't write.\"",
+            ])
+        );
+
+        let temp = crate::test_support::TempDirGuard::new("spp-codex-instructions")?;
+        let root = temp.path();
+        assert_eq!(
+            load_mode_instructions(root, &drive)?,
+            ModeInstructions::MissingDefault(root.join(DEFAULT_DRIVE_INSTRUCTIONS))
+        );
+        fs::create_dir_all(root.join(".agents/modes"))?;
+        fs::write(root.join(DEFAULT_DRIVE_INSTRUCTIONS), "Drive.")?;
+        assert_eq!(load_mode_instructions(root, &drive)?.text(), Some("Drive."));
+
+        let custom = CodexModeConfig {
+            instructions: Some("missing.md".to_string()),
+            ..drive
+        };
+        assert!(load_mode_instructions(root, &custom).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn build_codex_args_adds_profile_config_and_instructions() {
+        let codex_mode = CodexModeConfig {
+            profile: Some("coach".to_string()),
+            config: [
+                (
+                    "model".to_string(),
+                    toml::Value::String("o4-mini".to_string()),
+                ),
+                (
+                    "sandbox_workspace_write.network_access".to_string(),
+                    toml::Value::Boolean(false),
+                ),
+            ]
+            .into(),
+            instructions: Some(".agents/modes/drive.md".to_string()),
+            ..CodexModeConfig::default()
+        };
+        let built = build_codex_args(&args(&["exec"]), &codex_mode, Some("Ask, don't write."));
+        assert_eq!(
+            built,
+            args(&[
+                "exec",
+                "--sandbox",
+                "read-only",
+                "--ask-for-approval",
+                "on-request",
+                "--profile",
+                "coach",
+                "-c",
+                "model=\"o4-mini\"",
+                "-c",
+                "sandbox_workspace_write.network_access=false",
+                "-c",
+                "developer_instructions=\"Ask, don't write.\"",
+            ])
+        );
+    }
+
+    #[test]
+    fn overrides_of_enforced_settings_are_rejected() {
+        let codex_mode = CodexModeConfig {
+            deny_flags: Vec::new(),
+            deny_config: Vec::new(),
+            profile: Some("coach".to_string()),
+            config: [(
+                "sandbox_workspace_write.network_access".to_string(),
+                toml::Value::Boolean(false),
+            )]
+            .into(),
+            instructions: Some(".agents/modes/drive.md".to_string()),
+            ..CodexModeConfig::default()
+        };
+        assert_eq!(
+            rejection(&["--profile", "fast"], Mode::Drive, &codex_mode),
+            "`--profile fast` is not allowed: spp sets `--profile coach` in drive mode"
+        );
+        assert_eq!(
+            rejection(
+                &["-c", "sandbox_workspace_write={network_access=true}"],
+                Mode::Drive,
+                &codex_mode
+            ),
+            "`-c sandbox_workspace_write={network_access=true}` is not allowed: \
+             spp sets `sandbox_workspace_write.network_access` in drive mode"
+        );
+        assert!(rejection(
+            &["-c", "developer_instructions=\"write it all\""],
+            Mode::Drive,
+            &codex_mode
+        )
+        .contains("spp sets `developer_instructions`"));
+        assert!(validate_codex_extra_args(
+            &args(&["-c", "sandbox_workspace_write.writable_roots=[]"]),
+            &Mode::Drive,
+            &codex_mode
+        )
+        .is_ok());
+    }
+}
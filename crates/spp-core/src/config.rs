@@ -0,0 +1,682 @@
+//! Runtime configuration loaded from `.codex-spp/config.toml`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{RUNTIME_CONFIG, TEMPLATE_CONFIG};
+use crate::state::Actor;
+
+/// Default `[transcript].history_path`; resolved to `$CODEX_HOME/history.jsonl`.
+pub const DEFAULT_HISTORY_PATH: &str = "auto";
+/// The only supported `[transcript].chat_source`.
+pub const DEFAULT_CHAT_SOURCE: &str = "history_jsonl";
+/// Default cap on the serialized size of a single transcript event.
+pub const DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES: u64 = 64_000;
+/// Default `[attribution].notes_ref`.
+pub const DEFAULT_NOTES_REF: &str = "refs/notes/spp";
+/// Default recorder poll interval.
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
+/// Default `[codex.drive].instructions`, shipped in `.agents/modes/`.
+pub const DEFAULT_DRIVE_INSTRUCTIONS: &str = ".agents/modes/drive.md";
+
+/// Top-level runtime configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub log_schema_version: String,
+    /// Minimum human share of added lines required to pass the weekly gate.
+    pub weekly_ratio_target: f64,
+    /// Total size budget for session, weekly and transcript logs.
+    pub max_log_bytes: u64,
+    pub diff_snapshot_enabled: bool,
+    pub gate: GateConfig,
+    pub codex: CodexConfig,
+    pub transcript: TranscriptConfig,
+    pub attribution: AttributionConfig,
+    pub metrics: MetricsConfig,
+    pub git: GitConfig,
+}
+
+/// Time window the gate evaluates (`[gate]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateConfig {
+    pub window: GateWindowKind,
+    /// Length of the window for `window = "rolling_days"`.
+    pub rolling_days: u32,
+    /// First day of any sprint for `window = "sprint"`.
+    pub sprint_anchor: Option<NaiveDate>,
+    pub sprint_length_days: u32,
+    /// `UTC`, `local`, or a fixed offset such as `+09:00`.
+    pub timezone: String,
+    /// Decide the gate on the current `git config user.email`'s own ratio
+    /// instead of the whole team's.
+    pub per_author: bool,
+    /// Human share that decides `gate_passed`.
+    pub metric: GateMetric,
+    /// Blend of the individual metrics for `metric = "weighted"`.
+    pub weights: MetricWeights,
+    /// Commits the gate walks, independent of the checked-out branch when not `head`.
+    pub scope: GateScope,
+    /// Which commit timestamp places a commit in the window.
+    pub date_field: GateDateField,
+    /// How far outside the window the other timestamp may be before a commit
+    /// is reported as skewed.
+    pub max_date_skew_hours: u64,
+    /// Ratio below which the gate forces Drive; between this and
+    /// `weekly_ratio_target` it forces Coach. Unset: Drive whenever below target.
+    pub drive_below: Option<f64>,
+    /// Margin the ratio must clear above a threshold before the gate relaxes
+    /// to a less restrictive mode, so the mode does not flap around it.
+    pub hysteresis: f64,
+    pub recovery: GateRecoveryConfig,
+}
+
+/// What must happen after the gate trips before it releases back to Normal
+/// (`[gate.recovery]`); zero disables a requirement.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateRecoveryConfig {
+    /// Minutes of `spp drive` session time since the trip.
+    pub drive_minutes: u64,
+    /// Human lines added (under the gate's metric filters) since the trip.
+    pub human_lines: u64,
+}
+
+impl GateRecoveryConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.drive_minutes > 0 || self.human_lines > 0
+    }
+}
+
+/// Commit timestamp compared with the gate window (`[gate].date_field`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GateDateField {
+    /// When the commit was made; moves with rebases and cherry-picks.
+    #[default]
+    Committer,
+    /// When the work was originally written.
+    Author,
+}
+
+impl std::fmt::Display for GateDateField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GateDateField::Committer => "committer",
+            GateDateField::Author => "author",
+        })
+    }
+}
+
+/// History the gate counts (`[gate].scope`): `"head"`, `"all"`,
+/// `"default_branch"`, or a list of refs such as `["main", "origin/release"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "GateScopeRepr", into = "GateScopeRepr")]
+pub enum GateScope {
+    /// Ancestry of the checked-out `HEAD`.
+    #[default]
+    Head,
+    /// Every branch, remote-tracking branch and tag (`git log --all`, without notes).
+    All,
+    /// `origin/HEAD`, falling back to a local `main` or `master`.
+    DefaultBranch,
+    /// These branches, tags or other revisions.
+    Refs(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GateScopeRepr {
+    Name(String),
+    Refs(Vec<String>),
+}
+
+impl TryFrom<GateScopeRepr> for GateScope {
+    type Error = String;
+
+    fn try_from(repr: GateScopeRepr) -> std::result::Result<Self, Self::Error> {
+        match repr {
+            GateScopeRepr::Name(name) => match name.as_str() {
+                "head" => Ok(GateScope::Head),
+                "all" => Ok(GateScope::All),
+                "default_branch" => Ok(GateScope::DefaultBranch),
+                other => Err(format!(
+                    "unknown gate scope `{other}` (expected head, all, default_branch or a list of refs)"
+                )),
+            },
+            GateScopeRepr::Refs(refs) if refs.is_empty() => {
+                Err("gate scope ref list must not be empty".to_string())
+            }
+            GateScopeRepr::Refs(refs) => Ok(GateScope::Refs(refs)),
+        }
+    }
+}
+
+impl From<GateScope> for GateScopeRepr {
+    fn from(scope: GateScope) -> Self {
+        match scope {
+            GateScope::Head => GateScopeRepr::Name("head".to_string()),
+            GateScope::All => GateScopeRepr::Name("all".to_string()),
+            GateScope::DefaultBranch => GateScopeRepr::Name("default_branch".to_string()),
+            GateScope::Refs(refs) => GateScopeRepr::Refs(refs),
+        }
+    }
+}
+
+/// Human share compared with `weekly_ratio_target` (`[gate].metric`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GateMetric {
+    /// Added lines.
+    #[default]
+    Added,
+    /// Added plus deleted lines, so refactors and removals count.
+    #[serde(rename = "added+deleted")]
+    AddedDeleted,
+    /// Added minus deleted lines, floored at zero per actor.
+    Net,
+    /// Files changed, summed over commits.
+    FilesTouched,
+    /// Commits.
+    Commits,
+    /// Weighted mean of the above using `[gate.weights]`.
+    Weighted,
+}
+
+impl std::fmt::Display for GateMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GateMetric::Added => "added",
+            GateMetric::AddedDeleted => "added+deleted",
+            GateMetric::Net => "net",
+            GateMetric::FilesTouched => "files_touched",
+            GateMetric::Commits => "commits",
+            GateMetric::Weighted => "weighted",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Weights of each metric in the `weighted` composite (`[gate.weights]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MetricWeights {
+    pub added: f64,
+    #[serde(rename = "added+deleted")]
+    pub added_deleted: f64,
+    pub net: f64,
+    pub files_touched: f64,
+    pub commits: f64,
+}
+
+/// Shape of the gate window.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GateWindowKind {
+    /// Monday 00:00 to the next Monday 00:00 in the gate timezone.
+    #[default]
+    IsoWeek,
+    /// The last `rolling_days` days up to now.
+    RollingDays,
+    /// Consecutive `sprint_length_days` periods starting at `sprint_anchor`.
+    Sprint,
+}
+
+/// Codex launch settings (`[codex]`) and profiles per mode (`[codex.normal]` /
+/// `[codex.coach]` / `[codex.drive]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodexConfig {
+    /// Record a Drive transcript for the lifetime of every `spp codex` session.
+    pub record_transcript: bool,
+    /// Seconds between gate re-evaluations while Codex runs; `0` disables them.
+    pub supervise_interval_secs: u64,
+    /// What to do when the gate forces a stricter mode while Codex runs.
+    pub on_gate_trip: GateTripAction,
+    #[serde(default = "default_codex_mode_normal")]
+    pub normal: CodexModeConfig,
+    #[serde(default = "default_codex_mode_coach")]
+    pub coach: CodexModeConfig,
+    #[serde(default = "default_codex_mode_drive")]
+    pub drive: CodexModeConfig,
+}
+
+/// Reaction of `spp codex` to a gate trip while Codex runs (`[codex].on_gate_trip`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GateTripAction {
+    /// Print a warning and let Codex continue.
+    #[default]
+    Warn,
+    /// Stop Codex.
+    Terminate,
+    /// Stop Codex and relaunch it with the profile of the enforced mode.
+    Restart,
+}
+
+impl GateTripAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GateTripAction::Warn => "warn",
+            GateTripAction::Terminate => "terminate",
+            GateTripAction::Restart => "restart",
+        }
+    }
+}
+
+/// Sandbox, approval, profile and config overrides enforced by `spp codex` for
+/// one mode, and the policy for arguments passed through to Codex.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodexModeConfig {
+    pub sandbox: String,
+    pub approval: String,
+    /// Warning `spp codex` prints before launching in this mode; empty for none.
+    pub banner: String,
+    /// Codex `--profile` to launch with.
+    pub profile: Option<String>,
+    /// `-c key=value` overrides to launch with (`[codex.<mode>.config]`), e.g.
+    /// `model` or `sandbox_workspace_write.network_access`.
+    pub config: BTreeMap<String, toml::Value>,
+    /// File, relative to the repository root, passed to Codex as
+    /// `developer_instructions`.
+    pub instructions: Option<String>,
+    /// Codex options (long names such as `model`) that may be passed; empty
+    /// allows any that is not denied.
+    pub allow_flags: Vec<String>,
+    /// Codex options rejected in addition to the ones spp always manages.
+    pub deny_flags: Vec<String>,
+    /// Globs on `-c key=value` keys (or their parent tables) that may be
+    /// passed; empty allows any that is not denied.
+    pub allow_config: Vec<String>,
+    /// Globs on `-c` keys rejected in addition to the ones spp always manages;
+    /// keys inside a matching table, or tables holding matching keys, are
+    /// rejected too.
+    pub deny_config: Vec<String>,
+}
+
+/// Drive transcript recorder settings (`[transcript]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptConfig {
+    pub chat_source: String,
+    pub history_path: String,
+    pub capture_full_text: bool,
+    pub max_event_bytes: u64,
+    pub include_file_diff: bool,
+    pub watch_exclude: Vec<String>,
+    pub poll_interval_ms: u64,
+}
+
+/// Commit attribution settings (`[attribution]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributionConfig {
+    pub codex_author_emails: Vec<String>,
+    /// Append the built-in Codex trailer, author email and `spp:*` note rules
+    /// after `rules`.
+    pub builtin_rules: bool,
+    /// Ordered `[[attribution.rules]]`; the first matching rule decides.
+    pub rules: Vec<AttributionRule>,
+    /// Git notes ref holding overrides shared across clones (`spp attrib push/pull`).
+    pub notes_ref: String,
+    /// Save `spp attrib fix/review/accept` overrides to `notes_ref` instead of
+    /// the git-ignored `state.json`.
+    pub write_notes: bool,
+    /// Let rebased or cherry-picked commits inherit local overrides by `git patch-id`.
+    pub match_patch_id: bool,
+    /// Trailer keys whose values identify a change across rewrites and squash merges.
+    pub change_id_trailers: Vec<String>,
+}
+
+/// Which changed files count toward line metrics (`[metrics]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Path globs to count; empty counts every file.
+    pub include: Vec<String>,
+    /// Path globs never counted, e.g. `*.lock`. A glob without `/` matches the file name.
+    pub exclude: Vec<String>,
+    /// Skip files marked `linguist-generated` or `-diff` in `.gitattributes`.
+    pub honor_gitattributes: bool,
+    /// Most added lines counted per file per commit; `0` disables the cap.
+    pub max_lines_per_file: u64,
+}
+
+/// How spp reads the repository (`[git]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    pub backend: GitBackendKind,
+}
+
+/// Implementation behind [`crate::backend::GitBackend`] (`[git].backend`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GitBackendKind {
+    /// Runs the `git` executable.
+    #[default]
+    Subprocess,
+    /// Links libgit2; needs spp built with `--features libgit2`.
+    Libgit2,
+}
+
+/// One attribution rule. Every matcher that is set must match for the rule to
+/// fire; a rule needs at least one matcher.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AttributionRule {
+    /// Label shown by `spp attrib explain`; defaults to `rules[<index>]`.
+    #[serde(default)]
+    pub name: String,
+    pub actor: Actor,
+    /// AI share of added lines for `actor = "mixed"` (default 0.5).
+    pub ai_share: Option<f64>,
+    /// Regex tested against each `Key: value` trailer line of the message.
+    pub trailer: Option<String>,
+    /// Regex tested against the full commit message, body included.
+    pub message: Option<String>,
+    /// Literal prefix of the commit subject, e.g. `chore(codex):`.
+    pub message_prefix: Option<String>,
+    /// Glob on the author email (case-insensitive).
+    pub author_email: Option<String>,
+    /// Glob on the committer email (case-insensitive).
+    pub committer_email: Option<String>,
+    /// Glob on any local or remote-tracking branch containing the commit.
+    pub branch: Option<String>,
+    /// Globs that every changed path must match one of.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regex tested against the commit's git notes.
+    pub note: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            log_schema_version: "1.1".to_string(),
+            weekly_ratio_target: 0.70,
+            max_log_bytes: 524_288_000,
+            diff_snapshot_enabled: false,
+            gate: GateConfig::default(),
+            codex: CodexConfig::default(),
+            transcript: TranscriptConfig::default(),
+            attribution: AttributionConfig::default(),
+            metrics: MetricsConfig::default(),
+            git: GitConfig::default(),
+        }
+    }
+}
+
+impl Default for AttributionConfig {
+    fn default() -> Self {
+        Self {
+            codex_author_emails: Vec::new(),
+            builtin_rules: true,
+            rules: Vec::new(),
+            notes_ref: DEFAULT_NOTES_REF.to_string(),
+            write_notes: false,
+            match_patch_id: true,
+            change_id_trailers: vec!["Change-Id".to_string()],
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            honor_gitattributes: true,
+            max_lines_per_file: 0,
+        }
+    }
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            backend: GitBackendKind::Subprocess,
+        }
+    }
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            window: GateWindowKind::IsoWeek,
+            rolling_days: 7,
+            sprint_anchor: None,
+            sprint_length_days: 14,
+            timezone: "UTC".to_string(),
+            per_author: false,
+            metric: GateMetric::Added,
+            weights: MetricWeights::default(),
+            scope: GateScope::Head,
+            date_field: GateDateField::Committer,
+            max_date_skew_hours: 48,
+            drive_below: None,
+            hysteresis: 0.0,
+            recovery: GateRecoveryConfig::default(),
+        }
+    }
+}
+
+impl Default for MetricWeights {
+    fn default() -> Self {
+        Self {
+            added: 1.0,
+            added_deleted: 0.0,
+            net: 0.0,
+            files_touched: 0.0,
+            commits: 0.0,
+        }
+    }
+}
+
+impl Default for CodexConfig {
+    fn default() -> Self {
+        Self {
+            record_transcript: false,
+            supervise_interval_secs: 60,
+            on_gate_trip: GateTripAction::Warn,
+            normal: default_codex_mode_normal(),
+            coach: default_codex_mode_coach(),
+            drive: default_codex_mode_drive(),
+        }
+    }
+}
+
+impl Default for CodexModeConfig {
+    fn default() -> Self {
+        Self {
+            sandbox: "read-only".to_string(),
+            approval: "on-request".to_string(),
+            banner: String::new(),
+            profile: None,
+            config: BTreeMap::new(),
+            instructions: None,
+            allow_flags: Vec::new(),
+            deny_flags: vec![
+                "profile".to_string(),
+                "add-dir".to_string(),
+                "cd".to_string(),
+            ],
+            allow_config: Vec::new(),
+            deny_config: vec![
+                "profile".to_string(),
+                "profiles.*".to_string(),
+                "sandbox_workspace_write.*".to_string(),
+            ],
+        }
+    }
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self {
+            chat_source: DEFAULT_CHAT_SOURCE.to_string(),
+            history_path: DEFAULT_HISTORY_PATH.to_string(),
+            capture_full_text: true,
+            max_event_bytes: DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES,
+            include_file_diff: true,
+            watch_exclude: vec![
+                ".git/".to_string(),
+                ".codex-spp/".to_string(),
+                "target/".to_string(),
+            ],
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+        }
+    }
+}
+
+fn default_codex_mode_normal() -> CodexModeConfig {
+    CodexModeConfig {
+        sandbox: "workspace-write".to_string(),
+        approval: "on-request".to_string(),
+        ..CodexModeConfig::default()
+    }
+}
+
+fn default_codex_mode_coach() -> CodexModeConfig {
+    CodexModeConfig {
+        sandbox: "workspace-write".to_string(),
+        approval: "untrusted".to_string(),
+        banner: "Coach mode: human share is below target. Codex asks before every change; \
+                 write the code yourself where you can."
+            .to_string(),
+        ..CodexModeConfig::default()
+    }
+}
+
+fn default_codex_mode_drive() -> CodexModeConfig {
+    CodexModeConfig {
+        sandbox: "read-only".to_string(),
+        approval: "on-request".to_string(),
+        config: [(
+            "sandbox_workspace_write.network_access".to_string(),
+            toml::Value::Boolean(false),
+        )]
+        .into(),
+        instructions: Some(DEFAULT_DRIVE_INSTRUCTIONS.to_string()),
+        ..CodexModeConfig::default()
+    }
+}
+
+/// Loads the runtime config, falling back to the repo template and then to
+/// [`AppConfig::default`].
+pub fn load_config(repo_root: &Path) -> Result<AppConfig> {
+    let runtime_path = repo_root.join(RUNTIME_CONFIG);
+    if runtime_path.exists() {
+        let text = fs::read_to_string(&runtime_path)
+            .with_context(|| format!("failed to read {}", runtime_path.display()))?;
+        let cfg: AppConfig =
+            toml::from_str(&text).with_context(|| "failed to parse runtime config")?;
+        return Ok(cfg);
+    }
+
+    let template_path = repo_root.join(TEMPLATE_CONFIG);
+    if template_path.exists() {
+        let text = fs::read_to_string(&template_path)
+            .with_context(|| format!("failed to read {}", template_path.display()))?;
+        let cfg: AppConfig =
+            toml::from_str(&text).with_context(|| "failed to parse template config")?;
+        return Ok(cfg);
+    }
+
+    Ok(AppConfig::default())
+}
+
+/// Creates `.codex-spp/config.toml` from the repo template, or from
+/// [`AppConfig::default`] when there is no template. Existing configs are kept.
+pub fn init_runtime_config(repo_root: &Path) -> Result<()> {
+    let runtime_cfg_path = repo_root.join(RUNTIME_CONFIG);
+    if !runtime_cfg_path.exists() {
+        if let Some(parent) = runtime_cfg_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let template_cfg = repo_root.join(TEMPLATE_CONFIG);
+        if template_cfg.exists() {
+            fs::copy(&template_cfg, &runtime_cfg_path).with_context(|| {
+                format!(
+                    "failed to copy template config from {}",
+                    template_cfg.display()
+                )
+            })?;
+        } else {
+            let cfg_text = toml::to_string_pretty(&AppConfig::default())?;
+            fs::write(&runtime_cfg_path, cfg_text)
+                .with_context(|| "failed to write default runtime config")?;
+        }
+    }
+    Ok(())
+}
+
+/// `file_diff` capture requires both `diff_snapshot_enabled` and
+/// `[transcript].include_file_diff`.
+pub fn effective_include_file_diff(config: &AppConfig) -> bool {
+    config.diff_snapshot_enabled && config.transcript.include_file_diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effective_include_file_diff_requires_both_flags() {
+        let mut config = AppConfig {
+            diff_snapshot_enabled: false,
+            ..AppConfig::default()
+        };
+        config.transcript.include_file_diff = true;
+        assert!(!effective_include_file_diff(&config));
+
+        config.diff_snapshot_enabled = true;
+        assert!(effective_include_file_diff(&config));
+
+        config.transcript.include_file_diff = false;
+        assert!(!effective_include_file_diff(&config));
+    }
+
+    #[test]
+    fn codex_record_transcript_keeps_mode_defaults() -> Result<()> {
+        let codex: CodexConfig = toml::from_str("record_transcript = true")?;
+        assert!(codex.record_transcript);
+        assert_eq!(codex.supervise_interval_secs, 60);
+        assert_eq!(codex.on_gate_trip, GateTripAction::Warn);
+        let codex: CodexConfig = toml::from_str("on_gate_trip = \"restart\"")?;
+        assert_eq!(codex.on_gate_trip, GateTripAction::Restart);
+        assert_eq!(codex.coach.approval, "untrusted");
+        assert_eq!(codex.drive.sandbox, "read-only");
+        assert!(!CodexConfig::default().record_transcript);
+        Ok(())
+    }
+
+    #[test]
+    fn gate_scope_parses_names_and_ref_lists() -> Result<()> {
+        let parse = |raw: &str| toml::from_str::<GateConfig>(raw).map(|gate| gate.scope);
+        assert_eq!(parse("")?, GateScope::Head);
+        assert_eq!(
+            parse("scope = \"default_branch\"")?,
+            GateScope::DefaultBranch
+        );
+        assert_eq!(
+            parse("scope = [\"main\", \"origin/release\"]")?,
+            GateScope::Refs(vec!["main".to_string(), "origin/release".to_string()])
+        );
+        assert!(parse("scope = \"everything\"").is_err());
+        assert!(parse("scope = []").is_err());
+
+        let text = toml::to_string(&GateConfig {
+            scope: GateScope::All,
+            ..GateConfig::default()
+        })?;
+        assert!(text.contains("scope = \"all\""));
+        Ok(())
+    }
+}
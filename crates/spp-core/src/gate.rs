@@ -0,0 +1,1405 @@
+//! Weekly human:ai gate evaluation.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::attribution::{owner_email, Attributor, LoadedCommit};
+use crate::backend::{open_backend, CommitTimes, GitBackend, Revisions};
+use crate::config::{AppConfig, GateConfig, GateDateField, GateMetric, GateScope, MetricWeights};
+use crate::history::load_commit_records;
+use crate::infer::infer_worktree_share;
+use crate::metrics::{CountedLines, LineFilter};
+use crate::state::{GateTrip, Mode, State};
+use crate::week::IsoWeek;
+use crate::window::{current_gate_window, gate_today, iso_week_window, GateWindow};
+
+/// Gate result for one evaluation window, persisted to `.codex-spp/weekly/<year>-W<week>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyReport {
+    pub log_schema_version: String,
+    pub generated_at: DateTime<Utc>,
+    pub year: i32,
+    pub iso_week: u32,
+    /// Start (inclusive) of the evaluated window.
+    pub window_start: DateTime<Utc>,
+    /// End (exclusive) of the evaluated window.
+    pub window_end: DateTime<Utc>,
+    pub human_lines_added: u64,
+    pub ai_lines_added: u64,
+    pub human_commit_count: u64,
+    pub ai_commit_count: u64,
+    #[serde(default)]
+    pub human_lines_deleted: u64,
+    #[serde(default)]
+    pub ai_lines_deleted: u64,
+    /// Files changed per commit, summed and split by AI share like lines.
+    #[serde(default)]
+    pub human_files_touched: u64,
+    #[serde(default)]
+    pub ai_files_touched: u64,
+    /// `human_lines_added / (human_lines_added + ai_lines_added)`, `1.0` when empty.
+    pub ratio: f64,
+    pub target_ratio: f64,
+    /// Metric compared with `target_ratio` (`[gate].metric`).
+    #[serde(default)]
+    pub gate_metric: GateMetric,
+    /// Human share under `gate_metric` that decided `gate_passed`; the
+    /// `gate_author`'s own share when set.
+    #[serde(default)]
+    pub gate_ratio: f64,
+    /// Team-wide human share under every metric.
+    #[serde(default)]
+    pub metric_ratios: MetricRatios,
+    pub gate_passed: bool,
+    pub mode_after_evaluation: Mode,
+    /// Refs whose history was counted (`[gate].scope`).
+    #[serde(default)]
+    pub evaluated_refs: Vec<String>,
+    /// Author whose own ratio decided `gate_passed` when `[gate].per_author` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gate_author: Option<String>,
+    /// Per-author totals, collected only when `[gate].per_author` is on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<AuthorBreakdown>,
+    /// Progress toward `[gate.recovery]` while the gate holds Coach or Drive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoveryProgress>,
+    pub notes: Vec<String>,
+}
+
+/// Recovery done since the gate tripped, against `[gate.recovery]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryProgress {
+    pub tripped_at: DateTime<Utc>,
+    pub drive_minutes: u64,
+    pub required_drive_minutes: u64,
+    /// Human lines added since the trip; the gate author's own with `[gate].per_author`.
+    pub human_lines: u64,
+    pub required_human_lines: u64,
+}
+
+impl RecoveryProgress {
+    pub fn is_complete(&self) -> bool {
+        self.drive_minutes >= self.required_drive_minutes
+            && self.human_lines >= self.required_human_lines
+    }
+
+    /// What is still missing, e.g. `["12 Drive minutes", "40 human lines"]`.
+    pub fn remaining(&self) -> Vec<String> {
+        let mut remaining = Vec::new();
+        if self.drive_minutes < self.required_drive_minutes {
+            remaining.push(format!(
+                "{} Drive minutes",
+                self.required_drive_minutes - self.drive_minutes
+            ));
+        }
+        if self.human_lines < self.required_human_lines {
+            remaining.push(format!(
+                "{} human lines",
+                self.required_human_lines - self.human_lines
+            ));
+        }
+        remaining
+    }
+}
+
+/// Human share under each gate metric, `1.0` where nothing was counted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct MetricRatios {
+    pub added: f64,
+    #[serde(rename = "added+deleted")]
+    pub added_deleted: f64,
+    pub net: f64,
+    pub files_touched: f64,
+    pub commits: f64,
+    pub weighted: f64,
+}
+
+impl MetricRatios {
+    pub fn get(&self, metric: GateMetric) -> f64 {
+        match metric {
+            GateMetric::Added => self.added,
+            GateMetric::AddedDeleted => self.added_deleted,
+            GateMetric::Net => self.net,
+            GateMetric::FilesTouched => self.files_touched,
+            GateMetric::Commits => self.commits,
+            GateMetric::Weighted => self.weighted,
+        }
+    }
+}
+
+/// Totals for one commit owner (see [`owner_email`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AuthorBreakdown {
+    pub email: String,
+    pub human_lines_added: u64,
+    pub ai_lines_added: u64,
+    pub human_commit_count: u64,
+    pub ai_commit_count: u64,
+    #[serde(default)]
+    pub human_lines_deleted: u64,
+    #[serde(default)]
+    pub ai_lines_deleted: u64,
+    #[serde(default)]
+    pub human_files_touched: u64,
+    #[serde(default)]
+    pub ai_files_touched: u64,
+    /// Human share of added lines.
+    pub ratio: f64,
+}
+
+impl AuthorBreakdown {
+    fn record(&mut self, ai_share: f64, counted: &CountedLines) {
+        let (human, ai) = split_by_share(counted.added, ai_share);
+        self.human_lines_added += human;
+        self.ai_lines_added += ai;
+        let (human, ai) = split_by_share(counted.deleted, ai_share);
+        self.human_lines_deleted += human;
+        self.ai_lines_deleted += ai;
+        let (human, ai) = split_by_share(counted.files, ai_share);
+        self.human_files_touched += human;
+        self.ai_files_touched += ai;
+        if is_ai_commit(ai_share) {
+            self.ai_commit_count += 1;
+        } else {
+            self.human_commit_count += 1;
+        }
+        self.ratio = safe_ratio(
+            self.human_lines_added,
+            self.human_lines_added + self.ai_lines_added,
+        );
+    }
+
+    fn metric_ratios(&self, weights: &MetricWeights) -> MetricRatios {
+        metric_ratios(
+            ActorCounts {
+                lines_added: self.human_lines_added,
+                lines_deleted: self.human_lines_deleted,
+                files_touched: self.human_files_touched,
+                commits: self.human_commit_count,
+            },
+            ActorCounts {
+                lines_added: self.ai_lines_added,
+                lines_deleted: self.ai_lines_deleted,
+                files_touched: self.ai_files_touched,
+                commits: self.ai_commit_count,
+            },
+            weights,
+        )
+    }
+}
+
+/// One actor's side of the totals every metric is derived from.
+#[derive(Debug, Clone, Copy, Default)]
+struct ActorCounts {
+    lines_added: u64,
+    lines_deleted: u64,
+    files_touched: u64,
+    commits: u64,
+}
+
+impl ActorCounts {
+    fn net(&self) -> u64 {
+        self.lines_added.saturating_sub(self.lines_deleted)
+    }
+}
+
+fn metric_ratios(human: ActorCounts, ai: ActorCounts, weights: &MetricWeights) -> MetricRatios {
+    let share = |human: u64, ai: u64| safe_ratio(human, human + ai);
+    let mut ratios = MetricRatios {
+        added: share(human.lines_added, ai.lines_added),
+        added_deleted: share(
+            human.lines_added + human.lines_deleted,
+            ai.lines_added + ai.lines_deleted,
+        ),
+        net: share(human.net(), ai.net()),
+        files_touched: share(human.files_touched, ai.files_touched),
+        commits: share(human.commits, ai.commits),
+        weighted: 0.0,
+    };
+    let parts = [
+        (weights.added, ratios.added),
+        (weights.added_deleted, ratios.added_deleted),
+        (weights.net, ratios.net),
+        (weights.files_touched, ratios.files_touched),
+        (weights.commits, ratios.commits),
+    ];
+    let total_weight: f64 = parts.iter().map(|(weight, _)| weight.max(0.0)).sum();
+    ratios.weighted = if total_weight > 0.0 {
+        parts
+            .iter()
+            .map(|(weight, ratio)| weight.max(0.0) * ratio)
+            .sum::<f64>()
+            / total_weight
+    } else {
+        ratios.added
+    };
+    ratios
+}
+
+/// Raw per-actor totals for a week, before the ratios are derived.
+#[derive(Debug, Default)]
+pub struct WeeklyMetrics {
+    /// Team-wide totals; `email` is empty.
+    pub team: AuthorBreakdown,
+    /// Keyed by lower-cased owner email; empty unless `[gate].per_author` is on.
+    pub authors: BTreeMap<String, AuthorBreakdown>,
+    pub evaluated_refs: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Builds the report for the configured `[gate]` window at the current time,
+/// with the `[gate.recovery]` progress of an active gate trip.
+/// Does not change `state`; call [`apply_gate`] to act on the result.
+pub fn compute_weekly_report(
+    repo_root: &Path,
+    config: &AppConfig,
+    state: &State,
+) -> Result<WeeklyReport> {
+    let window = current_gate_window(&config.gate, Utc::now())?;
+    let git = open_backend(repo_root, &config.git)?;
+    let mut report = compute_report_for_window_with(git.as_ref(), config, state, &window)?;
+    report.recovery = recovery_progress(git.as_ref(), config, state, report.generated_at)?;
+    Ok(report)
+}
+
+/// Progress toward `[gate.recovery]` since `state.gate_trip`; `None` without a
+/// trip or when no requirement is configured.
+pub fn recovery_progress(
+    git: &dyn GitBackend,
+    config: &AppConfig,
+    state: &State,
+    now: DateTime<Utc>,
+) -> Result<Option<RecoveryProgress>> {
+    let recovery = &config.gate.recovery;
+    let Some(trip) = state.gate_trip.as_ref().filter(|_| recovery.is_enabled()) else {
+        return Ok(None);
+    };
+
+    let mut human_lines = 0;
+    if recovery.human_lines > 0 && trip.at < now {
+        let since_trip = GateWindow {
+            start: trip.at,
+            end: now,
+            week: IsoWeek::containing(gate_today(&config.gate, now)?),
+        };
+        let metrics = collect_weekly_metrics(git, config, state, &since_trip)?;
+        let own = config
+            .gate
+            .per_author
+            .then(|| git.user_email())
+            .flatten()
+            .and_then(|email| metrics.authors.get(&email.to_lowercase()));
+        human_lines = own.unwrap_or(&metrics.team).human_lines_added;
+    }
+
+    Ok(Some(RecoveryProgress {
+        tripped_at: trip.at,
+        drive_minutes: trip.drive_seconds_at(state.active_drive_session.as_ref(), now) / 60,
+        required_drive_minutes: recovery.drive_minutes,
+        human_lines,
+        required_human_lines: recovery.human_lines,
+    }))
+}
+
+/// Builds the report for an arbitrary ISO week (in the gate timezone), e.g. to
+/// regenerate a past week. `mode_after_evaluation` is the current mode of `state`.
+pub fn compute_report_for_week(
+    repo_root: &Path,
+    config: &AppConfig,
+    state: &State,
+    week: IsoWeek,
+) -> Result<WeeklyReport> {
+    let window = iso_week_window(&config.gate, week)?;
+    compute_report_for_window(repo_root, config, state, &window)
+}
+
+/// Builds the report for an explicit window.
+pub fn compute_report_for_window(
+    repo_root: &Path,
+    config: &AppConfig,
+    state: &State,
+    window: &GateWindow,
+) -> Result<WeeklyReport> {
+    let git = open_backend(repo_root, &config.git)?;
+    compute_report_for_window_with(git.as_ref(), config, state, window)
+}
+
+/// [`compute_report_for_window`] on an already opened backend.
+fn compute_report_for_window_with(
+    git: &dyn GitBackend,
+    config: &AppConfig,
+    state: &State,
+    window: &GateWindow,
+) -> Result<WeeklyReport> {
+    let now = Utc::now();
+    let mut metrics = collect_weekly_metrics(git, config, state, window)?;
+    let team = metrics.team.clone();
+    let metric_ratios = team.metric_ratios(&config.gate.weights);
+    let gate_metric = config.gate.metric;
+
+    let mut gate_ratio = metric_ratios.get(gate_metric);
+    let mut gate_author = None;
+    if config.gate.per_author {
+        match git.user_email() {
+            Some(email) => {
+                let email = email.to_lowercase();
+                gate_ratio = metrics
+                    .authors
+                    .get(&email)
+                    .map(|author| author.metric_ratios(&config.gate.weights).get(gate_metric))
+                    .unwrap_or(1.0);
+                metrics.notes.push(format!(
+                    "gate evaluated for author {email}: ratio {gate_ratio:.3}"
+                ));
+                gate_author = Some(email);
+            }
+            None => metrics.notes.push(
+                "per-author gate requested but git user.email is not set; using team ratio"
+                    .to_string(),
+            ),
+        }
+    }
+    let gate_passed = gate_ratio >= config.weekly_ratio_target;
+    Ok(WeeklyReport {
+        log_schema_version: config.log_schema_version.clone(),
+        generated_at: now,
+        year: window.week.year,
+        iso_week: window.week.week,
+        window_start: window.start,
+        window_end: window.end,
+        human_lines_added: team.human_lines_added,
+        ai_lines_added: team.ai_lines_added,
+        human_commit_count: team.human_commit_count,
+        ai_commit_count: team.ai_commit_count,
+        human_lines_deleted: team.human_lines_deleted,
+        ai_lines_deleted: team.ai_lines_deleted,
+        human_files_touched: team.human_files_touched,
+        ai_files_touched: team.ai_files_touched,
+        ratio: metric_ratios.added,
+        target_ratio: config.weekly_ratio_target,
+        gate_metric,
+        gate_ratio,
+        metric_ratios,
+        gate_passed,
+        mode_after_evaluation: state.mode.clone(),
+        evaluated_refs: metrics.evaluated_refs,
+        gate_author,
+        authors: metrics.authors.into_values().collect(),
+        recovery: None,
+        notes: metrics.notes,
+    })
+}
+
+/// Mode the gate requires for `ratio`: Normal at or above `target`, Coach
+/// down to `[gate].drive_below` (when set), Drive below that. Every threshold
+/// is raised by `margin`.
+fn gate_tier(gate: &GateConfig, ratio: f64, target: f64, margin: f64) -> Mode {
+    if ratio >= target + margin {
+        Mode::Normal
+    } else if gate
+        .drive_below
+        .is_some_and(|drive_below| ratio >= drive_below + margin)
+    {
+        Mode::Coach
+    } else {
+        Mode::Drive
+    }
+}
+
+/// Moves `state` to the tier the gate ratio calls for: Coach or Drive when it
+/// falls below a threshold, back towards Normal once it clears that threshold
+/// by `[gate].hysteresis`. Release to Normal also waits for `report.recovery`
+/// to complete. A manual Drive is kept until the gate requires Drive itself,
+/// which then takes it over; a lower gate tier is tracked underneath it in
+/// `state.gate_trip`, which records the trip. Does nothing but annotate the
+/// report while paused.
+///
+/// With `[gate].per_author`, `report.gate_ratio` already reflects the current
+/// user's own ratio rather than the team total.
+pub fn apply_gate(
+    state: &mut State,
+    report: &mut WeeklyReport,
+    gate: &GateConfig,
+    pause_active: bool,
+) {
+    if pause_active {
+        report
+            .notes
+            .push("gate evaluation bypassed due to active pause".to_string());
+        report.mode_after_evaluation = state.mode.clone();
+        return;
+    }
+
+    let required = gate_tier(gate, report.gate_ratio, report.target_ratio, 0.0);
+    let relaxed = gate_tier(
+        gate,
+        report.gate_ratio,
+        report.target_ratio,
+        gate.hysteresis,
+    );
+    // The tier the gate holds: the mode itself, or the one under a manual Drive.
+    let manual = state.drive_reason.as_deref() == Some("manual");
+    let held = if manual {
+        state.gate_trip.as_ref().map(|trip| trip.held.clone())
+    } else {
+        Some(state.mode.clone()).filter(|_| state.drive_reason.as_deref() == Some("gate"))
+    };
+    let gate_forced = held.is_some();
+    let held = held.unwrap_or_default();
+    let mut next = if gate_forced {
+        required.clone().max(held.clone().min(relaxed))
+    } else {
+        required.clone()
+    };
+    let pending_recovery = report
+        .recovery
+        .as_ref()
+        .filter(|recovery| gate_forced && next == Mode::Normal && !recovery.is_complete());
+    if pending_recovery.is_some() {
+        next = held;
+    }
+
+    // A manual Drive is left to `spp drive stop` unless the gate needs Drive too.
+    let held_manually = manual && next < state.mode;
+    if next == Mode::Normal {
+        state.gate_trip = None;
+        if !held_manually {
+            state.mode = Mode::Normal;
+            state.drive_reason = None;
+        }
+    } else {
+        let mode_name = next.as_str();
+        let note = if let Some(recovery) = pending_recovery {
+            format!(
+                "recovery incomplete ({} to go), {mode_name} mode held",
+                recovery.remaining().join(", ")
+            )
+        } else if next > required {
+            format!("ratio within hysteresis margin, {mode_name} mode held")
+        } else if next == Mode::Coach {
+            "ratio below target, coach mode enforced".to_string()
+        } else if gate.drive_below.is_some() {
+            "ratio below drive_below, forced drive mode".to_string()
+        } else {
+            "ratio below target, forced drive mode".to_string()
+        };
+        report.notes.push(note);
+        match state.gate_trip.as_mut().filter(|_| gate_forced) {
+            Some(trip) => {
+                trip.mode = trip.mode.clone().max(next.clone());
+                trip.held = next.clone();
+            }
+            None => {
+                state.gate_trip = Some(GateTrip {
+                    at: report.generated_at,
+                    mode: next.clone(),
+                    held: next.clone(),
+                    ratio: report.gate_ratio,
+                    target: report.target_ratio,
+                    drive_seconds: 0,
+                })
+            }
+        }
+        if held_manually {
+            report
+                .notes
+                .push("manual drive session keeps drive mode".to_string());
+        } else {
+            state.mode = next;
+            state.drive_reason = Some("gate".to_string());
+        }
+    }
+
+    report.mode_after_evaluation = state.mode.clone();
+}
+
+/// Puts `state` into Drive for `spp drive start`. The session is manual unless
+/// the gate already forces Drive; a gate-forced Coach stays tracked underneath.
+pub fn start_manual_drive(state: &mut State) {
+    let gate_drive = state.mode == Mode::Drive && state.drive_reason.as_deref() == Some("gate");
+    if !gate_drive {
+        if let Some(trip) = state
+            .gate_trip
+            .as_mut()
+            .filter(|_| state.drive_reason.as_deref() == Some("gate"))
+        {
+            trip.held = state.mode.clone();
+        }
+        state.drive_reason = Some("manual".to_string());
+    }
+    state.mode = Mode::Drive;
+}
+
+/// Ends a manual Drive for `spp drive stop`, falling back to the mode the gate
+/// holds underneath it, if any. A gate-forced Drive is left alone.
+pub fn stop_manual_drive(state: &mut State) {
+    if state.mode != Mode::Drive || state.drive_reason.as_deref() != Some("manual") {
+        return;
+    }
+    match state
+        .gate_trip
+        .as_ref()
+        .filter(|trip| trip.held > Mode::Normal)
+    {
+        Some(trip) => {
+            state.mode = trip.held.clone();
+            state.drive_reason = Some("gate".to_string());
+        }
+        None => {
+            state.mode = Mode::Normal;
+            state.drive_reason = None;
+            state.gate_trip = None;
+        }
+    }
+}
+
+/// The gate-enforced mode of `state` when it is stricter than `launched`, the
+/// mode a running Codex session was started in.
+pub fn gate_escalation(launched: &Mode, state: &State) -> Option<Mode> {
+    (state.drive_reason.as_deref() == Some("gate") && state.mode > *launched)
+        .then(|| state.mode.clone())
+}
+
+/// Provisional gate outcome if the uncommitted work were committed now.
+/// Only for display: never pass it to [`apply_gate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WipProjection {
+    /// Counted (filtered) lines and files of the uncommitted changes.
+    pub changes: CountedLines,
+    /// AI share of those lines from transcript matches; `0.0` means all human.
+    pub ai_share: f64,
+    /// `report.gate_metric` ratio including the uncommitted work.
+    pub gate_ratio: f64,
+    pub gate_passed: bool,
+}
+
+/// Projects `report` forward with staged and unstaged changes to tracked files
+/// (`git diff HEAD`), counted as one more commit of the gate author or team.
+/// Returns `None` when there is no `HEAD` or nothing is counted.
+pub fn project_worktree(
+    repo_root: &Path,
+    config: &AppConfig,
+    report: &WeeklyReport,
+) -> Result<Option<WipProjection>> {
+    let git = open_backend(repo_root, &config.git)?;
+    if git.current_commit().is_none() {
+        return Ok(None);
+    }
+    let changes = git.worktree_file_changes()?;
+    let counted = LineFilter::new(&config.metrics)?.count(git.as_ref(), &changes)?;
+    if counted.files == 0 {
+        return Ok(None);
+    }
+    let ai_share = infer_worktree_share(git.as_ref())?
+        .map(|suggestion| suggestion.ai_share)
+        .unwrap_or(0.0);
+
+    let mut base = report
+        .gate_author
+        .as_ref()
+        .and_then(|email| report.authors.iter().find(|author| &author.email == email))
+        .cloned()
+        .unwrap_or_else(|| AuthorBreakdown {
+            email: String::new(),
+            human_lines_added: report.human_lines_added,
+            ai_lines_added: report.ai_lines_added,
+            human_commit_count: report.human_commit_count,
+            ai_commit_count: report.ai_commit_count,
+            human_lines_deleted: report.human_lines_deleted,
+            ai_lines_deleted: report.ai_lines_deleted,
+            human_files_touched: report.human_files_touched,
+            ai_files_touched: report.ai_files_touched,
+            ratio: report.ratio,
+        });
+    base.record(ai_share, &counted);
+    let gate_ratio = base
+        .metric_ratios(&config.gate.weights)
+        .get(report.gate_metric);
+    Ok(Some(WipProjection {
+        changes: counted,
+        ai_share,
+        gate_ratio,
+        gate_passed: gate_ratio >= report.target_ratio,
+    }))
+}
+
+/// Attributes every non-merge commit in `window` and totals their changed lines
+/// as filtered by `[metrics]`, per owner as well when `[gate].per_author` is on.
+pub fn collect_weekly_metrics(
+    git: &dyn GitBackend,
+    config: &AppConfig,
+    state: &State,
+    window: &GateWindow,
+) -> Result<WeeklyMetrics> {
+    let attributor = Attributor::new(&config.attribution)?;
+    let mut filter = LineFilter::new(&config.metrics)?;
+    let mut filtered = CountedLines::default();
+    let refs = resolve_gate_refs(git, &config.gate.scope)?;
+    let mut metrics = WeeklyMetrics {
+        evaluated_refs: refs.names.clone(),
+        ..WeeklyMetrics::default()
+    };
+
+    let selected = window_commits(git, &config.gate, window, &refs)?;
+    metrics.notes.extend(skewed_commit_notes(
+        &selected.skewed,
+        config.gate.date_field,
+    ));
+    let records = load_commit_records(git, &selected.commits)?;
+    let shared_notes = git.commit_notes(Some(&config.attribution.notes_ref), &selected.commits)?;
+    let default_notes = git.commit_notes(None, &selected.commits)?;
+    let patch_ids = if attributor.matches_patch_ids(state) {
+        git.patch_ids(&selected.commits)?
+    } else {
+        HashMap::new()
+    };
+    filter.prefetch(git, records.values().flat_map(|record| record.files.iter()))?;
+
+    for commit in &selected.commits {
+        let record = records
+            .get(commit)
+            .with_context(|| format!("failed to load commit {commit}"))?;
+        let loaded = LoadedCommit {
+            record,
+            shared_note: shared_notes.get(commit).map(String::as_str),
+            notes: default_notes.get(commit).map(String::as_str),
+            patch_id: patch_ids.get(commit).map(String::as_str),
+        };
+        let attribution = attributor.classify_loaded(git, &loaded, state)?;
+        let ai_share = attribution.ai_share;
+        metrics.notes.extend(attribution.notes);
+        let counted = filter.count(git, &record.files)?;
+        filtered.absorb(counted);
+
+        if config.gate.per_author {
+            let email = owner_email(&record.author_email, &record.committer_email, config);
+            metrics
+                .authors
+                .entry(email.clone())
+                .or_insert_with(|| AuthorBreakdown {
+                    email,
+                    ratio: 1.0,
+                    ..AuthorBreakdown::default()
+                })
+                .record(ai_share, &counted);
+        }
+        metrics.team.record(ai_share, &counted);
+    }
+
+    metrics.notes.extend(filtered.notes());
+    Ok(metrics)
+}
+
+/// Revisions the gate walks for a [`GateScope`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateRefs {
+    pub revisions: Revisions,
+    /// What is recorded as `evaluated_refs` in the report.
+    pub names: Vec<String>,
+}
+
+/// Resolves `scope` against the repository; unknown refs are an error.
+pub fn resolve_gate_refs(git: &dyn GitBackend, scope: &GateScope) -> Result<GateRefs> {
+    let refs = match scope {
+        GateScope::Head => GateRefs {
+            revisions: Revisions::Head,
+            names: vec![git.current_branch()],
+        },
+        GateScope::All => GateRefs {
+            revisions: Revisions::All,
+            names: vec!["--all".to_string()],
+        },
+        GateScope::DefaultBranch => {
+            let branch = git.default_branch()?;
+            GateRefs {
+                revisions: Revisions::Refs(vec![branch.clone()]),
+                names: vec![branch],
+            }
+        }
+        GateScope::Refs(refs) => {
+            for name in refs {
+                git.resolve_commit(name)
+                    .with_context(|| format!("unknown ref `{name}` in [gate].scope"))?;
+            }
+            GateRefs {
+                revisions: Revisions::Refs(refs.clone()),
+                names: refs.clone(),
+            }
+        }
+    };
+    Ok(refs)
+}
+
+/// Commits selected for a window, plus those whose other timestamp lies far
+/// outside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowCommits {
+    /// Newest first.
+    pub commits: Vec<String>,
+    /// Committer timestamp of each of `commits`.
+    pub committed_at: HashMap<String, DateTime<Utc>>,
+    pub skewed: Vec<SkewedCommit>,
+}
+
+/// A commit whose author and committer dates disagree across the window
+/// boundary by more than `[gate].max_date_skew_hours`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkewedCommit {
+    pub commit: String,
+    pub authored_at: DateTime<Utc>,
+    pub committed_at: DateTime<Utc>,
+    /// Whether the commit is in [`WindowCommits::commits`].
+    pub counted: bool,
+}
+
+impl SkewedCommit {
+    fn note(&self, date_field: GateDateField) -> String {
+        let short = self.commit.get(..10).unwrap_or(&self.commit);
+        let authored = self.authored_at.format("%Y-%m-%d");
+        let committed = self.committed_at.format("%Y-%m-%d");
+        if self.counted {
+            format!(
+                "skewed commit {short} counted by {date_field} date (authored {authored}, committed {committed})"
+            )
+        } else {
+            format!(
+                "skewed commit {short} not counted: {date_field} date is outside the window (authored {authored}, committed {committed})"
+            )
+        }
+    }
+}
+
+/// Most skewed commits listed individually in report notes.
+const MAX_SKEW_NOTES: usize = 10;
+
+/// Report notes for `skewed`, listing at most [`MAX_SKEW_NOTES`] commits.
+pub fn skewed_commit_notes(skewed: &[SkewedCommit], date_field: GateDateField) -> Vec<String> {
+    let mut notes: Vec<String> = skewed
+        .iter()
+        .take(MAX_SKEW_NOTES)
+        .map(|commit| commit.note(date_field))
+        .collect();
+    if skewed.len() > MAX_SKEW_NOTES {
+        notes.push(format!(
+            "... and {} more skewed commits",
+            skewed.len() - MAX_SKEW_NOTES
+        ));
+    }
+    notes
+}
+
+/// Non-merge commits the gate counts in `window` on `refs`, by the
+/// `[gate].date_field` timestamp.
+///
+/// Every commit committed since the window start is read, since its author
+/// date may still fall inside the window.
+pub fn window_commits(
+    git: &dyn GitBackend,
+    gate: &GateConfig,
+    window: &GateWindow,
+    refs: &GateRefs,
+) -> Result<WindowCommits> {
+    let times = git.commit_times(&refs.revisions, window.start)?;
+
+    let skew = Duration::hours(gate.max_date_skew_hours as i64);
+    let in_window = |at: DateTime<Utc>| at >= window.start && at < window.end;
+    let far_outside = |at: DateTime<Utc>| at < window.start - skew || at >= window.end + skew;
+    let mut selected = WindowCommits::default();
+    for CommitTimes {
+        commit,
+        authored_at,
+        committed_at,
+    } in times
+    {
+        let (chosen, other) = match gate.date_field {
+            GateDateField::Committer => (committed_at, authored_at),
+            GateDateField::Author => (authored_at, committed_at),
+        };
+
+        let counted = in_window(chosen);
+        let skewed = if counted {
+            far_outside(other)
+        } else {
+            in_window(other) && (chosen - other).abs() > skew
+        };
+        if skewed {
+            selected.skewed.push(SkewedCommit {
+                commit: commit.clone(),
+                authored_at,
+                committed_at,
+                counted,
+            });
+        }
+        if counted {
+            selected.committed_at.insert(commit.clone(), committed_at);
+            selected.commits.push(commit);
+        }
+    }
+    Ok(selected)
+}
+
+/// Splits a commit's `count` (added or deleted lines, touched files) into
+/// `(human, ai)` by `ai_share`, rounding half a unit toward the human.
+fn split_by_share(count: u64, ai_share: f64) -> (u64, u64) {
+    let exact = count as f64 * ai_share.clamp(0.0, 1.0);
+    let ai = ((exact - 0.5).ceil().max(0.0) as u64).min(count);
+    (count - ai, ai)
+}
+
+/// A commit counts as an AI commit when more than half of it is AI work.
+fn is_ai_commit(ai_share: f64) -> bool {
+    ai_share > 0.5
+}
+
+/// `numerator / denominator`, treating an empty denominator as a perfect `1.0`.
+pub fn safe_ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        1.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Human share of commits in `report`.
+pub fn commit_ratio(report: &WeeklyReport) -> f64 {
+    let total = report.human_commit_count + report.ai_commit_count;
+    safe_ratio(report.human_commit_count, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::git::git_output;
+    use crate::state::Actor;
+    use crate::test_support::{commit_file, init_git_repo, sample_weekly_report, MemoryRepo};
+    use crate::week::IsoWeek;
+
+    #[test]
+    fn safe_ratio_returns_one_when_denominator_zero() {
+        assert_eq!(safe_ratio(0, 0), 1.0);
+        assert_eq!(safe_ratio(3, 0), 1.0);
+    }
+
+    #[test]
+    fn commit_ratio_uses_human_commits() {
+        let report = sample_weekly_report();
+        assert!((commit_ratio(&report) - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn mixed_commits_split_lines_evenly() {
+        assert_eq!(split_by_share(5, Actor::Mixed.default_ai_share()), (3, 2));
+        assert_eq!(split_by_share(5, Actor::Ai.default_ai_share()), (0, 5));
+    }
+
+    #[test]
+    fn fractional_share_splits_lines_proportionally() {
+        assert_eq!(split_by_share(10, 0.4), (6, 4));
+        assert_eq!(split_by_share(100, 0.29), (71, 29));
+        assert_eq!(split_by_share(7, 0.0), (7, 0));
+        assert!(!is_ai_commit(0.5));
+        assert!(is_ai_commit(0.6));
+    }
+
+    #[test]
+    fn author_breakdown_record_updates_ratio() {
+        let mut author = AuthorBreakdown {
+            email: "dev@example.com".to_string(),
+            ratio: 1.0,
+            ..AuthorBreakdown::default()
+        };
+        let lines = |added| CountedLines {
+            added,
+            ..CountedLines::default()
+        };
+        author.record(Actor::Human.default_ai_share(), &lines(30));
+        author.record(Actor::Ai.default_ai_share(), &lines(10));
+        assert_eq!(author.human_commit_count, 1);
+        assert_eq!(author.ai_commit_count, 1);
+        assert!((author.ratio - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn apply_gate_picks_the_tier_for_the_ratio() {
+        let gate = GateConfig {
+            drive_below: Some(0.5),
+            ..GateConfig::default()
+        };
+        let evaluate = |state: &mut State, ratio: f64| {
+            let mut report = sample_weekly_report();
+            report.gate_ratio = ratio;
+            report.gate_passed = ratio >= report.target_ratio;
+            apply_gate(state, &mut report, &gate, false);
+            report
+        };
+
+        let mut state = State::default();
+        let report = evaluate(&mut state, 0.6);
+        assert_eq!(state.mode, Mode::Coach);
+        assert_eq!(state.drive_reason.as_deref(), Some("gate"));
+        assert_eq!(report.mode_after_evaluation, Mode::Coach);
+        assert!(report
+            .notes
+            .contains(&"ratio below target, coach mode enforced".to_string()));
+
+        evaluate(&mut state, 0.4);
+        assert_eq!(state.mode, Mode::Drive);
+        evaluate(&mut state, 0.55);
+        assert_eq!(state.mode, Mode::Coach);
+        evaluate(&mut state, 0.7);
+        assert_eq!(state.mode, Mode::Normal);
+        assert_eq!(state.drive_reason, None);
+
+        let mut state = State::default();
+        evaluate(&mut state, 0.4);
+        assert_eq!(state.mode, Mode::Drive);
+        let without_tiers = GateConfig::default();
+        let mut report = sample_weekly_report();
+        let mut state = State::default();
+        apply_gate(&mut state, &mut report, &without_tiers, false);
+        assert_eq!(state.mode, Mode::Drive);
+    }
+
+    #[test]
+    fn apply_gate_holds_a_stricter_mode_within_the_hysteresis_margin() {
+        let gate = GateConfig {
+            drive_below: Some(0.5),
+            hysteresis: 0.05,
+            ..GateConfig::default()
+        };
+        let evaluate = |state: &mut State, ratio: f64| {
+            let mut report = sample_weekly_report();
+            report.gate_ratio = ratio;
+            report.gate_passed = ratio >= report.target_ratio;
+            apply_gate(state, &mut report, &gate, false);
+            report
+        };
+
+        let mut state = State::default();
+        evaluate(&mut state, 0.45);
+        assert_eq!(state.mode, Mode::Drive);
+        let report = evaluate(&mut state, 0.52);
+        assert_eq!(state.mode, Mode::Drive);
+        assert!(report
+            .notes
+            .contains(&"ratio within hysteresis margin, drive mode held".to_string()));
+        evaluate(&mut state, 0.56);
+        assert_eq!(state.mode, Mode::Coach);
+        evaluate(&mut state, 0.72);
+        assert_eq!(state.mode, Mode::Coach);
+        evaluate(&mut state, 0.75);
+        assert_eq!(state.mode, Mode::Normal);
+
+        // Tightening is immediate: no margin on the way down.
+        evaluate(&mut state, 0.69);
+        assert_eq!(state.mode, Mode::Coach);
+    }
+
+    #[test]
+    fn apply_gate_keeps_a_manual_drive_unless_the_gate_needs_it() {
+        let gate = GateConfig {
+            drive_below: Some(0.5),
+            ..GateConfig::default()
+        };
+        let mut state = State {
+            mode: Mode::Drive,
+            drive_reason: Some("manual".to_string()),
+            ..State::default()
+        };
+        let mut report = sample_weekly_report();
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.drive_reason.as_deref(), Some("manual"));
+
+        report.gate_ratio = 0.3;
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.drive_reason.as_deref(), Some("gate"));
+    }
+
+    #[test]
+    fn manual_drive_over_a_gate_coach_survives_the_gate_and_stops_back_to_coach() {
+        let gate = GateConfig {
+            drive_below: Some(0.5),
+            ..GateConfig::default()
+        };
+        let mut state = State::default();
+        let mut report = sample_weekly_report();
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Coach);
+        let tripped_at = state.gate_trip.as_ref().map(|trip| trip.at);
+
+        start_manual_drive(&mut state);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.drive_reason.as_deref(), Some("manual"));
+
+        let mut report = sample_weekly_report();
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.drive_reason.as_deref(), Some("manual"));
+        assert_eq!(report.mode_after_evaluation, Mode::Drive);
+        assert_eq!(state.gate_trip.as_ref().map(|trip| trip.at), tripped_at);
+        assert_eq!(gate_escalation(&Mode::Drive, &state), None);
+
+        stop_manual_drive(&mut state);
+        assert_eq!(state.mode, Mode::Coach);
+        assert_eq!(state.drive_reason.as_deref(), Some("gate"));
+        assert_eq!(state.gate_trip.as_ref().map(|trip| trip.at), tripped_at);
+
+        // Once the gate releases underneath, stopping returns to Normal.
+        start_manual_drive(&mut state);
+        let mut report = sample_weekly_report();
+        report.gate_ratio = 0.8;
+        report.gate_passed = true;
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.gate_trip, None);
+        stop_manual_drive(&mut state);
+        assert_eq!(state.mode, Mode::Normal);
+        assert_eq!(state.drive_reason, None);
+
+        // A gate-forced Drive is not the session's to undo.
+        let mut report = sample_weekly_report();
+        report.gate_ratio = 0.3;
+        apply_gate(&mut state, &mut report, &gate, false);
+        start_manual_drive(&mut state);
+        stop_manual_drive(&mut state);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.drive_reason.as_deref(), Some("gate"));
+    }
+
+    #[test]
+    fn gate_escalation_reports_only_stricter_gate_modes() {
+        let gate = GateConfig {
+            drive_below: Some(0.5),
+            ..GateConfig::default()
+        };
+        let mut state = State::default();
+        let mut report = sample_weekly_report();
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(gate_escalation(&Mode::Normal, &state), Some(Mode::Coach));
+        assert_eq!(gate_escalation(&Mode::Coach, &state), None);
+
+        report.gate_ratio = 0.3;
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(gate_escalation(&Mode::Coach, &state), Some(Mode::Drive));
+
+        let manual = State {
+            mode: Mode::Drive,
+            drive_reason: Some("manual".to_string()),
+            ..State::default()
+        };
+        assert_eq!(gate_escalation(&Mode::Normal, &manual), None);
+    }
+
+    #[test]
+    fn apply_gate_records_the_trip_and_waits_for_recovery() {
+        let gate = GateConfig::default();
+        let mut state = State::default();
+        let mut report = sample_weekly_report();
+        apply_gate(&mut state, &mut report, &gate, false);
+        let trip = state.gate_trip.clone().expect("trip recorded");
+        assert_eq!(trip.at, report.generated_at);
+        assert_eq!(trip.mode, Mode::Drive);
+        assert!((trip.ratio - 0.6).abs() < 1e-9);
+
+        let mut progress = RecoveryProgress {
+            tripped_at: trip.at,
+            drive_minutes: 10,
+            required_drive_minutes: 30,
+            human_lines: 200,
+            required_human_lines: 100,
+        };
+        let mut report = sample_weekly_report();
+        report.gate_ratio = 0.8;
+        report.gate_passed = true;
+        report.recovery = Some(progress.clone());
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Drive);
+        assert_eq!(state.gate_trip.as_ref().map(|trip| trip.at), Some(trip.at));
+        assert!(report.notes.contains(
+            &"recovery incomplete (20 Drive minutes to go), drive mode held".to_string()
+        ));
+
+        progress.drive_minutes = 30;
+        report.recovery = Some(progress);
+        apply_gate(&mut state, &mut report, &gate, false);
+        assert_eq!(state.mode, Mode::Normal);
+        assert_eq!(state.gate_trip, None);
+    }
+
+    #[test]
+    fn gate_trip_counts_drive_time_after_the_trip_only() {
+        let at = chrono::Utc::now();
+        let mut trip = GateTrip {
+            at,
+            mode: Mode::Drive,
+            held: Mode::Drive,
+            ratio: 0.5,
+            target: 0.7,
+            drive_seconds: 0,
+        };
+        trip.record_drive_session(
+            at - chrono::Duration::minutes(20),
+            at + chrono::Duration::minutes(5),
+        );
+        assert_eq!(trip.drive_seconds, 300);
+        assert_eq!(
+            trip.drive_seconds_at(None, at + chrono::Duration::hours(1)),
+            300
+        );
+    }
+
+    #[test]
+    fn recovery_progress_counts_human_lines_since_the_trip() -> Result<()> {
+        let now = chrono::Utc::now();
+        let mut repo = MemoryRepo::new("spp-memory-recovery")?;
+        repo.commit(
+            "dev@example.com",
+            "feat: before",
+            &[("a.rs", 50)],
+            now - chrono::Duration::hours(3),
+        );
+        repo.commit(
+            "dev@example.com",
+            "feat: after",
+            &[("b.rs", 25)],
+            now - chrono::Duration::hours(1),
+        );
+
+        let mut config = AppConfig::default();
+        let state = State {
+            gate_trip: Some(GateTrip {
+                at: now - chrono::Duration::hours(2),
+                mode: Mode::Drive,
+                held: Mode::Drive,
+                ratio: 0.5,
+                target: 0.7,
+                drive_seconds: 600,
+            }),
+            ..State::default()
+        };
+        assert_eq!(recovery_progress(&repo, &config, &state, now)?, None);
+
+        config.gate.recovery.human_lines = 40;
+        let progress = recovery_progress(&repo, &config, &state, now)?.expect("progress");
+        assert_eq!(progress.human_lines, 25);
+        assert_eq!(progress.drive_minutes, 10);
+        assert_eq!(progress.remaining(), vec!["15 human lines".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn metric_ratios_cover_every_metric_and_the_weighted_blend() {
+        let human = ActorCounts {
+            lines_added: 20,
+            lines_deleted: 60,
+            files_touched: 3,
+            commits: 3,
+        };
+        let ai = ActorCounts {
+            lines_added: 80,
+            lines_deleted: 0,
+            files_touched: 1,
+            commits: 1,
+        };
+        let weights = MetricWeights {
+            added: 1.0,
+            commits: 1.0,
+            ..MetricWeights::default()
+        };
+        let ratios = metric_ratios(human, ai, &weights);
+        assert!((ratios.added - 0.2).abs() < 1e-9);
+        assert!((ratios.added_deleted - 0.5).abs() < 1e-9);
+        assert_eq!(ratios.net, 0.0);
+        assert!((ratios.files_touched - 0.75).abs() < 1e-9);
+        assert!((ratios.commits - 0.75).abs() < 1e-9);
+        assert!((ratios.weighted - 0.475).abs() < 1e-9);
+        assert_eq!(ratios.get(GateMetric::Commits), ratios.commits);
+
+        let unweighted = MetricWeights {
+            added: 0.0,
+            ..MetricWeights::default()
+        };
+        assert_eq!(metric_ratios(human, ai, &unweighted).weighted, ratios.added);
+    }
+
+    #[test]
+    fn project_worktree_counts_uncommitted_lines_as_human() -> Result<()> {
+        let repo = init_git_repo("spp-wip")?;
+        let root = repo.path();
+        commit_file(root, "src/lib.rs", "fn a() {}\n", "feat: a")?;
+
+        let config = AppConfig::default();
+        let mut report = sample_weekly_report();
+        report.human_lines_added = 50;
+        report.ai_lines_added = 50;
+        assert_eq!(project_worktree(root, &config, &report)?, None);
+
+        std::fs::write(root.join("src/lib.rs"), "fn a() {}\n".repeat(101))?;
+        let projection = project_worktree(root, &config, &report)?.expect("uncommitted work");
+        assert_eq!(projection.changes.added, 100);
+        assert_eq!(projection.ai_share, 0.0);
+        assert!((projection.gate_ratio - 0.75).abs() < 1e-9);
+        assert!(projection.gate_passed);
+        Ok(())
+    }
+
+    #[test]
+    fn gate_scope_selects_history_independent_of_checkout() -> Result<()> {
+        let repo = init_git_repo("spp-scope")?;
+        let root = repo.path();
+        let base = commit_file(root, "a.txt", "a\n", "feat: base")?;
+        git_output(root, &["checkout", "-q", "-b", "topic"])?;
+        let topic = commit_file(root, "b.txt", "b\n", "feat: topic")?;
+
+        let now = chrono::Utc::now();
+        let window = GateWindow {
+            start: now - chrono::Duration::days(1),
+            end: now + chrono::Duration::days(1),
+            week: IsoWeek::containing(now.date_naive()),
+        };
+        let git = SubprocessBackend::new(root);
+        let commits = |scope: GateScope| -> Result<(Vec<String>, Vec<String>)> {
+            let refs = resolve_gate_refs(&git, &scope)?;
+            let gate = GateConfig::default();
+            Ok((
+                window_commits(&git, &gate, &window, &refs)?.commits,
+                refs.names,
+            ))
+        };
+
+        assert_eq!(
+            commits(GateScope::Head)?,
+            (vec![topic.clone(), base.clone()], vec!["topic".to_string()])
+        );
+        assert_eq!(
+            commits(GateScope::DefaultBranch)?,
+            (vec![base.clone()], vec!["main".to_string()])
+        );
+        git_output(root, &["notes", "--ref=spp", "add", "-m", "spp:ai", &base])?;
+        git_output(root, &["checkout", "-q", "main"])?;
+        let mut all = commits(GateScope::All)?.0;
+        all.sort();
+        let mut expected = vec![topic, base];
+        expected.sort();
+        assert_eq!(all, expected);
+        assert!(resolve_gate_refs(&git, &GateScope::Refs(vec!["nope".to_string()])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn date_field_selects_commits_and_reports_skew() -> Result<()> {
+        let repo = init_git_repo("spp-skew")?;
+        let root = repo.path();
+        let fresh = commit_file(root, "a.txt", "a\n", "feat: fresh")?;
+        std::fs::write(root.join("b.txt"), "b\n")?;
+        git_output(root, &["add", "b.txt"])?;
+        let status = std::process::Command::new("git")
+            .args(["commit", "-q", "-m", "feat: old work"])
+            .env("GIT_AUTHOR_DATE", "2020-01-01T00:00:00Z")
+            .current_dir(root)
+            .status()?;
+        assert!(status.success());
+        let old = git_output(root, &["rev-parse", "HEAD"])?.trim().to_string();
+
+        let now = chrono::Utc::now();
+        let window = GateWindow {
+            start: now - chrono::Duration::days(1),
+            end: now + chrono::Duration::days(1),
+            week: IsoWeek::containing(now.date_naive()),
+        };
+        let git = SubprocessBackend::new(root);
+        let refs = resolve_gate_refs(&git, &GateScope::Head)?;
+
+        let mut gate = GateConfig::default();
+        let by_committer = window_commits(&git, &gate, &window, &refs)?;
+        assert_eq!(by_committer.commits, vec![old.clone(), fresh.clone()]);
+        assert_eq!(by_committer.skewed.len(), 1);
+        assert!(by_committer.skewed[0].counted);
+
+        gate.date_field = GateDateField::Author;
+        let by_author = window_commits(&git, &gate, &window, &refs)?;
+        assert_eq!(by_author.commits, vec![fresh]);
+        assert_eq!(by_author.skewed.len(), 1);
+        assert!(!by_author.skewed[0].counted);
+        let notes = skewed_commit_notes(&by_author.skewed, gate.date_field);
+        assert_eq!(
+            notes,
+            vec![format!(
+                "skewed commit {} not counted: author date is outside the window (authored 2020-01-01, committed {})",
+                &old[..10],
+                by_author.skewed[0].committed_at.format("%Y-%m-%d")
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn collect_weekly_metrics_reads_an_in_memory_backend() -> Result<()> {
+        let now = chrono::Utc::now();
+        let mut repo = MemoryRepo::new("spp-memory-gate")?;
+        repo.commit(
+            "dev@example.com",
+            "feat: old",
+            &[("a.rs", 50)],
+            now - chrono::Duration::days(9),
+        );
+        repo.commit(
+            "dev@example.com",
+            "feat: human",
+            &[("a.rs", 30), ("b.rs", 10)],
+            now,
+        );
+        repo.commit(
+            "dev@example.com",
+            "feat: codex\n\nCo-Authored-By: Codex <codex@openai.com>",
+            &[("c.rs", 20)],
+            now,
+        );
+        let shared = repo.commit("peer@example.com", "feat: peer", &[("d.rs", 40)], now);
+        let malformed = repo.commit(
+            "dev@example.com",
+            "feat: typo\n\nSPP-AI-Share: 1.5",
+            &[("e.rs", 10)],
+            now,
+        );
+        repo.notes.get_mut().insert(
+            "refs/notes/spp".to_string(),
+            [(shared, "SPP-AI-Share: 0.5".to_string())].into(),
+        );
+
+        let mut config = AppConfig::default();
+        config.gate.per_author = true;
+        let window = GateWindow {
+            start: now - chrono::Duration::days(1),
+            end: now + chrono::Duration::days(1),
+            week: IsoWeek::containing(now.date_naive()),
+        };
+        let metrics = collect_weekly_metrics(&repo, &config, &State::default(), &window)?;
+        assert_eq!(metrics.evaluated_refs, vec!["main"]);
+        assert_eq!(metrics.team.human_lines_added, 40 + 20 + 10);
+        assert_eq!(metrics.team.ai_lines_added, 20 + 20);
+        assert_eq!(metrics.team.human_commit_count, 3);
+        assert!(metrics.notes.contains(&format!(
+            "ignored invalid SPP-AI-Share trailer in commit {}: AI share `1.5` must be between 0 and 1",
+            &malformed[..10]
+        )));
+        assert_eq!(metrics.authors["peer@example.com"].ai_lines_added, 20);
+        assert_eq!(metrics.authors["dev@example.com"].human_files_touched, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn commit_ratio_returns_one_when_total_commit_is_zero() {
+        let mut report = sample_weekly_report();
+        report.human_commit_count = 0;
+        report.ai_commit_count = 0;
+        assert_eq!(commit_ratio(&report), 1.0);
+    }
+}
@@ -0,0 +1,243 @@
+//! Thin wrappers around the `git` executable; see [`crate::backend`] for the
+//! queries that can also go through libgit2.
+
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Resolves the top-level directory of the repository containing the current
+/// directory. Without a `git` executable this needs the `libgit2` feature.
+pub fn detect_repo_root() -> Result<PathBuf> {
+    let out = match Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+    {
+        Ok(out) => out,
+        #[cfg(feature = "libgit2")]
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return crate::backend::Libgit2Backend::discover_root(&std::env::current_dir()?);
+        }
+        Err(err) => return Err(exec_error(err, &["rev-parse", "--show-toplevel"])),
+    };
+    if !out.status.success() {
+        bail!("current directory is not a git repository");
+    }
+    let root = String::from_utf8(out.stdout)?.trim().to_string();
+    if root.is_empty() {
+        bail!("failed to resolve git repo root");
+    }
+    Ok(PathBuf::from(root))
+}
+
+/// Explains a failure to start `git`, pointing at PATH when it is missing.
+fn exec_error(err: io::Error, args: &[&str]) -> anyhow::Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        anyhow!(
+            "`git` was not found on PATH (needed for git {:?}); install git, or build spp \
+             with `--features libgit2` and set `[git].backend = \"libgit2\"`",
+            args
+        )
+    } else {
+        anyhow!(err).context(format!("failed to execute git {:?}", args))
+    }
+}
+
+/// Fails unless the `git` executable can be run; `operation` names what needs
+/// it, since no [`crate::backend::GitBackend`] talks to remotes.
+pub fn require_git_executable(operation: &str) -> Result<()> {
+    match Command::new("git").arg("--version").output() {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => bail!(
+            "{operation} requires the `git` executable, also with `[git].backend = \"libgit2\"`; \
+             install git and put it on PATH"
+        ),
+        Err(err) => Err(anyhow!(err).context("failed to execute git --version")),
+    }
+}
+
+/// Runs `git <args>` in `repo_root` and returns stdout, failing on a non-zero exit.
+pub fn git_output(repo_root: &Path, args: &[&str]) -> Result<String> {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .output()
+        .map_err(|err| exec_error(err, args))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        bail!("git {:?} failed: {}", args, stderr);
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+/// Like [`git_output`], writing `input` to git's stdin (for `--stdin` options).
+pub fn git_output_with_stdin(repo_root: &Path, args: &[&str], input: &str) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| exec_error(err, args))?;
+    let mut stdin = child.stdin.take().context("git stdin unavailable")?;
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let out = child
+        .wait_with_output()
+        .with_context(|| format!("failed to wait for git {:?}", args))?;
+    writer
+        .join()
+        .map_err(|_| anyhow!("git stdin writer panicked"))?
+        .with_context(|| format!("failed to write stdin of git {:?}", args))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        bail!("git {:?} failed: {}", args, stderr);
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+/// Current branch name, or `"unknown"` when it cannot be resolved.
+pub fn current_branch(repo_root: &Path) -> String {
+    git_output(repo_root, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .unwrap_or_else(|_| "unknown".to_string())
+        .trim()
+        .to_string()
+}
+
+/// Full hash of `HEAD`, if any commit exists.
+pub fn current_commit(repo_root: &Path) -> Option<String> {
+    git_output(repo_root, &["rev-parse", "HEAD"])
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// `git config user.email`, if configured.
+pub fn current_user_email(repo_root: &Path) -> Option<String> {
+    git_output(repo_root, &["config", "user.email"])
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Full hash of the commit `rev` points at.
+pub fn resolve_commit(repo_root: &Path, rev: &str) -> Result<String> {
+    let commit = git_output(
+        repo_root,
+        &["rev-parse", "--verify", &format!("{rev}^{{commit}}")],
+    )?
+    .trim()
+    .to_string();
+    if commit.is_empty() {
+        bail!("commit not found: {rev}");
+    }
+    Ok(commit)
+}
+
+/// Stable `git patch-id`s of the non-merge commits selected by `git log <log_args>`,
+/// as `(patch_id, commit)` pairs. Commits without a diff have no patch-id.
+pub fn patch_ids(repo_root: &Path, log_args: &[&str]) -> Result<Vec<(String, String)>> {
+    run_patch_ids(repo_root, log_args, None)
+}
+
+/// Like [`patch_ids`] for exactly `commits`, read in one `git log --stdin` pass.
+pub fn patch_ids_of(repo_root: &Path, commits: &[String]) -> Result<Vec<(String, String)>> {
+    if commits.is_empty() {
+        return Ok(Vec::new());
+    }
+    run_patch_ids(
+        repo_root,
+        &["--no-walk=unsorted", "--stdin"],
+        Some(commits.join("\n") + "\n"),
+    )
+}
+
+fn run_patch_ids(
+    repo_root: &Path,
+    log_args: &[&str],
+    input: Option<String>,
+) -> Result<Vec<(String, String)>> {
+    let mut log = Command::new("git")
+        .args([
+            "log",
+            "-p",
+            "--no-merges",
+            "--no-color",
+            "--format=commit %H",
+        ])
+        .args(log_args)
+        .current_dir(repo_root)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| exec_error(err, &["log"]))?;
+    let writer = match (input, log.stdin.take()) {
+        (Some(input), Some(mut stdin)) => Some(std::thread::spawn(move || {
+            stdin.write_all(input.as_bytes())
+        })),
+        _ => None,
+    };
+    let diff = log
+        .stdout
+        .take()
+        .with_context(|| "git log produced no stdout")?;
+    let out = Command::new("git")
+        .args(["patch-id", "--stable"])
+        .current_dir(repo_root)
+        .stdin(diff)
+        .output()
+        .with_context(|| "failed to execute git patch-id")?;
+    let status = log.wait()?;
+    if let Some(writer) = writer {
+        writer
+            .join()
+            .map_err(|_| anyhow!("git stdin writer panicked"))?
+            .with_context(|| "failed to write stdin of git log")?;
+    }
+    if !status.success() {
+        bail!("git log {:?} failed", log_args);
+    }
+    if !out.status.success() {
+        bail!(
+            "git patch-id failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(out.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(patch_id, commit)| (patch_id.to_string(), commit.trim().to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn patch_ids_match_across_cherry_picks() -> Result<()> {
+        let repo = init_git_repo("spp-git-patch-id")?;
+        let root = repo.path();
+        commit_file(root, "base", "base\n", "base")?;
+        git_output(root, &["checkout", "-q", "-b", "topic"])?;
+        let original = commit_file(root, "feature", "feature\n", "feature")?;
+        git_output(root, &["checkout", "-q", "main"])?;
+        commit_file(root, "other", "other\n", "other")?;
+        git_output(root, &["cherry-pick", &original])?;
+        let picked = current_commit(root).expect("HEAD exists");
+
+        let original_id = patch_ids(root, &["-1", &original])?;
+        let picked_id = patch_ids(root, &["-1", &picked])?;
+        assert_eq!(original_id.len(), 1);
+        assert_eq!(original_id[0].0, picked_id[0].0);
+        assert_ne!(original, picked);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! Shell-style glob patterns (`*`, `**`, `?`) used by config rules.
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+/// A compiled glob. `*` and `?` stop at `/`; `**` crosses directories.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    raw: String,
+    regex: Regex,
+}
+
+impl Glob {
+    /// Compiles a case-sensitive glob.
+    pub fn new(raw: &str) -> Result<Self> {
+        Self::build(raw, false)
+    }
+
+    /// Compiles a glob that ignores ASCII case, for emails and ref names.
+    pub fn case_insensitive(raw: &str) -> Result<Self> {
+        Self::build(raw, true)
+    }
+
+    fn build(raw: &str, case_insensitive: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(&glob_to_regex(raw))
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("invalid glob `{raw}`"))?;
+        Ok(Self {
+            raw: raw.to_string(),
+            regex,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Whether the whole of `text` matches.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Like [`Glob::is_match`] for repo-relative paths, except that a pattern
+    /// without `/` is matched against the file name alone (as in `.gitignore`).
+    pub fn matches_path(&self, path: &str) -> bool {
+        if self.raw.contains('/') {
+            return self.is_match(path);
+        }
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.is_match(name)
+    }
+}
+
+fn glob_to_regex(raw: &str) -> String {
+    let mut out = String::from("^");
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            other => out.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_single_and_double_stars() -> Result<()> {
+        let glob = Glob::new("src/**/*.rs")?;
+        assert!(glob.is_match("src/main.rs"));
+        assert!(glob.is_match("src/a/b/lib.rs"));
+        assert!(!glob.is_match("tests/main.rs"));
+
+        let name_only = Glob::new("*.lock")?;
+        assert!(name_only.matches_path("crates/spp/Cargo.lock"));
+        assert!(!name_only.is_match("crates/spp/Cargo.lock"));
+
+        let email = Glob::case_insensitive("*[bot]@users.noreply.github.com")?;
+        assert!(email.is_match("Copilot[bot]@users.noreply.github.com"));
+        assert!(!email.is_match("dev@example.com"));
+        Ok(())
+    }
+}
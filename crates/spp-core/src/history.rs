@@ -0,0 +1,164 @@
+//! Bulk loading of the commit metadata the gate needs.
+//!
+//! Emails, message, trailers and per-file stats of every window commit come
+//! from one [`GitBackend::commit_records`] call (a single `git log --no-walk
+//! --stdin --numstat` pass for the subprocess backend) and are cached per hash
+//! in `.codex-spp/cache/`, since they never change for a given commit. Git
+//! notes can change at any time, so they are read through
+//! [`GitBackend::commit_notes`] and never cached.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::GitBackend;
+use crate::metrics::FileChange;
+use crate::paths::CACHE_DIR;
+
+/// Bumped whenever [`CommitRecord`] changes shape; older cache files are reloaded.
+const CACHE_VERSION: u32 = 2;
+
+/// Immutable metadata of one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitRecord {
+    pub commit: String,
+    pub author_email: String,
+    pub committer_email: String,
+    pub subject: String,
+    /// Full commit message, for body matchers and change ids.
+    pub message: String,
+    /// Unfolded `Key: value` trailer lines.
+    pub trailers: Vec<String>,
+    pub files: Vec<FileChange>,
+}
+
+impl CommitRecord {
+    /// Added lines over all files, binary files counting none.
+    pub fn added_lines(&self) -> u64 {
+        self.files.iter().filter_map(|file| file.added).sum()
+    }
+
+    /// Changed paths, new names for renames.
+    pub fn paths(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.path.clone()).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRecord {
+    version: u32,
+    record: CommitRecord,
+}
+
+/// Records of `commits`, from the cache where possible and otherwise from a
+/// single [`GitBackend::commit_records`] call whose results are cached.
+pub fn load_commit_records(
+    git: &dyn GitBackend,
+    commits: &[String],
+) -> Result<HashMap<String, CommitRecord>> {
+    let repo_root = git.repo_root();
+    let mut records = HashMap::new();
+    let mut missing = Vec::new();
+    for commit in commits {
+        match read_cached(repo_root, commit) {
+            Some(record) => {
+                records.insert(commit.clone(), record);
+            }
+            None => missing.push(commit.clone()),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(records);
+    }
+
+    for record in git.commit_records(&missing)? {
+        write_cached(repo_root, &record)?;
+        records.insert(record.commit.clone(), record);
+    }
+    Ok(records)
+}
+
+/// Removes every cached commit record.
+pub fn clear_commit_cache(repo_root: &Path) -> Result<()> {
+    let dir = repo_root.join(CACHE_DIR);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("failed to remove {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+fn cache_path(repo_root: &Path, commit: &str) -> std::path::PathBuf {
+    repo_root.join(CACHE_DIR).join(format!("{commit}.json"))
+}
+
+/// A cached record, or `None` when absent, unreadable or from an older version.
+fn read_cached(repo_root: &Path, commit: &str) -> Option<CommitRecord> {
+    let text = fs::read_to_string(cache_path(repo_root, commit)).ok()?;
+    let cached: CachedRecord = serde_json::from_str(&text).ok()?;
+    (cached.version == CACHE_VERSION && cached.record.commit == commit).then_some(cached.record)
+}
+
+fn write_cached(repo_root: &Path, record: &CommitRecord) -> Result<()> {
+    let path = cache_path(repo_root, &record.commit);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cached = CachedRecord {
+        version: CACHE_VERSION,
+        record: record.clone(),
+    };
+    fs::write(&path, serde_json::to_string(&cached)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::git::git_output;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn records_load_in_one_pass_and_come_back_from_the_cache() -> Result<()> {
+        let repo = init_git_repo("spp-history")?;
+        let root = repo.path();
+        let first = commit_file(root, "a.txt", "a\nb\n", "feat: a\n\nSPP-AI-Share: 0.5")?;
+        git_output(root, &["mv", "a.txt", "c.txt"])?;
+        let second = commit_file(root, "d.txt", "d\n", "chore: rename")?;
+        git_output(
+            root,
+            &["notes", "--ref=spp", "add", "-m", "SPP-Actor: ai", &second],
+        )?;
+
+        let git = SubprocessBackend::new(root);
+        let commits = vec![second.clone(), first.clone()];
+        let records = load_commit_records(&git, &commits)?;
+        assert_eq!(records[&first].trailers, vec!["SPP-AI-Share: 0.5"]);
+        assert_eq!(records[&first].files[0].added, Some(2));
+        assert_eq!(records[&second].subject, "chore: rename");
+        assert_eq!(
+            records[&first].message.trim_end(),
+            "feat: a\n\nSPP-AI-Share: 0.5"
+        );
+        assert_eq!(records[&second].author_email, "dev@example.com");
+        let mut paths = records[&second].paths();
+        paths.sort();
+        assert_eq!(paths, vec!["c.txt", "d.txt"]);
+        assert!(root.join(CACHE_DIR).join(format!("{first}.json")).exists());
+
+        let cached = load_commit_records(&git, &commits)?;
+        assert_eq!(cached, records);
+
+        let notes = git.commit_notes(Some("refs/notes/spp"), &commits)?;
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[&second], "SPP-Actor: ai");
+        assert!(git.commit_notes(None, &commits)?.is_empty());
+
+        clear_commit_cache(root)?;
+        assert!(!root.join(CACHE_DIR).exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+//! `spp attrib infer`: estimating a commit's AI share from transcript output.
+//!
+//! Added lines of each commit are compared with lines Codex produced: fenced code
+//! blocks in `chat_assistant` messages and added lines of `file_diff` events
+//! recorded in Normal or Coach mode (where Codex edits the workspace). Drive-mode
+//! diffs are the human's own edits and are ignored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::backend::GitBackend;
+use crate::config::GateConfig;
+use crate::gate::{resolve_gate_refs, window_commits};
+use crate::history::load_commit_records;
+use crate::paths::TRANSCRIPT_DIR;
+use crate::state::{AttributionSuggestion, Mode, State};
+use crate::transcript::TranscriptEvent;
+use crate::window::GateWindow;
+
+/// Normalized lines shorter than this are too generic (`}`, `else {`) to match on.
+const MIN_SIGNIFICANT_CHARS: usize = 10;
+
+/// Inference result for one commit in the window.
+#[derive(Debug, Clone)]
+pub struct InferredCommit {
+    pub commit: String,
+    pub subject: String,
+    pub suggestion: AttributionSuggestion,
+}
+
+/// Lines Codex produced, with the first time each one was seen.
+#[derive(Debug, Default)]
+struct AiCorpus {
+    first_seen: HashMap<String, DateTime<Utc>>,
+}
+
+impl AiCorpus {
+    fn add(&mut self, line: &str, at: DateTime<Utc>) {
+        let Some(line) = normalize_line(line) else {
+            return;
+        };
+        self.first_seen
+            .entry(line)
+            .and_modify(|seen| *seen = (*seen).min(at))
+            .or_insert(at);
+    }
+
+    fn add_event(&mut self, event: &TranscriptEvent) {
+        let Some(payload) = &event.payload else {
+            return;
+        };
+        match event.event_type.as_str() {
+            "chat_assistant" => {
+                let content = payload["content"].as_str().unwrap_or_default();
+                for line in fenced_code_lines(content) {
+                    self.add(line, event.timestamp);
+                }
+            }
+            "file_diff" if event.mode != Mode::Drive => {
+                let diff = payload["diff_unified"].as_str().unwrap_or_default();
+                for line in added_diff_lines(diff) {
+                    self.add(line, event.timestamp);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `line` (already normalized) was produced no later than `at`.
+    fn produced_by(&self, line: &str, at: DateTime<Utc>) -> bool {
+        self.first_seen.get(line).is_some_and(|seen| *seen <= at)
+    }
+}
+
+/// Estimates the AI share of every commit the gate counts in `window` (same
+/// `[gate].scope` and `date_field`) that has no manual override and at least
+/// one significant added line.
+pub fn infer_attributions(
+    git: &dyn GitBackend,
+    gate: &GateConfig,
+    state: &State,
+    window: &GateWindow,
+) -> Result<Vec<InferredCommit>> {
+    let repo_root = git.repo_root();
+    let corpus = load_ai_corpus(repo_root, window.end)?;
+    let refs = resolve_gate_refs(git, &gate.scope)?;
+    let selected = window_commits(git, gate, window, &refs)?;
+    let records = load_commit_records(git, &selected.commits)?;
+
+    let now = Utc::now();
+    let mut inferred = Vec::new();
+    for commit in selected.commits {
+        if state.attribution_overrides.contains_key(&commit) {
+            continue;
+        }
+        let committed_at = selected
+            .committed_at
+            .get(&commit)
+            .copied()
+            .with_context(|| format!("failed to read commit time of {commit}"))?;
+        let subject = records
+            .get(&commit)
+            .map(|record| record.subject.clone())
+            .unwrap_or_default();
+
+        let hunk_lines = git.commit_diff_lines(&commit)?;
+        if let Some(suggestion) = suggest_share(&corpus, &hunk_lines, committed_at, now) {
+            inferred.push(InferredCommit {
+                commit,
+                subject,
+                suggestion,
+            });
+        }
+    }
+    Ok(inferred)
+}
+
+/// Estimates the AI share of the uncommitted (staged and unstaged) lines of
+/// tracked files against `HEAD`; `None` without significant added lines.
+pub fn infer_worktree_share(git: &dyn GitBackend) -> Result<Option<AttributionSuggestion>> {
+    let now = Utc::now();
+    let corpus = load_ai_corpus(git.repo_root(), now)?;
+    let lines = git.worktree_added_lines()?;
+    Ok(suggest_share(&corpus, &lines, now, now))
+}
+
+/// Share of the significant `lines` Codex produced no later than `at`.
+fn suggest_share(
+    corpus: &AiCorpus,
+    lines: &[String],
+    at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Option<AttributionSuggestion> {
+    let mut considered = 0_u64;
+    let mut matched = 0_u64;
+    for added in lines {
+        let Some(added) = normalize_line(added) else {
+            continue;
+        };
+        considered += 1;
+        if corpus.produced_by(&added, at) {
+            matched += 1;
+        }
+    }
+    if considered == 0 {
+        return None;
+    }
+    Some(AttributionSuggestion {
+        ai_share: (matched as f64 / considered as f64 * 100.0).round() / 100.0,
+        matched_lines: matched,
+        considered_lines: considered,
+        inferred_at: now,
+    })
+}
+
+/// Reads every transcript event recorded before `until`; unreadable lines are skipped.
+fn load_ai_corpus(repo_root: &Path, until: DateTime<Utc>) -> Result<AiCorpus> {
+    let mut corpus = AiCorpus::default();
+    let dir = repo_root.join(TRANSCRIPT_DIR);
+    if !dir.exists() {
+        return Ok(corpus);
+    }
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        for line in text.lines() {
+            let Ok(event) = serde_json::from_str::<TranscriptEvent>(line) else {
+                continue;
+            };
+            if event.timestamp < until {
+                corpus.add_event(&event);
+            }
+        }
+    }
+    Ok(corpus)
+}
+
+/// `+` lines inside the hunks of a unified diff.
+pub(crate) fn added_diff_lines(diff: &str) -> impl Iterator<Item = &str> {
+    let mut in_hunk = false;
+    diff.lines().filter_map(move |line| {
+        if line.starts_with("@@") {
+            in_hunk = true;
+            return None;
+        }
+        if line.starts_with("diff --git") {
+            in_hunk = false;
+            return None;
+        }
+        if in_hunk {
+            line.strip_prefix('+')
+        } else {
+            None
+        }
+    })
+}
+
+/// Lines inside ``` fences of a chat message; an unterminated fence runs to the end.
+fn fenced_code_lines(content: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Trims and collapses whitespace; `None` for lines too short or without any
+/// alphanumeric character to be meaningful evidence.
+fn normalize_line(line: &str) -> Option<String> {
+    let normalized = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    let significant = normalized.chars().count() >= MIN_SIGNIFICANT_CHARS
+        && normalized.chars().any(char::is_alphanumeric);
+    significant.then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::config::GateScope;
+    use crate::git::git_output;
+    use crate::test_support::{commit_file, init_git_repo};
+    use crate::transcript::write_transcript_event;
+    use crate::week::IsoWeek;
+
+    fn assistant_event(content: &str, at: DateTime<Utc>) -> TranscriptEvent {
+        TranscriptEvent {
+            log_schema_version: "1.1".to_string(),
+            event_id: "evt-1".to_string(),
+            session_id: "session-1".to_string(),
+            event_type: "chat_assistant".to_string(),
+            timestamp: at,
+            mode: Mode::Drive,
+            payload: Some(json!({ "role": "assistant", "content": content })),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn fenced_code_and_normal_mode_diffs_feed_the_corpus() {
+        let at = Utc::now();
+        let mut corpus = AiCorpus::default();
+        corpus.add_event(&assistant_event(
+            "Try this:\n```rust\nlet total = values.iter().sum::<u64>();\n}\n```\nlet outside = fenced;",
+            at,
+        ));
+        let mut diff = assistant_event("", at);
+        diff.event_type = "file_diff".to_string();
+        diff.payload = Some(json!({
+            "diff_unified": "--- a/x\n+++ b/x\n@@ -0,0 +1 @@\n+println!(\"hello world\");\n"
+        }));
+        corpus.add_event(&diff);
+
+        assert!(corpus.produced_by("let total = values.iter().sum::<u64>();", at));
+        assert!(!corpus.produced_by("let outside = fenced;", at));
+        assert!(!corpus.produced_by("println!(\"hello world\");", at));
+
+        diff.mode = Mode::Normal;
+        corpus.add_event(&diff);
+        assert!(corpus.produced_by("println!(\"hello world\");", at));
+        assert!(!corpus.produced_by(
+            "println!(\"hello world\");",
+            at - chrono::Duration::seconds(1)
+        ));
+    }
+
+    #[test]
+    fn infer_attributions_estimates_share_from_matching_lines() -> Result<()> {
+        let repo = init_git_repo("spp-infer")?;
+        let root = repo.path();
+        let transcript = root.join(TRANSCRIPT_DIR).join("session-1.jsonl");
+        let suggested =
+            "```\nfn compute_total(values: &[u64]) -> u64 {\n    values.iter().sum()\n```";
+        write_transcript_event(
+            &transcript,
+            &assistant_event(suggested, Utc::now() - chrono::Duration::minutes(5)),
+        )?;
+        let commit = commit_file(
+            root,
+            "src/lib.rs",
+            "fn compute_total(values: &[u64]) -> u64 {\n    values.iter().sum()\n}\n\n// handwritten explanation here\nfn other_function() {}\n",
+            "feat: totals",
+        )?;
+        git_output(root, &["checkout", "-q", "-b", "topic"])?;
+        let topic = commit_file(
+            root,
+            "src/total.rs",
+            "fn compute_total(values: &[u64]) -> u64 {\n",
+            "feat: topic",
+        )?;
+        git_output(root, &["checkout", "-q", "main"])?;
+
+        let now = Utc::now();
+        let window = GateWindow {
+            start: now - chrono::Duration::days(1),
+            end: now + chrono::Duration::days(1),
+            week: IsoWeek::containing(now.date_naive()),
+        };
+        let git = SubprocessBackend::new(root);
+        let mut gate = GateConfig::default();
+        let inferred = infer_attributions(&git, &gate, &State::default(), &window)?;
+        assert_eq!(inferred.len(), 1);
+        assert_eq!(inferred[0].commit, commit);
+        assert_eq!(inferred[0].subject, "feat: totals");
+        assert_eq!(inferred[0].suggestion.considered_lines, 4);
+        assert_eq!(inferred[0].suggestion.matched_lines, 2);
+        assert_eq!(inferred[0].suggestion.ai_share, 0.5);
+
+        gate.scope = GateScope::All;
+        let mut all = infer_attributions(&git, &gate, &State::default(), &window)?
+            .into_iter()
+            .map(|row| row.commit)
+            .collect::<Vec<_>>();
+        all.sort();
+        let mut expected = vec![commit, topic];
+        expected.sort();
+        assert_eq!(all, expected);
+        Ok(())
+    }
+}
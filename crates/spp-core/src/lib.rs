@@ -0,0 +1,370 @@
+//! Core library behind the `spp` wrapper CLI.
+//!
+//! The weekly gate, commit attribution, transcript recording and runtime
+//! state handling live here so that other tooling (editor plugins, git hooks,
+//! dashboards) can reuse them without going through the CLI.
+
+pub mod attribution;
+pub mod backend;
+pub mod codex;
+pub mod config;
+pub mod gate;
+pub mod git;
+pub mod glob;
+pub mod history;
+pub mod infer;
+pub mod logs;
+pub mod metrics;
+pub mod paths;
+pub mod project;
+pub mod recorder;
+pub mod render;
+pub mod state;
+pub mod supervise;
+pub mod transcript;
+pub mod trend;
+pub mod week;
+pub mod window;
+
+pub use attribution::{classify_actor, commit_added_lines, Attribution, Attributor};
+pub use config::{load_config, AppConfig};
+pub use gate::{
+    apply_gate, collect_weekly_metrics, compute_report_for_week, compute_report_for_window,
+    compute_weekly_report, WeeklyReport,
+};
+pub use state::{load_state, save_state, Actor, Mode, State};
+pub use transcript::TranscriptEvent;
+pub use week::IsoWeek;
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use anyhow::{bail, Context, Result};
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use crate::backend::{CommitTimes, GitBackend, Revisions};
+    use crate::config::GateMetric;
+    use crate::gate::{MetricRatios, WeeklyReport};
+    use crate::git::git_output;
+    use crate::history::CommitRecord;
+    use crate::metrics::FileChange;
+    use crate::state::Mode;
+
+    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+    pub(crate) struct TempDirGuard {
+        path: PathBuf,
+    }
+
+    impl TempDirGuard {
+        pub(crate) fn new(prefix: &str) -> Result<Self> {
+            let id = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("{prefix}-{}-{id}", std::process::id()));
+            fs::create_dir_all(&path)?;
+            Ok(Self { path })
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDirGuard {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    /// A fresh git repository with a fixed test identity.
+    pub(crate) fn init_git_repo(prefix: &str) -> Result<TempDirGuard> {
+        let temp = TempDirGuard::new(prefix)?;
+        let status = Command::new("git")
+            .args(["init", "-q", "-b", "main"])
+            .current_dir(temp.path())
+            .status()?;
+        if !status.success() {
+            bail!("git init failed");
+        }
+        git_output(temp.path(), &["config", "user.email", "dev@example.com"])?;
+        git_output(temp.path(), &["config", "user.name", "Dev"])?;
+        Ok(temp)
+    }
+
+    /// Writes `contents` to `file`, commits it with `message` and returns the hash.
+    pub(crate) fn commit_file(
+        repo_root: &Path,
+        file: &str,
+        contents: &str,
+        message: &str,
+    ) -> Result<String> {
+        let path = repo_root.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        git_output(repo_root, &["add", "--", file])?;
+        git_output(repo_root, &["commit", "-q", "-m", message])?;
+        Ok(git_output(repo_root, &["rev-parse", "HEAD"])?
+            .trim()
+            .to_string())
+    }
+
+    /// A linear history held in memory, for testing code written against
+    /// [`GitBackend`] without a git repository. Only runtime files (the commit
+    /// cache) are written, below a temporary directory.
+    pub(crate) struct MemoryRepo {
+        root: TempDirGuard,
+        pub(crate) user_email: Option<String>,
+        /// Newest first.
+        commits: Vec<(CommitRecord, DateTime<Utc>)>,
+        /// Note text per notes ref and commit.
+        pub(crate) notes: RefCell<HashMap<String, HashMap<String, String>>>,
+        /// Makes the worktree queries fail, as with an unreadable index.
+        pub(crate) fail_worktree: bool,
+    }
+
+    impl MemoryRepo {
+        pub(crate) fn new(prefix: &str) -> Result<Self> {
+            Ok(Self {
+                root: TempDirGuard::new(prefix)?,
+                user_email: Some("dev@example.com".to_string()),
+                commits: Vec::new(),
+                notes: RefCell::default(),
+                fail_worktree: false,
+            })
+        }
+
+        /// Adds a commit by `email` on top, adding `added` lines to each of `files`.
+        pub(crate) fn commit(
+            &mut self,
+            email: &str,
+            message: &str,
+            files: &[(&str, u64)],
+            at: DateTime<Utc>,
+        ) -> String {
+            let commit = format!("{:040x}", self.commits.len() + 1);
+            let (subject, body) = message.split_once("\n\n").unwrap_or((message, ""));
+            self.commits.insert(
+                0,
+                (
+                    CommitRecord {
+                        commit: commit.clone(),
+                        author_email: email.to_string(),
+                        committer_email: email.to_string(),
+                        subject: subject.to_string(),
+                        message: message.to_string(),
+                        trailers: body
+                            .lines()
+                            .filter(|line| line.contains(": "))
+                            .map(str::to_string)
+                            .collect(),
+                        files: files
+                            .iter()
+                            .map(|(path, added)| FileChange {
+                                path: path.to_string(),
+                                added: Some(*added),
+                                deleted: Some(0),
+                            })
+                            .collect(),
+                    },
+                    at,
+                ),
+            );
+            commit
+        }
+
+        fn record(&self, commit: &str) -> Option<&CommitRecord> {
+            self.commits
+                .iter()
+                .map(|(record, _)| record)
+                .find(|record| record.commit == commit)
+        }
+    }
+
+    impl GitBackend for MemoryRepo {
+        fn repo_root(&self) -> &Path {
+            self.root.path()
+        }
+
+        fn current_branch(&self) -> String {
+            "main".to_string()
+        }
+
+        fn current_commit(&self) -> Option<String> {
+            self.commits
+                .first()
+                .map(|(record, _)| record.commit.clone())
+        }
+
+        fn user_email(&self) -> Option<String> {
+            self.user_email.clone()
+        }
+
+        fn resolve_commit(&self, rev: &str) -> Result<String> {
+            match rev {
+                "HEAD" | "main" => self.current_commit(),
+                _ => self.record(rev).map(|record| record.commit.clone()),
+            }
+            .with_context(|| format!("commit not found: {rev}"))
+        }
+
+        fn origin_head(&self) -> Option<String> {
+            None
+        }
+
+        fn commit_times(
+            &self,
+            _revisions: &Revisions,
+            since: DateTime<Utc>,
+        ) -> Result<Vec<CommitTimes>> {
+            Ok(self
+                .commits
+                .iter()
+                .filter(|(_, at)| *at >= since)
+                .map(|(record, at)| CommitTimes {
+                    commit: record.commit.clone(),
+                    authored_at: *at,
+                    committed_at: *at,
+                })
+                .collect())
+        }
+
+        fn commit_records(&self, commits: &[String]) -> Result<Vec<CommitRecord>> {
+            Ok(commits
+                .iter()
+                .filter_map(|commit| self.record(commit).cloned())
+                .collect())
+        }
+
+        fn commit_notes(
+            &self,
+            notes_ref: Option<&str>,
+            commits: &[String],
+        ) -> Result<HashMap<String, String>> {
+            let notes = self.notes.borrow();
+            let Some(notes) = notes.get(notes_ref.unwrap_or("refs/notes/commits")) else {
+                return Ok(HashMap::new());
+            };
+            Ok(commits
+                .iter()
+                .filter_map(|commit| Some((commit.clone(), notes.get(commit)?.clone())))
+                .collect())
+        }
+
+        fn write_note(&self, notes_ref: &str, commit: &str, note: &str) -> Result<()> {
+            self.record(commit)
+                .with_context(|| format!("commit not found: {commit}"))?;
+            self.notes
+                .borrow_mut()
+                .entry(notes_ref.to_string())
+                .or_default()
+                .insert(commit.to_string(), note.to_string());
+            Ok(())
+        }
+
+        fn branches_containing(&self, commit: &str) -> Result<Vec<String>> {
+            Ok(self
+                .record(commit)
+                .map(|_| vec!["refs/heads/main".to_string()])
+                .unwrap_or_default())
+        }
+
+        fn commit_message(&self, commit: &str) -> Result<String> {
+            let record = self
+                .record(commit)
+                .with_context(|| format!("commit not found: {commit}"))?;
+            Ok(format!(
+                "{}\n\n{}\n",
+                record.subject,
+                record.trailers.join("\n")
+            ))
+        }
+
+        fn range_commits(&self, range: &str) -> Result<Vec<String>> {
+            let (from, to) = range.split_once("..").context("not a range")?;
+            let to = if to.is_empty() { "HEAD" } else { to };
+            let start = self.resolve_commit(to)?;
+            let stop = self.resolve_commit(if from.is_empty() { "HEAD" } else { from })?;
+            let mut commits = self
+                .commits
+                .iter()
+                .map(|(record, _)| record.commit.clone())
+                .skip_while(|commit| *commit != start)
+                .take_while(|commit| *commit != stop)
+                .collect::<Vec<_>>();
+            commits.reverse();
+            Ok(commits)
+        }
+
+        fn commit_diff_lines(&self, _commit: &str) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        fn is_reachable(&self, commit: &str) -> bool {
+            self.record(commit).is_some()
+        }
+
+        fn patch_id(&self, _commit: &str) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        fn worktree_file_changes(&self) -> Result<Vec<FileChange>> {
+            if self.fail_worktree {
+                bail!("index file corrupt");
+            }
+            Ok(Vec::new())
+        }
+
+        fn worktree_added_lines(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        fn generated_paths(&self, _paths: &[&str]) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+    }
+
+    pub(crate) fn sample_weekly_report() -> WeeklyReport {
+        WeeklyReport {
+            log_schema_version: "1.1".to_string(),
+            generated_at: Utc::now(),
+            year: 2026,
+            iso_week: 7,
+            window_start: Utc.with_ymd_and_hms(2026, 2, 9, 0, 0, 0).unwrap(),
+            window_end: Utc.with_ymd_and_hms(2026, 2, 16, 0, 0, 0).unwrap(),
+            human_lines_added: 60,
+            ai_lines_added: 40,
+            human_commit_count: 3,
+            ai_commit_count: 1,
+            human_lines_deleted: 10,
+            ai_lines_deleted: 5,
+            human_files_touched: 4,
+            ai_files_touched: 2,
+            ratio: 0.6,
+            target_ratio: 0.7,
+            gate_metric: GateMetric::Added,
+            gate_ratio: 0.6,
+            metric_ratios: MetricRatios {
+                added: 0.6,
+                added_deleted: 70.0 / 115.0,
+                net: 50.0 / 85.0,
+                files_touched: 4.0 / 6.0,
+                commits: 0.75,
+                weighted: 0.6,
+            },
+            gate_passed: false,
+            mode_after_evaluation: Mode::Normal,
+            evaluated_refs: vec!["main".to_string()],
+            gate_author: None,
+            authors: Vec::new(),
+            recovery: None,
+            notes: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Session logs, weekly report files and log retention.
+
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::gate::WeeklyReport;
+use crate::metrics::FileChange;
+use crate::paths::{SESSION_DIR, TRANSCRIPT_DIR, WEEKLY_DIR};
+use crate::state::Mode;
+
+/// `event` of a [`SessionEndEntry`].
+pub const SESSION_END_EVENT: &str = "session_end";
+/// `event` of a [`GateTrippedEntry`].
+pub const GATE_TRIPPED_EVENT: &str = "gate_tripped";
+
+/// One `spp codex` launch, appended to `.codex-spp/sessions/<year>-W<week>.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLogEntry {
+    pub log_schema_version: String,
+    /// Shared with the [`SessionEndEntry`] written when Codex exits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub mode: Mode,
+    pub sandbox: String,
+    pub approval: String,
+    /// Codex `--profile` enforced for the mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Enforced `-c key=value` overrides, excluding the instructions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_overrides: Vec<String>,
+    /// Instruction file passed as `developer_instructions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions_file: Option<String>,
+    pub git_branch: String,
+    pub git_commit: Option<String>,
+    pub gate_ratio: Option<f64>,
+    pub gate_target: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// How a `spp codex` launch ended, appended after its [`SessionLogEntry`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEndEntry {
+    pub log_schema_version: String,
+    /// Always [`SESSION_END_EVENT`].
+    pub event: String,
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub duration_sec: i64,
+    /// Exit code of Codex; `None` when it was ended by a signal or is unknown.
+    pub exit_code: Option<i32>,
+    /// Why spp lost track of Codex before it exited (exit code unknown).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub git_commit_before: Option<String>,
+    pub git_commit_after: Option<String>,
+    /// `git diff --numstat HEAD` when Codex started.
+    pub diff_before: Vec<FileChange>,
+    /// `git diff --numstat HEAD` when Codex exited.
+    pub diff_after: Vec<FileChange>,
+    /// Files whose uncommitted changes grew during the session, counted by the
+    /// growth: candidates for AI attribution.
+    pub ai_touched: Vec<FileChange>,
+    /// Drive transcript recorded alongside the session, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript_path: Option<String>,
+}
+
+/// The gate forcing a stricter mode while a `spp codex` session was running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateTrippedEntry {
+    pub log_schema_version: String,
+    /// Always [`GATE_TRIPPED_EVENT`].
+    pub event: String,
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    /// Mode Codex was launched in.
+    pub mode_before: Mode,
+    /// Mode the gate now enforces.
+    pub mode_after: Mode,
+    pub gate_ratio: f64,
+    pub gate_target: f64,
+    /// `[codex].on_gate_trip` applied: `warn`, `terminate` or `restart`.
+    pub action: String,
+}
+
+/// Files whose added or deleted lines against `HEAD` grew between two
+/// `git diff --numstat` snapshots, with the growth as their counts. Binary
+/// files count when they appear or change state.
+pub fn touched_during_session(before: &[FileChange], after: &[FileChange]) -> Vec<FileChange> {
+    after
+        .iter()
+        .filter_map(|change| {
+            let previous = before.iter().find(|prior| prior.path == change.path);
+            let grown = |now: Option<u64>, then: Option<Option<u64>>| match (now, then) {
+                (Some(now), Some(Some(then))) => Some(now.saturating_sub(then)),
+                (Some(now), _) => Some(now),
+                (None, _) => None,
+            };
+            let added = grown(change.added, previous.map(|prior| prior.added));
+            let deleted = grown(change.deleted, previous.map(|prior| prior.deleted));
+            let touched = match (added, deleted) {
+                (Some(added), Some(deleted)) => added > 0 || deleted > 0,
+                _ => previous != Some(change),
+            };
+            touched.then(|| FileChange {
+                path: change.path.clone(),
+                added,
+                deleted,
+            })
+        })
+        .collect()
+}
+
+/// Writes `report` to `.codex-spp/weekly/<year>-W<week>.json`, replacing any previous file.
+pub fn write_weekly_report(repo_root: &Path, report: &WeeklyReport) -> Result<()> {
+    let path = repo_root
+        .join(WEEKLY_DIR)
+        .join(format!("{}-W{:02}.json", report.year, report.iso_week));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(report)?;
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Appends `entry` to the session log of its ISO week.
+pub fn write_session_log(repo_root: &Path, entry: &SessionLogEntry) -> Result<()> {
+    append_jsonl(&session_log_path(repo_root, entry.timestamp), entry)
+}
+
+/// Appends `entry` to the session log its session started in.
+pub fn write_session_end(repo_root: &Path, entry: &SessionEndEntry) -> Result<()> {
+    append_jsonl(&session_log_path(repo_root, entry.started_at), entry)
+}
+
+/// Appends `entry` to the session log its session started in.
+pub fn write_gate_tripped(repo_root: &Path, entry: &GateTrippedEntry) -> Result<()> {
+    append_jsonl(&session_log_path(repo_root, entry.started_at), entry)
+}
+
+fn session_log_path(repo_root: &Path, timestamp: DateTime<Utc>) -> PathBuf {
+    let iso = timestamp.iso_week();
+    repo_root
+        .join(SESSION_DIR)
+        .join(format!("{}-W{:02}.jsonl", iso.year(), iso.week()))
+}
+
+/// Appends `value` as a single JSON line, creating the file if needed.
+pub fn append_jsonl<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(value)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+/// Deletes the oldest session, weekly and transcript files until their total
+/// size fits in `max_bytes`.
+pub fn enforce_log_size(repo_root: &Path, max_bytes: u64) -> Result<()> {
+    let mut files = collect_log_files(repo_root)?;
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    if total <= max_bytes {
+        return Ok(());
+    }
+
+    files.sort_by_key(|f| f.modified);
+    for file in files {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&file.path)
+            .with_context(|| format!("failed to remove {}", file.path.display()))?;
+        total = total.saturating_sub(file.size);
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct SizedFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn collect_log_files(repo_root: &Path) -> Result<Vec<SizedFile>> {
+    let mut files = Vec::new();
+    for rel in [SESSION_DIR, WEEKLY_DIR, TRANSCRIPT_DIR] {
+        let dir = repo_root.join(rel);
+        if !dir.exists() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            if path.extension().and_then(OsStr::to_str).is_none() {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            files.push(SizedFile {
+                path,
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, added: Option<u64>, deleted: Option<u64>) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            added,
+            deleted,
+        }
+    }
+
+    #[test]
+    fn touched_during_session_keeps_the_growth_of_changed_files() {
+        let before = vec![
+            change("src/lib.rs", Some(10), Some(2)),
+            change("README.md", Some(3), Some(0)),
+            change("logo.png", None, None),
+        ];
+        let after = vec![
+            change("src/lib.rs", Some(25), Some(2)),
+            change("README.md", Some(3), Some(0)),
+            change("logo.png", None, None),
+            change("src/new.rs", Some(40), Some(0)),
+            change("icon.png", None, None),
+        ];
+        assert_eq!(
+            touched_during_session(&before, &after),
+            vec![
+                change("src/lib.rs", Some(15), Some(0)),
+                change("src/new.rs", Some(40), Some(0)),
+                change("icon.png", None, None),
+            ]
+        );
+        assert!(touched_during_session(&after, &after).is_empty());
+    }
+}
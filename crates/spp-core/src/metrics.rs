@@ -0,0 +1,286 @@
+//! Which changed lines count toward the gate (`[metrics]`).
+//!
+//! Lock files and generated sources can dwarf hand-written changes, so each
+//! file of a commit is filtered by the include/exclude globs and by
+//! `.gitattributes` markers (`linguist-generated`, `-diff`), and the lines
+//! counted per file are capped.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::GitBackend;
+use crate::config::MetricsConfig;
+use crate::glob::Glob;
+
+/// One file of `git show --numstat`; `None` counts mean a binary file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub added: Option<u64>,
+    pub deleted: Option<u64>,
+}
+
+/// Changed lines of one commit after filtering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CountedLines {
+    pub added: u64,
+    pub deleted: u64,
+    /// Text files that were counted.
+    pub files: u64,
+    /// Lines in files dropped by the globs or `.gitattributes`.
+    pub excluded_lines: u64,
+    pub excluded_files: u64,
+    /// Lines above `max_lines_per_file` in files that were kept.
+    pub capped_lines: u64,
+    pub capped_files: u64,
+}
+
+impl CountedLines {
+    pub fn absorb(&mut self, other: CountedLines) {
+        self.added += other.added;
+        self.deleted += other.deleted;
+        self.files += other.files;
+        self.excluded_lines += other.excluded_lines;
+        self.excluded_files += other.excluded_files;
+        self.capped_lines += other.capped_lines;
+        self.capped_files += other.capped_files;
+    }
+
+    /// Report notes describing what was left out, if anything.
+    pub fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.excluded_files > 0 {
+            notes.push(format!(
+                "excluded {} added lines in {} files ([metrics] globs / generated)",
+                self.excluded_lines, self.excluded_files
+            ));
+        }
+        if self.capped_files > 0 {
+            notes.push(format!(
+                "capped {} added lines in {} files ([metrics].max_lines_per_file)",
+                self.capped_lines, self.capped_files
+            ));
+        }
+        notes
+    }
+}
+
+/// Compiled `[metrics]` settings, caching `.gitattributes` lookups per path.
+#[derive(Debug)]
+pub struct LineFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    honor_gitattributes: bool,
+    max_lines_per_file: u64,
+    generated: HashMap<String, bool>,
+}
+
+impl LineFilter {
+    pub fn new(config: &MetricsConfig) -> Result<Self> {
+        let compile = |globs: &[String]| -> Result<Vec<Glob>> {
+            globs.iter().map(|raw| Glob::new(raw)).collect()
+        };
+        Ok(Self {
+            include: compile(&config.include)?,
+            exclude: compile(&config.exclude)?,
+            honor_gitattributes: config.honor_gitattributes,
+            max_lines_per_file: config.max_lines_per_file,
+            generated: HashMap::new(),
+        })
+    }
+
+    /// Counts the changed lines of `commit` that the gate should see.
+    pub fn count_commit(&mut self, git: &dyn GitBackend, commit: &str) -> Result<CountedLines> {
+        let record = git
+            .commit_records(&[commit.to_string()])?
+            .pop()
+            .with_context(|| format!("commit not found: {commit}"))?;
+        self.count(git, &record.files)
+    }
+
+    /// Counts the changed lines of `changes`; binary files are skipped silently.
+    /// The cap applies to added and deleted lines separately.
+    pub fn count(&mut self, git: &dyn GitBackend, changes: &[FileChange]) -> Result<CountedLines> {
+        let mut counted = CountedLines::default();
+        let mut kept = Vec::new();
+        for change in changes {
+            let (Some(added), Some(deleted)) = (change.added, change.deleted) else {
+                continue;
+            };
+            if self.matches_globs(&change.path) {
+                kept.push((change.path.as_str(), added, deleted));
+            } else {
+                counted.excluded_lines += added;
+                counted.excluded_files += 1;
+            }
+        }
+
+        if self.honor_gitattributes {
+            let paths: Vec<&str> = kept.iter().map(|(path, _, _)| *path).collect();
+            self.load_generated(git, &paths)?;
+        }
+        for (path, added, deleted) in kept {
+            if self.generated.get(path).copied().unwrap_or(false) {
+                counted.excluded_lines += added;
+                counted.excluded_files += 1;
+                continue;
+            }
+            counted.files += 1;
+            counted.deleted += match self.max_lines_per_file {
+                0 => deleted,
+                cap => deleted.min(cap),
+            };
+            if self.max_lines_per_file > 0 && added > self.max_lines_per_file {
+                counted.capped_lines += added - self.max_lines_per_file;
+                counted.capped_files += 1;
+                counted.added += self.max_lines_per_file;
+            } else {
+                counted.added += added;
+            }
+        }
+        Ok(counted)
+    }
+
+    /// Looks up `.gitattributes` for every counted path of `changes` in one
+    /// batch, so later [`LineFilter::count`] calls spawn no git process.
+    pub fn prefetch<'a>(
+        &mut self,
+        git: &dyn GitBackend,
+        changes: impl IntoIterator<Item = &'a FileChange>,
+    ) -> Result<()> {
+        if !self.honor_gitattributes {
+            return Ok(());
+        }
+        let paths: Vec<&str> = changes
+            .into_iter()
+            .filter(|change| change.added.is_some() && self.matches_globs(&change.path))
+            .map(|change| change.path.as_str())
+            .collect();
+        self.load_generated(git, &paths)
+    }
+
+    fn matches_globs(&self, path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|glob| glob.matches_path(path));
+        included && !self.exclude.iter().any(|glob| glob.matches_path(path))
+    }
+
+    /// Looks up `linguist-generated` and `diff` for paths not seen yet, in one
+    /// [`GitBackend::generated_paths`] call. Attributes come from the current checkout.
+    fn load_generated(&mut self, git: &dyn GitBackend, paths: &[&str]) -> Result<()> {
+        let mut missing: Vec<&str> = paths
+            .iter()
+            .copied()
+            .filter(|path| !self.generated.contains_key(*path))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let generated = git.generated_paths(&missing)?;
+
+        for path in &missing {
+            self.generated.insert((*path).to_string(), false);
+        }
+        for path in generated {
+            self.generated.insert(path, true);
+        }
+        Ok(())
+    }
+}
+
+/// Parses `--numstat -z` output, where a rename is `added\tdeleted\t\0old\0new\0`.
+pub(crate) fn parse_numstat_z(out: &str) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    let mut fields = out.split('\0');
+    while let Some(field) = fields.next() {
+        let field = field.trim_start_matches('\n');
+        if field.is_empty() {
+            continue;
+        }
+        let mut parts = field.splitn(3, '\t');
+        let added = parts.next().unwrap_or_default();
+        let deleted = parts.next().unwrap_or_default();
+        let mut path = parts.next().unwrap_or_default().to_string();
+        if path.is_empty() {
+            let _old = fields.next();
+            path = fields.next().unwrap_or_default().to_string();
+        }
+        changes.push(FileChange {
+            path,
+            added: added.parse().ok(),
+            deleted: deleted.parse().ok(),
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::backend::SubprocessBackend;
+    use crate::git::git_output;
+    use crate::test_support::{commit_file, init_git_repo};
+
+    #[test]
+    fn parse_numstat_z_handles_renames_and_binaries() {
+        let out = concat!(
+            "3\t1\tsrc/lib.rs\0",
+            "-\t-\tlogo.png\0",
+            "2\t0\t\0old.rs\0new.rs\0"
+        );
+        let changes = parse_numstat_z(out);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].added, Some(3));
+        assert_eq!(changes[1].added, None);
+        assert_eq!(changes[2].path, "new.rs");
+        assert_eq!(changes[2].deleted, Some(0));
+    }
+
+    #[test]
+    fn filter_excludes_globs_and_generated_files_and_caps_lines() -> Result<()> {
+        let repo = init_git_repo("spp-metrics")?;
+        let root = repo.path();
+        fs::write(
+            root.join(".gitattributes"),
+            "gen/** linguist-generated\n*.snap -diff\n",
+        )?;
+        fs::create_dir_all(root.join("gen"))?;
+        fs::write(root.join("gen/api.rs"), "a\nb\nc\n")?;
+        fs::write(root.join("ui.snap"), "x\ny\n")?;
+        fs::write(root.join("Cargo.lock"), "1\n2\n3\n4\n")?;
+        git_output(root, &["add", "-A"])?;
+        let commit = commit_file(root, "src/lib.rs", "1\n2\n3\n4\n5\n6\n", "feat: mixed")?;
+
+        let config = MetricsConfig {
+            exclude: vec!["*.lock".to_string()],
+            max_lines_per_file: 5,
+            ..MetricsConfig::default()
+        };
+        let git = SubprocessBackend::new(root);
+        let counted = LineFilter::new(&config)?.count_commit(&git, &commit)?;
+        // `-diff` files already come out of numstat as binary and are skipped.
+        assert_eq!(counted.added, 5 + 2);
+        assert_eq!(counted.excluded_files, 2);
+        assert_eq!(counted.excluded_lines, 4 + 3);
+        assert_eq!(counted.capped_lines, 1);
+        assert_eq!(counted.capped_files, 1);
+        assert_eq!(counted.files, 2);
+        assert_eq!(counted.deleted, 0);
+
+        let config = MetricsConfig {
+            include: vec!["src/**".to_string()],
+            honor_gitattributes: false,
+            ..MetricsConfig::default()
+        };
+        let counted = LineFilter::new(&config)?.count_commit(&git, &commit)?;
+        assert_eq!(counted.added, 6);
+        assert_eq!(counted.excluded_files, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+//! Repository-relative locations of the codex-spp runtime files.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+/// Runtime configuration read by every command.
+pub const RUNTIME_CONFIG: &str = ".codex-spp/config.toml";
+/// Persisted [`State`](crate::state::State).
+pub const STATE_FILE: &str = ".codex-spp/state.json";
+/// Weekly JSONL files of [`SessionLogEntry`](crate::logs::SessionLogEntry) records.
+pub const SESSION_DIR: &str = ".codex-spp/sessions";
+/// Weekly [`WeeklyReport`](crate::gate::WeeklyReport) files.
+pub const WEEKLY_DIR: &str = ".codex-spp/weekly";
+/// Per-session transcript JSONL files.
+pub const TRANSCRIPT_DIR: &str = ".codex-spp/transcripts";
+/// Per-commit metadata cache, keyed by commit hash.
+pub const CACHE_DIR: &str = ".codex-spp/cache";
+/// Recorder control and done files.
+pub const RUNTIME_DIR: &str = ".codex-spp/runtime";
+/// Template config used when no runtime config exists yet.
+pub const TEMPLATE_CONFIG: &str = "template_spp.config.toml";
+/// Destination of the runtime config when scaffolding a project.
+pub const PROJECT_RUNTIME_CONFIG_FILE: &str = ".codex-spp/config.toml";
+/// Destination of the optional Codex config when scaffolding a project.
+pub const PROJECT_CODEX_CONFIG_FILE: &str = ".codex/config.toml";
+/// Gitignore rule that keeps the runtime directory out of version control.
+pub const GITIGNORE_RULE_CODEX_SPP: &str = "/.codex-spp/";
+
+/// Creates every runtime directory under `repo_root` if missing.
+pub fn ensure_runtime_dirs(repo_root: &Path) -> Result<()> {
+    fs::create_dir_all(repo_root.join(SESSION_DIR))?;
+    fs::create_dir_all(repo_root.join(WEEKLY_DIR))?;
+    fs::create_dir_all(repo_root.join(TRANSCRIPT_DIR))?;
+    fs::create_dir_all(repo_root.join(RUNTIME_DIR))?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! Scaffolding of SPP assets into a target project (`spp project init`).
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Policy, schema and skill files copied into a project, keyed by relative path.
+pub const PROJECT_ASSETS: &[(&str, &str)] = &[
+    ("AGENTS.md", include_str!("../../../AGENTS.md")),
+    (
+        ".agents/policy.md",
+        include_str!("../../../.agents/policy.md"),
+    ),
+    (
+        ".agents/attribution.md",
+        include_str!("../../../.agents/attribution.md"),
+    ),
+    (
+        ".agents/modes/coach.md",
+        include_str!("../../../.agents/modes/coach.md"),
+    ),
+    (
+        ".agents/modes/drive.md",
+        include_str!("../../../.agents/modes/drive.md"),
+    ),
+    (
+        ".agents/modes/normal.md",
+        include_str!("../../../.agents/modes/normal.md"),
+    ),
+    (
+        ".agents/schemas/template_spp.session.schema.json",
+        include_str!("../../../.agents/schemas/template_spp.session.schema.json"),
+    ),
+    (
+        ".agents/schemas/template_spp.session_end.schema.json",
+        include_str!("../../../.agents/schemas/template_spp.session_end.schema.json"),
+    ),
+    (
+        ".agents/schemas/template_spp.gate_tripped.schema.json",
+        include_str!("../../../.agents/schemas/template_spp.gate_tripped.schema.json"),
+    ),
+    (
+        ".agents/schemas/template_spp.weekly_report.schema.json",
+        include_str!("../../../.agents/schemas/template_spp.weekly_report.schema.json"),
+    ),
+    (
+        ".agents/schemas/template_spp.transcript_event.schema.json",
+        include_str!("../../../.agents/schemas/template_spp.transcript_event.schema.json"),
+    ),
+    (
+        ".agents/skills/spp-drive/SKILL.md",
+        include_str!("../../../.agents/skills/spp-drive/SKILL.md"),
+    ),
+    (
+        ".agents/skills/spp-coach/SKILL.md",
+        include_str!("../../../.agents/skills/spp-coach/SKILL.md"),
+    ),
+    (
+        ".agents/skills/spp-stats/SKILL.md",
+        include_str!("../../../.agents/skills/spp-stats/SKILL.md"),
+    ),
+    (
+        "skills/spp-drive/SKILL.md",
+        include_str!("../../../.agents/skills/spp-drive/SKILL.md"),
+    ),
+    (
+        "skills/spp-coach/SKILL.md",
+        include_str!("../../../.agents/skills/spp-coach/SKILL.md"),
+    ),
+    (
+        "skills/spp-stats/SKILL.md",
+        include_str!("../../../.agents/skills/spp-stats/SKILL.md"),
+    ),
+];
+
+/// Contents of `template_spp.config.toml`.
+pub const PROJECT_RUNTIME_CONFIG_ASSET: &str = include_str!("../../../template_spp.config.toml");
+/// Contents of `template_spp.codex.config.toml`.
+pub const PROJECT_CODEX_CONFIG_ASSET: &str =
+    include_str!("../../../template_spp.codex.config.toml");
+
+/// Result of writing a single scaffolded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    Created,
+    Overwritten,
+    Skipped,
+}
+
+/// Writes `content` to `path`, leaving existing files alone unless `force` is set.
+pub fn write_text_asset(path: &Path, content: &str, force: bool) -> Result<WriteOutcome> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if path.exists() && !force {
+        return Ok(WriteOutcome::Skipped);
+    }
+
+    let outcome = if path.exists() {
+        WriteOutcome::Overwritten
+    } else {
+        WriteOutcome::Created
+    };
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(outcome)
+}
+
+/// Appends `rule` to the gitignore at `path` unless an equivalent rule exists.
+/// Returns whether the file was changed.
+pub fn ensure_gitignore_rule(path: &Path, rule: &str) -> Result<bool> {
+    let mut content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let has_rule = content.lines().any(|line| {
+        let normalized = line.trim();
+        normalized == rule || normalized == ".codex-spp/" || normalized == "/.codex-spp"
+    });
+    if has_rule {
+        return Ok(false);
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(rule);
+    content.push('\n');
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(true)
+}
@@ -0,0 +1,889 @@
+//! Background Drive transcript recorder.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration as StdDuration, Instant, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use similar::TextDiff;
+use walkdir::WalkDir;
+
+use crate::backend::GitBackend;
+use crate::config::{effective_include_file_diff, AppConfig, TranscriptConfig};
+use crate::paths::{RUNTIME_DIR, TRANSCRIPT_DIR};
+use crate::state::{ActiveDriveSession, Mode};
+use crate::transcript::{
+    append_start_failed_session_end, extract_chat_messages, file_inode, generate_event_id,
+    read_history_values, resolve_history_path, summarize_text, truncate_to_bytes,
+    validate_transcript_source, write_transcript_event, TranscriptEvent,
+};
+
+/// Inputs of a recorder run; mirrors the hidden `spp drive record` arguments.
+#[derive(Debug, Clone)]
+pub struct RecorderArgs {
+    pub session_id: String,
+    pub log_schema_version: String,
+    pub transcript_path: PathBuf,
+    pub history_path: PathBuf,
+    pub history_offset: u64,
+    pub control_path: PathBuf,
+    pub done_path: PathBuf,
+    pub include_file_diff: bool,
+    pub capture_full_text: bool,
+    pub max_event_bytes: u64,
+    pub poll_interval_ms: u64,
+    pub exclude: Vec<String>,
+}
+
+const MAX_RECORDER_ERRORS: usize = 50;
+
+const FILE_STATE_REVALIDATE_INTERVAL_POLLS: u64 = 30;
+
+/// Summary written by the recorder to its done file when it exits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderDone {
+    pub session_id: String,
+    pub finished_at: DateTime<Utc>,
+    pub history_offset: u64,
+    pub chat_events: u64,
+    pub diff_events: u64,
+    pub errors: Vec<String>,
+}
+
+impl Default for RecorderDone {
+    fn default() -> Self {
+        Self {
+            session_id: String::new(),
+            finished_at: Utc::now(),
+            history_offset: 0,
+            chat_events: 0,
+            diff_events: 0,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Cached content of a workspace text file, reused while its size and mtime are unchanged.
+#[derive(Debug, Clone)]
+pub struct FileState {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+    pub content: Arc<str>,
+}
+
+/// Assembles recorder arguments from the transcript config.
+#[allow(clippy::too_many_arguments)]
+pub fn build_recorder_args(
+    session_id: &str,
+    log_schema_version: &str,
+    transcript_path: &Path,
+    history_path: &Path,
+    history_offset: u64,
+    control_path: &Path,
+    done_path: &Path,
+    transcript: &TranscriptConfig,
+    include_file_diff: bool,
+) -> RecorderArgs {
+    RecorderArgs {
+        session_id: session_id.to_string(),
+        log_schema_version: log_schema_version.to_string(),
+        transcript_path: transcript_path.to_path_buf(),
+        history_path: history_path.to_path_buf(),
+        history_offset,
+        control_path: control_path.to_path_buf(),
+        done_path: done_path.to_path_buf(),
+        include_file_diff,
+        capture_full_text: transcript.capture_full_text,
+        max_event_bytes: transcript.max_event_bytes,
+        poll_interval_ms: transcript.poll_interval_ms,
+        exclude: transcript.watch_exclude.clone(),
+    }
+}
+
+/// Launches `program drive record ...` as a detached background recorder and
+/// returns its pid. `program` must be an `spp` executable.
+pub fn spawn_recorder(program: &Path, repo_root: &Path, args: &RecorderArgs) -> Result<u32> {
+    let mut command = Command::new(program);
+    command
+        .current_dir(repo_root)
+        .arg("drive")
+        .arg("record")
+        .arg("--session-id")
+        .arg(&args.session_id)
+        .arg("--log-schema-version")
+        .arg(&args.log_schema_version)
+        .arg("--transcript-path")
+        .arg(&args.transcript_path)
+        .arg("--history-path")
+        .arg(&args.history_path)
+        .arg("--history-offset")
+        .arg(args.history_offset.to_string())
+        .arg("--control-path")
+        .arg(&args.control_path)
+        .arg("--done-path")
+        .arg(&args.done_path)
+        .arg("--include-file-diff")
+        .arg(args.include_file_diff.to_string())
+        .arg("--capture-full-text")
+        .arg(args.capture_full_text.to_string())
+        .arg("--max-event-bytes")
+        .arg(args.max_event_bytes.to_string())
+        .arg("--poll-interval-ms")
+        .arg(args.poll_interval_ms.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Keep a Ctrl-C meant for a foreground `spp codex` from reaching the recorder.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    for pattern in &args.exclude {
+        command.arg("--exclude").arg(pattern);
+    }
+    let child = command
+        .spawn()
+        .with_context(|| "failed to spawn drive recorder process")?;
+    Ok(child.id())
+}
+
+/// Ignores SIGINT in this process until dropped, so a terminal Ctrl-C meant for
+/// a supervised child does not skip the cleanup after it exits. Create it after
+/// spawning the child: an ignored SIGINT is inherited across `exec`.
+pub struct IgnoreInterrupts {
+    #[cfg(unix)]
+    previous: libc::sighandler_t,
+}
+
+impl IgnoreInterrupts {
+    pub fn new() -> Self {
+        Self {
+            // SAFETY: swaps the disposition of SIGINT; restored in `drop`.
+            #[cfg(unix)]
+            previous: unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) },
+        }
+    }
+}
+
+impl Default for IgnoreInterrupts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for IgnoreInterrupts {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.previous != libc::SIG_ERR {
+            // SAFETY: restores the disposition saved in `new`.
+            unsafe {
+                libc::signal(libc::SIGINT, self.previous);
+            }
+        }
+    }
+}
+
+/// Polls for the recorder done file until `timeout` elapses.
+pub fn wait_for_recorder_done(path: &Path, timeout: StdDuration) -> Result<Option<RecorderDone>> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if path.exists() {
+            let raw = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let done: RecorderDone =
+                serde_json::from_str(&raw).with_context(|| "failed to parse recorder done file")?;
+            return Ok(Some(done));
+        }
+        sleep(StdDuration::from_millis(200));
+    }
+    Ok(None)
+}
+
+/// How long `spp drive stop` waits for the recorder to flush, at least 15 seconds.
+pub fn stop_wait_timeout(config: &AppConfig) -> StdDuration {
+    let poll_ms = config.transcript.poll_interval_ms.max(100);
+    let dynamic_ms = poll_ms.saturating_mul(3);
+    let wait_ms = dynamic_ms.max(15_000);
+    StdDuration::from_millis(wait_ms)
+}
+
+/// Asks process `pid` to exit: `kill -TERM`, or `taskkill /F` on Windows.
+#[allow(clippy::needless_return)]
+pub fn terminate_process(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let status = Command::new("kill")
+            .arg("-TERM")
+            .arg(pid.to_string())
+            .status()
+            .with_context(|| "failed to execute kill command")?;
+        if !status.success() {
+            bail!("kill -TERM {} exited with status {}", pid, status);
+        }
+        return Ok(());
+    }
+
+    #[cfg(windows)]
+    {
+        let status = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .status()
+            .with_context(|| "failed to execute taskkill command")?;
+        if !status.success() {
+            bail!("taskkill {} exited with status {}", pid, status);
+        }
+        return Ok(());
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = pid;
+        bail!("recorder termination is not supported on this platform");
+    }
+}
+
+/// Writes the recorder summary to `path`.
+pub fn write_recorder_done(path: &Path, done: &RecorderDone) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(done)?;
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// The Codex history file transcripts are recorded from, when it does not exist
+/// yet (Codex history persistence disabled, or Codex never run).
+pub fn missing_history_file(
+    repo_root: &Path,
+    config: &TranscriptConfig,
+) -> Result<Option<PathBuf>> {
+    let path = resolve_history_path(repo_root, config)?;
+    Ok((!path.exists()).then_some(path))
+}
+
+/// Writes `session_start` to a new transcript and spawns `program` (an `spp`
+/// executable) as the history recorder for it. On a spawn failure the
+/// transcript is closed with `start_failed`.
+pub fn start_transcript_recorder(
+    program: &Path,
+    config: &AppConfig,
+    git: &dyn GitBackend,
+    mode: &Mode,
+    session_id: &str,
+) -> Result<ActiveDriveSession> {
+    validate_transcript_source(&config.transcript)?;
+    let repo_root = git.repo_root();
+    let include_file_diff = effective_include_file_diff(config);
+
+    let history_path = resolve_history_path(repo_root, &config.transcript)?;
+    if !history_path.exists() {
+        bail!(
+            "history file not found: {}. Ensure Codex history persistence is enabled.",
+            history_path.display()
+        );
+    }
+    let metadata = fs::metadata(&history_path)
+        .with_context(|| format!("failed to stat history file {}", history_path.display()))?;
+    let history_offset = metadata.len();
+
+    let transcript_path = repo_root
+        .join(TRANSCRIPT_DIR)
+        .join(format!("{session_id}.jsonl"));
+    let control_path = repo_root
+        .join(RUNTIME_DIR)
+        .join(format!("{session_id}.control"));
+    let done_path = repo_root
+        .join(RUNTIME_DIR)
+        .join(format!("{session_id}.done"));
+
+    if let Some(parent) = transcript_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(parent) = control_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&control_path, "run\n")
+        .with_context(|| format!("failed to write {}", control_path.display()))?;
+    if done_path.exists() {
+        fs::remove_file(&done_path)
+            .with_context(|| format!("failed to clean stale {}", done_path.display()))?;
+    }
+
+    let start_event = TranscriptEvent {
+        log_schema_version: config.log_schema_version.clone(),
+        event_id: generate_event_id(),
+        session_id: session_id.to_string(),
+        event_type: "session_start".to_string(),
+        timestamp: Utc::now(),
+        mode: mode.clone(),
+        payload: Some(json!({
+            "config_snapshot": {
+                "chat_source": config.transcript.chat_source,
+                "capture_full_text": config.transcript.capture_full_text,
+                "include_file_diff": include_file_diff,
+                "max_event_bytes": config.transcript.max_event_bytes,
+                "poll_interval_ms": config.transcript.poll_interval_ms
+            },
+            "history": {
+                "path": history_path.to_string_lossy(),
+                "offset": history_offset,
+                "inode": file_inode(&metadata)
+            },
+            "git": {
+                "branch": git.current_branch(),
+                "commit": git.current_commit()
+            }
+        })),
+        notes: None,
+    };
+    write_transcript_event(&transcript_path, &start_event)?;
+
+    let recorder_args = build_recorder_args(
+        session_id,
+        &config.log_schema_version,
+        &transcript_path,
+        &history_path,
+        history_offset,
+        &control_path,
+        &done_path,
+        &config.transcript,
+        include_file_diff,
+    );
+    let recorder_pid = match spawn_recorder(program, repo_root, &recorder_args) {
+        Ok(pid) => pid,
+        Err(err) => {
+            let spawn_error_message = format!("{err:#}");
+            let _ = append_start_failed_session_end(
+                &transcript_path,
+                &config.log_schema_version,
+                session_id,
+                mode.clone(),
+                &spawn_error_message,
+            );
+            let _ = fs::remove_file(&control_path);
+            if done_path.exists() {
+                let _ = fs::remove_file(&done_path);
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(ActiveDriveSession {
+        session_id: session_id.to_string(),
+        started_at: Utc::now(),
+        history_path: history_path.to_string_lossy().to_string(),
+        history_offset,
+        transcript_path: transcript_path.to_string_lossy().to_string(),
+        control_path: control_path.to_string_lossy().to_string(),
+        done_path: done_path.to_string_lossy().to_string(),
+        recorder_pid: Some(recorder_pid),
+    })
+}
+
+/// Asks `active`'s recorder to stop, terminating it if it does not finish in
+/// time, and closes the transcript with `session_end` for `reason` (suffixed
+/// `_timeout` after a forced stop). Returns the summary and the end time.
+pub fn stop_transcript_recorder(
+    config: &AppConfig,
+    active: &ActiveDriveSession,
+    mode: &Mode,
+    reason: &str,
+) -> Result<(RecorderDone, DateTime<Utc>)> {
+    let control_path = PathBuf::from(&active.control_path);
+    let done_path = PathBuf::from(&active.done_path);
+    let transcript_path = PathBuf::from(&active.transcript_path);
+
+    fs::write(&control_path, "stop\n")
+        .with_context(|| format!("failed to write {}", control_path.display()))?;
+
+    let wait_timeout = stop_wait_timeout(config);
+    let done = wait_for_recorder_done(&done_path, wait_timeout)?;
+    let timeout = done.is_none();
+    let mut timeout_errors = Vec::new();
+    if timeout {
+        if let Some(pid) = active.recorder_pid {
+            if let Err(err) = terminate_process(pid) {
+                timeout_errors.push(format!(
+                    "recorder did not finish in time; failed to terminate pid {}: {}",
+                    pid, err
+                ));
+            } else {
+                timeout_errors.push(format!(
+                    "recorder did not finish in time; sent termination signal to pid {}",
+                    pid
+                ));
+            }
+        } else {
+            timeout_errors.push("recorder did not finish in time (pid unavailable)".to_string());
+        }
+    }
+    let done = match done {
+        Some(done) => done,
+        None => RecorderDone {
+            session_id: active.session_id.clone(),
+            finished_at: Utc::now(),
+            history_offset: active.history_offset,
+            chat_events: 0,
+            diff_events: 0,
+            errors: if timeout_errors.is_empty() {
+                vec!["recorder did not finish in time".to_string()]
+            } else {
+                timeout_errors
+            },
+        },
+    };
+
+    let end_event = TranscriptEvent {
+        log_schema_version: config.log_schema_version.clone(),
+        event_id: generate_event_id(),
+        session_id: active.session_id.clone(),
+        event_type: "session_end".to_string(),
+        timestamp: Utc::now(),
+        mode: mode.clone(),
+        payload: Some(json!({
+            "stats": {
+                "chat_events": done.chat_events,
+                "diff_events": done.diff_events,
+                "duration_sec": (Utc::now() - active.started_at).num_seconds().max(0),
+                "history_offset": done.history_offset
+            },
+            "reason": if timeout { format!("{reason}_timeout") } else { reason.to_string() },
+            "errors": done.errors.clone()
+        })),
+        notes: None,
+    };
+    write_transcript_event(&transcript_path, &end_event)?;
+
+    if !timeout {
+        let _ = fs::remove_file(control_path);
+        let _ = fs::remove_file(done_path);
+    }
+    Ok((done, end_event.timestamp))
+}
+
+/// Runs the recorder until its control file says `stop` (or disappears),
+/// appending `chat_*` events from the history file and, when enabled,
+/// `file_diff` events from workspace snapshots.
+pub fn run_drive_recorder_loop(repo_root: &Path, args: &RecorderArgs) -> Result<RecorderDone> {
+    let mut history_offset = args.history_offset;
+    let mut snapshot = if args.include_file_diff {
+        capture_workspace_text_files(repo_root, &args.exclude, None, false)?
+    } else {
+        HashMap::new()
+    };
+    let mut chat_events = 0_u64;
+    let mut diff_events = 0_u64;
+    let mut errors = Vec::new();
+    let mut error_seen = HashSet::new();
+    let mut poll_count = 0_u64;
+    let poll_interval = StdDuration::from_millis(args.poll_interval_ms.max(100));
+
+    loop {
+        if should_stop_recorder(&args.control_path) {
+            break;
+        }
+
+        match read_history_values(&args.history_path, history_offset) {
+            Ok((next_offset, values)) => {
+                history_offset = next_offset;
+                for value in values {
+                    let messages = extract_chat_messages(&value, args.max_event_bytes);
+                    for message in messages {
+                        let event_type = if message.role == "assistant" {
+                            "chat_assistant"
+                        } else {
+                            "chat_user"
+                        };
+                        let content = if args.capture_full_text {
+                            truncate_to_bytes(&message.content, args.max_event_bytes)
+                        } else {
+                            truncate_to_bytes(
+                                &summarize_text(&message.content),
+                                args.max_event_bytes,
+                            )
+                        };
+                        let payload = json!({
+                            "role": message.role,
+                            "message_id": message.message_id,
+                            "content": content,
+                            "raw": message.raw
+                        });
+                        let event = TranscriptEvent {
+                            log_schema_version: args.log_schema_version.clone(),
+                            event_id: generate_event_id(),
+                            session_id: args.session_id.clone(),
+                            event_type: event_type.to_string(),
+                            timestamp: Utc::now(),
+                            mode: Mode::Drive,
+                            payload: Some(payload),
+                            notes: None,
+                        };
+                        if let Err(err) = write_transcript_event(&args.transcript_path, &event) {
+                            push_recorder_error(
+                                &mut errors,
+                                &mut error_seen,
+                                format!("failed to write chat event: {err:#}"),
+                            );
+                        } else {
+                            chat_events += 1;
+                        }
+                    }
+                }
+            }
+            Err(err) => push_recorder_error(
+                &mut errors,
+                &mut error_seen,
+                format!("failed to read history stream: {err:#}"),
+            ),
+        }
+
+        if should_stop_recorder(&args.control_path) {
+            break;
+        }
+
+        if args.include_file_diff {
+            poll_count = poll_count.saturating_add(1);
+            let force_content_refresh = should_force_refresh_file_state(poll_count);
+            match capture_workspace_text_files(
+                repo_root,
+                &args.exclude,
+                Some(&snapshot),
+                force_content_refresh,
+            ) {
+                Ok(next_snapshot) => {
+                    let mut paths: HashSet<String> = HashSet::new();
+                    paths.extend(snapshot.keys().cloned());
+                    paths.extend(next_snapshot.keys().cloned());
+                    let mut paths = paths.into_iter().collect::<Vec<_>>();
+                    paths.sort();
+                    for path in paths {
+                        let before = snapshot.get(&path).map(|state| state.content.as_ref());
+                        let after = next_snapshot.get(&path).map(|state| state.content.as_ref());
+                        if before == after {
+                            continue;
+                        }
+                        if let Some(diff) = build_unified_diff(before, after, &path) {
+                            let payload = json!({
+                                "path": path,
+                                "diff_unified": truncate_to_bytes(&diff, args.max_event_bytes),
+                                "bytes": diff.len(),
+                                "language": guess_language(&path),
+                            });
+                            let event = TranscriptEvent {
+                                log_schema_version: args.log_schema_version.clone(),
+                                event_id: generate_event_id(),
+                                session_id: args.session_id.clone(),
+                                event_type: "file_diff".to_string(),
+                                timestamp: Utc::now(),
+                                mode: Mode::Drive,
+                                payload: Some(payload),
+                                notes: None,
+                            };
+                            if let Err(err) = write_transcript_event(&args.transcript_path, &event)
+                            {
+                                push_recorder_error(
+                                    &mut errors,
+                                    &mut error_seen,
+                                    format!("failed to write diff event: {err:#}"),
+                                );
+                            } else {
+                                diff_events += 1;
+                            }
+                        }
+                    }
+                    snapshot = next_snapshot;
+                }
+                Err(err) => push_recorder_error(
+                    &mut errors,
+                    &mut error_seen,
+                    format!("failed to capture workspace snapshot: {err:#}"),
+                ),
+            }
+        }
+
+        sleep(poll_interval);
+    }
+
+    Ok(RecorderDone {
+        session_id: args.session_id.clone(),
+        finished_at: Utc::now(),
+        history_offset,
+        chat_events,
+        diff_events,
+        errors,
+    })
+}
+
+fn push_recorder_error(errors: &mut Vec<String>, seen: &mut HashSet<String>, message: String) {
+    let truncated_marker = "__truncated__";
+    if errors.len() > MAX_RECORDER_ERRORS {
+        return;
+    }
+
+    if !seen.insert(message.clone()) {
+        return;
+    }
+
+    errors.push(message);
+    if errors.len() == MAX_RECORDER_ERRORS && !seen.contains(truncated_marker) {
+        let _ = seen.insert(truncated_marker.to_string());
+        errors.push(format!(
+            "recorder errors truncated at {} unique entries",
+            MAX_RECORDER_ERRORS
+        ));
+    }
+}
+
+fn should_stop_recorder(control_path: &Path) -> bool {
+    match fs::read_to_string(control_path) {
+        Ok(value) => value.trim() == "stop",
+        Err(err) if err.kind() == ErrorKind::NotFound => true,
+        Err(_) => true,
+    }
+}
+
+fn should_force_refresh_file_state(poll_count: u64) -> bool {
+    poll_count > 0 && poll_count.is_multiple_of(FILE_STATE_REVALIDATE_INTERVAL_POLLS)
+}
+
+/// Snapshots UTF-8 text files under `repo_root` (up to 2 MB each), reusing
+/// unchanged entries from `previous` unless `force_content_refresh` is set.
+pub fn capture_workspace_text_files(
+    repo_root: &Path,
+    exclude: &[String],
+    previous: Option<&HashMap<String, FileState>>,
+    force_content_refresh: bool,
+) -> Result<HashMap<String, FileState>> {
+    let mut snapshot = HashMap::new();
+    for entry in WalkDir::new(repo_root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| should_walk_entry(repo_root, entry.path(), exclude))
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = match relative_path_string(repo_root, entry.path()) {
+            Some(rel_path) if !rel_path.is_empty() => rel_path,
+            _ => continue,
+        };
+        if is_excluded_path(&rel_path, exclude) {
+            continue;
+        }
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let len = metadata.len();
+        if len > 2_000_000 {
+            continue;
+        }
+        let modified = metadata.modified().ok();
+
+        if let Some(prev) = previous.and_then(|prev| prev.get(&rel_path)) {
+            if should_reuse_previous_file_state(prev, len, modified, force_content_refresh) {
+                snapshot.insert(rel_path, prev.clone());
+                continue;
+            }
+        }
+
+        let mut bytes = Vec::new();
+        if File::open(entry.path())
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .is_err()
+        {
+            continue;
+        }
+        if bytes.contains(&0) {
+            continue;
+        }
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        snapshot.insert(
+            rel_path,
+            FileState {
+                len,
+                modified,
+                content: Arc::<str>::from(text),
+            },
+        );
+    }
+    Ok(snapshot)
+}
+
+/// `path` relative to `repo_root` with forward slashes.
+pub fn relative_path_string(repo_root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(repo_root).ok()?;
+    Some(rel.to_string_lossy().replace('\\', "/"))
+}
+
+fn should_walk_entry(repo_root: &Path, entry_path: &Path, excludes: &[String]) -> bool {
+    let Some(rel_path) = relative_path_string(repo_root, entry_path) else {
+        return true;
+    };
+    if rel_path.is_empty() {
+        return true;
+    }
+    !is_excluded_path(&rel_path, excludes)
+}
+
+fn should_reuse_previous_file_state(
+    previous: &FileState,
+    len: u64,
+    modified: Option<SystemTime>,
+    force_content_refresh: bool,
+) -> bool {
+    !force_content_refresh && previous.len == len && previous.modified == modified
+}
+
+/// Whether `path` equals or lies under one of the `excludes` prefixes.
+pub fn is_excluded_path(path: &str, excludes: &[String]) -> bool {
+    excludes.iter().any(|pattern| {
+        let normalized = pattern.trim_start_matches("./").trim_end_matches('/');
+        path == normalized || path.starts_with(&format!("{normalized}/"))
+    })
+}
+
+/// Unified diff between two file versions, `None` when they are identical.
+pub fn build_unified_diff(before: Option<&str>, after: Option<&str>, path: &str) -> Option<String> {
+    let old = before.unwrap_or_default();
+    let new = after.unwrap_or_default();
+    if old == new {
+        return None;
+    }
+    let diff = TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string();
+    if diff.trim().is_empty() {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+/// Lower-cased file extension, or `"unknown"`.
+pub fn guess_language(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES;
+    use crate::test_support::TempDirGuard;
+
+    #[test]
+    fn should_walk_entry_skips_excluded_dirs_and_keeps_similar_prefixes() {
+        let repo_root = PathBuf::from("/tmp/repo");
+        let excludes = vec![".codex-spp/".to_string(), ".git/".to_string()];
+
+        assert!(!should_walk_entry(
+            &repo_root,
+            &repo_root.join(".codex-spp"),
+            &excludes
+        ));
+        assert!(!should_walk_entry(
+            &repo_root,
+            &repo_root.join(".codex-spp/runtime"),
+            &excludes
+        ));
+        assert!(should_walk_entry(
+            &repo_root,
+            &repo_root.join(".codex-spp-backup"),
+            &excludes
+        ));
+    }
+
+    #[test]
+    fn missing_history_file_reports_the_path_until_it_exists() -> Result<()> {
+        let temp = TempDirGuard::new("spp-recorder-history")?;
+        let config = TranscriptConfig {
+            history_path: "history.jsonl".to_string(),
+            ..TranscriptConfig::default()
+        };
+        let path = temp.path().join("history.jsonl");
+        assert_eq!(
+            missing_history_file(temp.path(), &config)?,
+            Some(path.clone())
+        );
+        fs::write(&path, "")?;
+        assert_eq!(missing_history_file(temp.path(), &config)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn recorder_loop_stops_before_history_read_when_stop_requested() -> Result<()> {
+        let temp = TempDirGuard::new("spp-recorder-stop")?;
+        let control_path = temp.path().join("control");
+        fs::write(&control_path, "stop\n")?;
+
+        let args = RecorderArgs {
+            session_id: "session".to_string(),
+            log_schema_version: "1.1".to_string(),
+            transcript_path: temp.path().join("transcript.jsonl"),
+            history_path: temp.path().join("missing-history.jsonl"),
+            history_offset: 0,
+            control_path,
+            done_path: temp.path().join("done.json"),
+            include_file_diff: true,
+            capture_full_text: true,
+            max_event_bytes: DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES,
+            poll_interval_ms: 100,
+            exclude: Vec::new(),
+        };
+
+        let done = run_drive_recorder_loop(temp.path(), &args)?;
+        assert_eq!(done.chat_events, 0);
+        assert_eq!(done.diff_events, 0);
+        assert!(done.errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn should_reuse_previous_file_state_respects_force_refresh() {
+        let previous = FileState {
+            len: 10,
+            modified: Some(SystemTime::UNIX_EPOCH),
+            content: Arc::<str>::from("previous"),
+        };
+
+        assert!(should_reuse_previous_file_state(
+            &previous,
+            10,
+            Some(SystemTime::UNIX_EPOCH),
+            false
+        ));
+        assert!(!should_reuse_previous_file_state(
+            &previous,
+            10,
+            Some(SystemTime::UNIX_EPOCH),
+            true
+        ));
+    }
+
+    #[test]
+    fn should_force_refresh_file_state_uses_fixed_interval() {
+        assert!(!should_force_refresh_file_state(1));
+        assert!(!should_force_refresh_file_state(
+            FILE_STATE_REVALIDATE_INTERVAL_POLLS - 1
+        ));
+        assert!(should_force_refresh_file_state(
+            FILE_STATE_REVALIDATE_INTERVAL_POLLS
+        ));
+    }
+}
@@ -0,0 +1,581 @@
+//! Text rendering of gate status for `spp status`.
+
+use std::fmt::Write as _;
+
+use crate::config::GateMetric;
+use crate::gate::{commit_ratio, WeeklyReport, WipProjection};
+use crate::infer::InferredCommit;
+use crate::state::{Mode, State};
+use crate::trend::TrendPoint;
+
+/// Legacy two-line status output (`spp status --plain`).
+pub fn render_status_plain(state: &State, report: &WeeklyReport) -> String {
+    let mut lines = vec![
+        format!("mode: {:?}", state.mode),
+        format!(
+            "ratio: {:.3} (target: {:.3}) gate_passed: {}",
+            report.ratio, report.target_ratio, report.gate_passed
+        ),
+    ];
+    if let Some(pause_until) = state.pause_until {
+        lines.push(format!("pause_until: {}", pause_until.to_rfc3339()));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Two-block status summary: gate overview, then human/AI breakdown, pause and notes,
+/// followed by the provisional uncommitted-work projection when given.
+pub fn render_status_rich(
+    state: &State,
+    report: &WeeklyReport,
+    pause_active: bool,
+    projection: Option<&WipProjection>,
+) -> String {
+    let gate = if report.gate_passed { "PASS" } else { "FAIL" };
+    let mode = &state.mode;
+    let reason = status_gate_reason(state, report, pause_active);
+    let lines_delta = report.ratio - report.target_ratio;
+    let commits_ratio = commit_ratio(report);
+    let pause_text = state
+        .pause_until
+        .map(|pause_until| pause_until.to_rfc3339())
+        .unwrap_or_else(|| "none".to_string());
+
+    let mut out = String::new();
+    let _ = writeln!(
+        &mut out,
+        "Mode: {mode} | Gate: {gate} | Week: {}-W{:02}",
+        report.year, report.iso_week
+    );
+    let _ = writeln!(&mut out, "Gate reason: {reason}");
+    let _ = writeln!(
+        &mut out,
+        "Window: {} -> {}",
+        report.window_start.format("%Y-%m-%d %H:%M UTC"),
+        report.window_end.format("%Y-%m-%d %H:%M UTC")
+    );
+    let _ = writeln!(
+        &mut out,
+        "Lines: {} | Target: {} | Delta: {}",
+        percent_1dp(report.ratio),
+        percent_1dp(report.target_ratio),
+        points_delta(lines_delta)
+    );
+    let _ = writeln!(&mut out, "Commits: {}", percent_1dp(commits_ratio));
+    if report.gate_metric != GateMetric::Added {
+        let _ = writeln!(
+            &mut out,
+            "Gate metric: {} | {} | Delta: {}",
+            report.gate_metric,
+            percent_1dp(report.gate_ratio),
+            points_delta(report.gate_ratio - report.target_ratio)
+        );
+    }
+    if let Some(email) = &report.gate_author {
+        let author_ratio = report
+            .authors
+            .iter()
+            .find(|author| &author.email == email)
+            .map(|author| author.ratio)
+            .unwrap_or(1.0);
+        let _ = writeln!(
+            &mut out,
+            "Gate author: {email} | Lines: {} | Delta: {}",
+            percent_1dp(author_ratio),
+            points_delta(author_ratio - report.target_ratio)
+        );
+    }
+    if let Some(trip) = &state.gate_trip {
+        let _ = writeln!(
+            &mut out,
+            "Gate trip: {} | {} | Ratio: {} | Target: {}",
+            trip.at.format("%Y-%m-%d %H:%M UTC"),
+            trip.mode,
+            percent_1dp(trip.ratio),
+            percent_1dp(trip.target)
+        );
+    }
+    if let Some(recovery) = &report.recovery {
+        let remaining = recovery.remaining();
+        let _ = writeln!(
+            &mut out,
+            "Recovery: Drive {}/{} min | Human lines {}/{} | {}",
+            recovery.drive_minutes,
+            recovery.required_drive_minutes,
+            recovery.human_lines,
+            recovery.required_human_lines,
+            if remaining.is_empty() {
+                "complete".to_string()
+            } else {
+                format!("{} to go", remaining.join(", "))
+            }
+        );
+    }
+    let _ = writeln!(&mut out);
+    let _ = writeln!(
+        &mut out,
+        "Human: {} commits / {} lines",
+        report.human_commit_count, report.human_lines_added
+    );
+    let _ = writeln!(
+        &mut out,
+        "AI: {} commits / {} lines",
+        report.ai_commit_count, report.ai_lines_added
+    );
+    if !report.authors.is_empty() {
+        let _ = writeln!(&mut out, "Authors:");
+        for author in &report.authors {
+            let _ = writeln!(
+                &mut out,
+                "  - {}: {} | human {} commits / {} lines | AI {} commits / {} lines",
+                author.email,
+                percent_1dp(author.ratio),
+                author.human_commit_count,
+                author.human_lines_added,
+                author.ai_commit_count,
+                author.ai_lines_added
+            );
+        }
+    }
+    let _ = writeln!(&mut out, "Pause until: {pause_text}");
+    if report.notes.is_empty() {
+        let _ = writeln!(&mut out, "Notes: none");
+    } else {
+        let _ = writeln!(&mut out, "Notes:");
+        for note in &report.notes {
+            let _ = writeln!(&mut out, "  - {note}");
+        }
+    }
+
+    if let Some(projection) = projection {
+        let label = match report.gate_metric {
+            GateMetric::Added => "Lines".to_string(),
+            metric => metric.to_string(),
+        };
+        let gate = if projection.gate_passed {
+            "PASS"
+        } else {
+            "FAIL"
+        };
+        let _ = writeln!(&mut out);
+        let _ = writeln!(
+            &mut out,
+            "Projected (provisional, uncommitted): +{} / -{} lines in {} files | AI share {}",
+            projection.changes.added,
+            projection.changes.deleted,
+            projection.changes.files,
+            percent_1dp(projection.ai_share)
+        );
+        let _ = writeln!(
+            &mut out,
+            "Projected gate: {gate} | {label}: {} | Delta: {}",
+            percent_1dp(projection.gate_ratio),
+            points_delta(projection.gate_ratio - report.target_ratio)
+        );
+    }
+
+    out
+}
+
+/// Compact two-line summary of one report, used by `spp report`.
+pub fn render_report_summary(report: &WeeklyReport) -> String {
+    let gate = if report.gate_passed { "PASS" } else { "FAIL" };
+    let mut out = String::new();
+    let _ = writeln!(
+        &mut out,
+        "{}-W{:02} | Gate: {gate} | Lines: {} | Target: {} | Delta: {} | Commits: {}",
+        report.year,
+        report.iso_week,
+        percent_1dp(report.ratio),
+        percent_1dp(report.target_ratio),
+        points_delta(report.ratio - report.target_ratio),
+        percent_1dp(commit_ratio(report))
+    );
+    let _ = writeln!(
+        &mut out,
+        "  Human: {} commits / {} lines | AI: {} commits / {} lines",
+        report.human_commit_count,
+        report.human_lines_added,
+        report.ai_commit_count,
+        report.ai_lines_added
+    );
+    out
+}
+
+/// Sparkline of the weekly ratio followed by a per-week table, used by `spp trend`.
+pub fn render_trend_table(points: &[TrendPoint], target_ratio: f64, window: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        &mut out,
+        "Trend: {} weeks | Rolling window: {} | Target: {}",
+        points.len(),
+        window,
+        percent_1dp(target_ratio)
+    );
+    let ratios = points.iter().map(|point| point.ratio).collect::<Vec<_>>();
+    let rolling = points
+        .iter()
+        .map(|point| point.rolling_ratio)
+        .collect::<Vec<_>>();
+    let _ = writeln!(&mut out, "Lines:   {}", sparkline(&ratios));
+    let _ = writeln!(&mut out, "Rolling: {}", sparkline(&rolling));
+    let _ = writeln!(&mut out);
+    let _ = writeln!(
+        &mut out,
+        "{:<9} {:>7} {:>8} {:>9} {:>8} {:>7} {:>7}",
+        "Week", "Lines", "Rolling", "Delta", "Commits", "Human", "AI"
+    );
+    for point in points {
+        let delta = point
+            .delta
+            .map(points_delta)
+            .unwrap_or_else(|| "-".to_string());
+        let _ = writeln!(
+            &mut out,
+            "{:<9} {:>7} {:>8} {:>9} {:>8} {:>7} {:>7}",
+            format!("{}-W{:02}", point.year, point.iso_week),
+            percent_1dp(point.ratio),
+            percent_1dp(point.rolling_ratio),
+            delta,
+            percent_1dp(point.commit_ratio),
+            point.human_lines_added,
+            point.ai_lines_added
+        );
+    }
+    out
+}
+
+/// CSV export of trend points with a header row.
+pub fn render_trend_csv(points: &[TrendPoint]) -> String {
+    let mut out = String::from(
+        "week,ratio,rolling_ratio,delta,commit_ratio,human_lines_added,ai_lines_added\n",
+    );
+    for point in points {
+        let delta = point
+            .delta
+            .map(|delta| format!("{delta:.4}"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            &mut out,
+            "{}-W{:02},{:.4},{:.4},{},{:.4},{},{}",
+            point.year,
+            point.iso_week,
+            point.ratio,
+            point.rolling_ratio,
+            delta,
+            point.commit_ratio,
+            point.human_lines_added,
+            point.ai_lines_added
+        );
+    }
+    out
+}
+
+/// One block character per value, scaled over the `0.0..=1.0` ratio range.
+pub fn sparkline(values: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    values
+        .iter()
+        .map(|value| {
+            let idx = (value.clamp(0.0, 1.0) * (BARS.len() - 1) as f64).round() as usize;
+            BARS[idx]
+        })
+        .collect()
+}
+
+/// Short human-readable explanation of the gate outcome.
+pub fn status_gate_reason(
+    state: &State,
+    report: &WeeklyReport,
+    pause_active: bool,
+) -> &'static str {
+    let gate_forced = state.drive_reason.as_deref() == Some("gate");
+    let recovering = report
+        .recovery
+        .as_ref()
+        .is_some_and(|recovery| !recovery.is_complete());
+    if report.gate_passed {
+        return match (&state.mode, gate_forced) {
+            (Mode::Coach, true) if recovering => "on target, coach held until recovery",
+            (Mode::Drive, true) if recovering => "on target, drive held until recovery",
+            (Mode::Coach, true) => "on target, coach held by hysteresis",
+            (Mode::Drive, true) => "on target, drive held by hysteresis",
+            _ => "on target",
+        };
+    }
+    if pause_active {
+        return "below target, gate paused";
+    }
+    match (&state.mode, gate_forced) {
+        (Mode::Coach, true) => "below target, coach enforced",
+        (Mode::Drive, true) => "below target, drive enforced",
+        _ => "below target",
+    }
+}
+
+/// `spp attrib infer` table; rows at or above `min_share` are marked as suggested.
+pub fn render_inferred_table(inferred: &[InferredCommit], min_share: f64) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        &mut out,
+        "{:<10} {:>8} {:>9} {:<9}  Subject",
+        "Commit", "AI share", "Matched", "Suggest"
+    );
+    for row in inferred {
+        let suggestion = &row.suggestion;
+        let short = row.commit.get(..10).unwrap_or(&row.commit);
+        let marker = if suggestion.ai_share >= min_share {
+            "yes"
+        } else {
+            "-"
+        };
+        let _ = writeln!(
+            &mut out,
+            "{:<10} {:>8} {:>9} {:<9}  {}",
+            short,
+            percent_1dp(suggestion.ai_share),
+            format!(
+                "{}/{}",
+                suggestion.matched_lines, suggestion.considered_lines
+            ),
+            marker,
+            row.subject
+        );
+    }
+    out
+}
+
+/// Formats a ratio as a percentage with one decimal place.
+pub fn percent_1dp(ratio: f64) -> String {
+    format!("{:.1}%", ratio * 100.0)
+}
+
+/// Formats a ratio difference as signed percentage points.
+pub fn points_delta(delta: f64) -> String {
+    format!("{:+.1}pt", delta * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gate::AuthorBreakdown;
+    use crate::test_support::sample_weekly_report;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn render_status_plain_matches_legacy_output_shape() {
+        let state = State::default();
+        let report = sample_weekly_report();
+        let rendered = render_status_plain(&state, &report);
+        assert_eq!(
+            rendered,
+            "mode: Normal\nratio: 0.600 (target: 0.700) gate_passed: false\n"
+        );
+    }
+
+    #[test]
+    fn render_status_rich_always_shows_pause_and_notes() {
+        let state = State::default();
+        let report = sample_weekly_report();
+        let rendered = render_status_rich(&state, &report, false, None);
+
+        assert!(rendered.contains("Mode: Normal | Gate: FAIL | Week: 2026-W07"));
+        assert!(rendered.contains("Gate reason: below target"));
+        assert!(rendered.contains("Window: 2026-02-09 00:00 UTC -> 2026-02-16 00:00 UTC"));
+        assert!(rendered.contains("Lines: 60.0% | Target: 70.0% | Delta: -10.0pt"));
+        assert!(rendered.contains("Commits: 75.0%"));
+        assert!(rendered.contains("Commits: 75.0%\n\nHuman: 3 commits / 60 lines"));
+        assert!(rendered.contains("Human: 3 commits / 60 lines"));
+        assert!(rendered.contains("AI: 1 commits / 40 lines"));
+        assert!(rendered.contains("Pause until: none"));
+        assert!(rendered.contains("Notes: none"));
+    }
+
+    #[test]
+    fn render_status_rich_shows_pause_and_each_note() {
+        let state = State {
+            pause_until: Some(Utc::now() + Duration::hours(1)),
+            ..State::default()
+        };
+
+        let mut report = sample_weekly_report();
+        report.notes = vec![
+            "gate evaluation bypassed due to active pause".to_string(),
+            "sample note".to_string(),
+        ];
+
+        let rendered = render_status_rich(&state, &report, true, None);
+        assert!(rendered.contains("Gate reason: below target, gate paused"));
+        assert!(rendered.contains("Pause until: "));
+        assert!(rendered.contains("Notes:\n  - gate evaluation bypassed due to active pause"));
+        assert!(rendered.contains("  - sample note"));
+    }
+
+    #[test]
+    fn render_report_summary_shows_week_gate_and_breakdown() {
+        let report = sample_weekly_report();
+        assert_eq!(
+            render_report_summary(&report),
+            "2026-W07 | Gate: FAIL | Lines: 60.0% | Target: 70.0% | Delta: -10.0pt | Commits: 75.0%\n  Human: 3 commits / 60 lines | AI: 1 commits / 40 lines\n"
+        );
+    }
+
+    #[test]
+    fn render_trend_outputs_sparkline_table_and_csv() {
+        let points = vec![
+            TrendPoint {
+                year: 2026,
+                iso_week: 39,
+                ratio: 1.0,
+                commit_ratio: 1.0,
+                human_lines_added: 10,
+                ai_lines_added: 0,
+                rolling_ratio: 1.0,
+                delta: None,
+            },
+            TrendPoint {
+                year: 2026,
+                iso_week: 40,
+                ratio: 0.5,
+                commit_ratio: 0.5,
+                human_lines_added: 10,
+                ai_lines_added: 10,
+                rolling_ratio: 0.75,
+                delta: Some(-0.5),
+            },
+        ];
+
+        let table = render_trend_table(&points, 0.7, 4);
+        assert!(table.contains("Trend: 2 weeks | Rolling window: 4 | Target: 70.0%"));
+        assert!(table.contains("Lines:   █▅"));
+        assert!(table.contains("2026-W40    50.0%    75.0%   -50.0pt"));
+
+        let csv = render_trend_csv(&points);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("2026-W39,1.0000,1.0000,,1.0000,10,0")
+        );
+        assert_eq!(
+            csv.lines().nth(2),
+            Some("2026-W40,0.5000,0.7500,-0.5000,0.5000,10,10")
+        );
+    }
+
+    #[test]
+    fn render_status_rich_shows_gate_author_and_breakdown() {
+        let state = State::default();
+        let mut report = sample_weekly_report();
+        report.gate_author = Some("me@example.com".to_string());
+        report.authors = vec![AuthorBreakdown {
+            email: "me@example.com".to_string(),
+            human_lines_added: 80,
+            ai_lines_added: 20,
+            human_commit_count: 2,
+            ai_commit_count: 1,
+            ratio: 0.8,
+            ..AuthorBreakdown::default()
+        }];
+
+        let rendered = render_status_rich(&state, &report, false, None);
+        assert!(rendered.contains("Gate author: me@example.com | Lines: 80.0% | Delta: +10.0pt"));
+        assert!(rendered.contains(
+            "Authors:\n  - me@example.com: 80.0% | human 2 commits / 80 lines | AI 1 commits / 20 lines"
+        ));
+    }
+
+    #[test]
+    fn render_status_rich_shows_non_default_gate_metric() {
+        let state = State::default();
+        let mut report = sample_weekly_report();
+        assert!(!render_status_rich(&state, &report, false, None).contains("Gate metric:"));
+
+        report.gate_metric = GateMetric::AddedDeleted;
+        report.gate_ratio = report.metric_ratios.added_deleted;
+        let rendered = render_status_rich(&state, &report, false, None);
+        assert!(rendered.contains("Gate metric: added+deleted | 60.9% | Delta: -9.1pt"));
+    }
+
+    #[test]
+    fn render_status_rich_appends_projection_when_given() {
+        let state = State::default();
+        let report = sample_weekly_report();
+        let projection = WipProjection {
+            changes: crate::metrics::CountedLines {
+                added: 40,
+                deleted: 2,
+                files: 3,
+                ..Default::default()
+            },
+            ai_share: 0.25,
+            gate_ratio: 0.7,
+            gate_passed: true,
+        };
+        let rendered = render_status_rich(&state, &report, false, Some(&projection));
+        assert!(rendered.ends_with(
+            "\nProjected (provisional, uncommitted): +40 / -2 lines in 3 files | AI share 25.0%\nProjected gate: PASS | Lines: 70.0% | Delta: +0.0pt\n"
+        ));
+    }
+
+    #[test]
+    fn render_status_rich_shows_gate_trip_and_remaining_recovery() {
+        let at = chrono::DateTime::parse_from_rfc3339("2026-02-10T09:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let state = State {
+            mode: Mode::Drive,
+            drive_reason: Some("gate".to_string()),
+            gate_trip: Some(crate::state::GateTrip {
+                at,
+                mode: Mode::Drive,
+                held: Mode::Drive,
+                ratio: 0.55,
+                target: 0.7,
+                drive_seconds: 0,
+            }),
+            ..State::default()
+        };
+        let mut report = sample_weekly_report();
+        report.gate_passed = true;
+        report.recovery = Some(crate::gate::RecoveryProgress {
+            tripped_at: at,
+            drive_minutes: 12,
+            required_drive_minutes: 30,
+            human_lines: 120,
+            required_human_lines: 100,
+        });
+        let rendered = render_status_rich(&state, &report, false, None);
+        assert!(rendered.contains("Gate reason: on target, drive held until recovery\n"));
+        assert!(rendered
+            .contains("Gate trip: 2026-02-10 09:30 UTC | Drive | Ratio: 55.0% | Target: 70.0%\n"));
+        assert!(rendered.contains(
+            "Recovery: Drive 12/30 min | Human lines 120/100 | 18 Drive minutes to go\n"
+        ));
+    }
+
+    #[test]
+    fn status_gate_reason_covers_all_branches() {
+        let state = State::default();
+        let mut report = sample_weekly_report();
+
+        report.gate_passed = true;
+        assert_eq!(status_gate_reason(&state, &report, false), "on target");
+
+        report.gate_passed = false;
+        assert_eq!(
+            status_gate_reason(&state, &report, true),
+            "below target, gate paused"
+        );
+
+        let drive_state = State {
+            mode: Mode::Drive,
+            drive_reason: Some("gate".to_string()),
+            ..State::default()
+        };
+        assert_eq!(
+            status_gate_reason(&drive_state, &report, false),
+            "below target, drive enforced"
+        );
+
+        assert_eq!(status_gate_reason(&state, &report, false), "below target");
+    }
+}
@@ -0,0 +1,357 @@
+//! Persisted runtime state (`.codex-spp/state.json`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::paths::STATE_FILE;
+
+/// Codex operating mode enforced by `spp`, ordered from least to most restrictive.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Normal,
+    /// Codex may still edit, but asks before each change and shows a warning banner.
+    Coach,
+    Drive,
+}
+
+impl Mode {
+    /// Lower-case name, as in `state.json` and `[codex.<mode>]`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Coach => "coach",
+            Self::Drive => "drive",
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Normal => "Normal",
+            Self::Coach => "Coach",
+            Self::Drive => "Drive",
+        })
+    }
+}
+
+/// Who a commit is attributed to.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Actor {
+    #[default]
+    Human,
+    Ai,
+    /// Human and AI both contributed; see [`Actor::default_ai_share`].
+    Mixed,
+}
+
+impl Actor {
+    /// AI share of added lines implied by the actor alone: 0, 1, or 0.5 for mixed.
+    pub fn default_ai_share(self) -> f64 {
+        match self {
+            Self::Human => 0.0,
+            Self::Ai => 1.0,
+            Self::Mixed => 0.5,
+        }
+    }
+
+    /// The actor label for an explicit AI share.
+    pub fn from_ai_share(ai_share: f64) -> Self {
+        if ai_share <= 0.0 {
+            Self::Human
+        } else if ai_share >= 1.0 {
+            Self::Ai
+        } else {
+            Self::Mixed
+        }
+    }
+}
+
+impl FromStr for Actor {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "human" => Ok(Self::Human),
+            "ai" => Ok(Self::Ai),
+            "mixed" => Ok(Self::Mixed),
+            other => bail!("unknown actor `{other}` (expected human, ai or mixed)"),
+        }
+    }
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Human => "human",
+            Self::Ai => "ai",
+            Self::Mixed => "mixed",
+        })
+    }
+}
+
+/// Mode, pause window, manual attribution overrides and the active Drive session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub mode: Mode,
+    /// `"gate"` when Coach or Drive was forced by the weekly gate, `"manual"` for
+    /// `spp drive start` (which may sit on top of a gate-forced Coach; see
+    /// [`GateTrip::held`]).
+    pub drive_reason: Option<String>,
+    pub pause_until: Option<DateTime<Utc>>,
+    /// Manual overrides keyed by full commit hash.
+    pub attribution_overrides: HashMap<String, AttributionOverride>,
+    /// `spp attrib infer` results awaiting `spp attrib accept`, keyed by full commit hash.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub attribution_suggestions: HashMap<String, AttributionSuggestion>,
+    pub active_drive_session: Option<ActiveDriveSession>,
+    /// When and why the gate last forced Coach or Drive; cleared once it
+    /// releases back to Normal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gate_trip: Option<GateTrip>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The gate evaluation that forced Coach or Drive, and the recovery since.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GateTrip {
+    pub at: DateTime<Utc>,
+    /// Most restrictive mode the gate has forced since `at`.
+    pub mode: Mode,
+    /// Mode the gate currently requires; below [`State::mode`] while a manual
+    /// Drive session holds Drive on top of it.
+    #[serde(default)]
+    pub held: Mode,
+    /// Gate ratio and target at the trip.
+    pub ratio: f64,
+    pub target: f64,
+    /// Drive session time since `at` from sessions that have already stopped.
+    #[serde(default)]
+    pub drive_seconds: u64,
+}
+
+impl GateTrip {
+    /// Adds the part of a Drive session from `started_at` to `ended_at` that
+    /// falls after the trip.
+    pub fn record_drive_session(&mut self, started_at: DateTime<Utc>, ended_at: DateTime<Utc>) {
+        let seconds = (ended_at - started_at.max(self.at)).num_seconds().max(0);
+        self.drive_seconds += seconds as u64;
+    }
+
+    /// Drive session time since the trip, including the running session if any.
+    pub fn drive_seconds_at(&self, active: Option<&ActiveDriveSession>, now: DateTime<Utc>) -> u64 {
+        let running = active
+            .map(|session| (now - session.started_at.max(self.at)).num_seconds().max(0))
+            .unwrap_or(0);
+        self.drive_seconds + running as u64
+    }
+}
+
+/// A manual attribution saved by `spp attrib fix`.
+///
+/// Older state files stored a bare actor (`"ai"`); those still load.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(from = "AttributionOverrideRepr")]
+pub struct AttributionOverride {
+    pub actor: Actor,
+    /// Explicit AI share of added lines in `0.0..=1.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_share: Option<f64>,
+    /// `git patch-id --stable` of the commit when the override was saved, so
+    /// rebased or cherry-picked copies inherit it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch_id: Option<String>,
+    /// `Change-Id`-style trailer values of the commit when the override was saved.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub change_ids: Vec<String>,
+}
+
+impl AttributionOverride {
+    pub fn from_actor(actor: Actor) -> Self {
+        Self {
+            actor,
+            ai_share: None,
+            patch_id: None,
+            change_ids: Vec::new(),
+        }
+    }
+
+    pub fn from_ai_share(ai_share: f64) -> Self {
+        Self {
+            actor: Actor::from_ai_share(ai_share),
+            ai_share: Some(ai_share),
+            ..Self::from_actor(Actor::Human)
+        }
+    }
+
+    /// The explicit share, or the one implied by the actor.
+    pub fn ai_share(&self) -> f64 {
+        self.ai_share
+            .unwrap_or_else(|| self.actor.default_ai_share())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AttributionOverrideRepr {
+    Legacy(Actor),
+    Full {
+        actor: Actor,
+        #[serde(default)]
+        ai_share: Option<f64>,
+        #[serde(default)]
+        patch_id: Option<String>,
+        #[serde(default)]
+        change_ids: Vec<String>,
+    },
+}
+
+impl From<AttributionOverrideRepr> for AttributionOverride {
+    fn from(repr: AttributionOverrideRepr) -> Self {
+        match repr {
+            AttributionOverrideRepr::Legacy(actor) => Self::from_actor(actor),
+            AttributionOverrideRepr::Full {
+                actor,
+                ai_share,
+                patch_id,
+                change_ids,
+            } => Self {
+                actor,
+                ai_share,
+                patch_id,
+                change_ids,
+            },
+        }
+    }
+}
+
+/// AI share estimated by matching a commit's added lines against transcript output.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttributionSuggestion {
+    pub ai_share: f64,
+    /// Added lines that also appeared in assistant code blocks or Codex diffs.
+    pub matched_lines: u64,
+    /// Added lines considered for matching (blank and trivial lines are skipped).
+    pub considered_lines: u64,
+    pub inferred_at: DateTime<Utc>,
+}
+
+/// Parses an AI share written as a fraction (`0.4`) or percentage (`40%`).
+pub fn parse_ai_share(raw: &str) -> Result<f64> {
+    let raw = raw.trim();
+    let value = match raw.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|v| v / 100.0),
+        None => raw.parse::<f64>(),
+    }
+    .with_context(|| format!("invalid AI share `{raw}`"))?;
+    if !(0.0..=1.0).contains(&value) {
+        bail!("AI share `{raw}` must be between 0 and 1");
+    }
+    Ok(value)
+}
+
+/// Bookkeeping for a running Drive transcript recorder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveDriveSession {
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+    pub history_path: String,
+    pub history_offset: u64,
+    pub transcript_path: String,
+    pub control_path: String,
+    pub done_path: String,
+    pub recorder_pid: Option<u32>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Normal,
+            drive_reason: None,
+            pause_until: None,
+            attribution_overrides: HashMap::new(),
+            attribution_suggestions: HashMap::new(),
+            active_drive_session: None,
+            gate_trip: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Reads the state file, returning [`State::default`] when it does not exist.
+pub fn load_state(repo_root: &Path) -> Result<State> {
+    let path = repo_root.join(STATE_FILE);
+    if !path.exists() {
+        return Ok(State::default());
+    }
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let state: State = serde_json::from_str(&text).with_context(|| "failed to parse state")?;
+    Ok(state)
+}
+
+/// Writes the state file, stamping `updated_at` with the current time.
+pub fn save_state(repo_root: &Path, state: &State) -> Result<()> {
+    let mut state = state.clone();
+    state.updated_at = Utc::now();
+    let path = repo_root.join(STATE_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(&state)?;
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Clears `pause_until` once it has expired.
+pub fn refresh_pause(state: &mut State) {
+    if let Some(until) = state.pause_until {
+        if Utc::now() >= until {
+            state.pause_until = None;
+        }
+    }
+}
+
+/// Whether gate enforcement is currently paused.
+pub fn pause_active(state: &State) -> bool {
+    matches!(state.pause_until, Some(until) if Utc::now() < until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribution_overrides_load_legacy_and_share_forms() -> Result<()> {
+        let state: State = serde_json::from_str(
+            r#"{"attribution_overrides": {"a": "ai", "b": {"actor": "mixed", "ai_share": 0.4}}}"#,
+        )?;
+        assert_eq!(
+            state.attribution_overrides["a"],
+            AttributionOverride::from_actor(Actor::Ai)
+        );
+        assert_eq!(state.attribution_overrides["b"].ai_share(), 0.4);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ai_share_accepts_fractions_and_percentages() -> Result<()> {
+        assert_eq!(parse_ai_share("0.4")?, 0.4);
+        assert_eq!(parse_ai_share("25%")?, 0.25);
+        assert!(parse_ai_share("1.5").is_err());
+        assert!(parse_ai_share("lots").is_err());
+        Ok(())
+    }
+}
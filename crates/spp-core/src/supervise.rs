@@ -0,0 +1,273 @@
+//! Gate supervision of a running Codex session (`[codex].supervise_interval_secs`).
+
+use std::path::Path;
+use std::process::{Child, ExitStatus};
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::backend::GitBackend;
+use crate::config::{AppConfig, GateTripAction};
+use crate::gate::{apply_gate, compute_weekly_report, gate_escalation};
+use crate::logs::{
+    enforce_log_size, touched_during_session, write_gate_tripped, write_session_end,
+    write_weekly_report, GateTrippedEntry, SessionEndEntry, GATE_TRIPPED_EVENT, SESSION_END_EVENT,
+};
+use crate::metrics::FileChange;
+use crate::recorder::terminate_process;
+use crate::state::{load_state, pause_active, refresh_pause, save_state, Mode};
+
+/// The Codex session being supervised.
+#[derive(Debug, Clone)]
+pub struct SupervisedSession<'a> {
+    pub session_id: &'a str,
+    pub started_at: DateTime<Utc>,
+    /// Mode Codex was launched in.
+    pub launched: Mode,
+}
+
+/// What the supervisor reports while Codex runs.
+#[derive(Debug)]
+pub enum SuperviseEvent {
+    /// The gate now enforces a stricter mode; `entry` was logged as
+    /// `gate_tripped` and `action` is being applied.
+    Tripped {
+        entry: GateTrippedEntry,
+        action: GateTripAction,
+    },
+    /// Re-evaluating the gate failed; supervision continues.
+    CheckFailed(anyhow::Error),
+    /// Stopping Codex after a trip failed; it keeps running.
+    StopFailed(anyhow::Error),
+}
+
+/// Waits for `child`, re-evaluating the gate every `[codex].supervise_interval_secs`
+/// and reporting each mode it enforces beyond the launch mode and earlier trips,
+/// until spp stops Codex or the gate has reached Drive. Returns the exit status
+/// and the `[codex].on_gate_trip` action if spp stopped Codex.
+pub fn supervise_codex(
+    repo_root: &Path,
+    config: &AppConfig,
+    child: &mut Child,
+    session: &SupervisedSession<'_>,
+    on_event: &mut dyn FnMut(SuperviseEvent),
+) -> Result<(ExitStatus, Option<GateTripAction>)> {
+    let interval = StdDuration::from_secs(config.codex.supervise_interval_secs);
+    let supervised = !interval.is_zero() && session.launched < Mode::Drive;
+    let mut next_check = supervised.then(|| Instant::now() + interval);
+    let mut reported = session.launched.clone();
+    let mut stopped = None;
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| "failed to wait for codex")?
+        {
+            return Ok((status, stopped));
+        }
+        if next_check.is_some_and(|at| Instant::now() >= at) {
+            next_check = Some(Instant::now() + interval);
+            match check_gate_trip(repo_root, config, session, &reported) {
+                Ok(None) => {}
+                Ok(Some(entry)) => {
+                    reported = entry.mode_after.clone();
+                    if reported == Mode::Drive {
+                        next_check = None;
+                    }
+                    let action = config.codex.on_gate_trip;
+                    on_event(SuperviseEvent::Tripped { entry, action });
+                    if action != GateTripAction::Warn {
+                        match terminate_process(child.id()) {
+                            Ok(()) => {
+                                stopped = Some(action);
+                                next_check = None;
+                            }
+                            Err(err) => on_event(SuperviseEvent::StopFailed(err)),
+                        }
+                    }
+                }
+                Err(err) => on_event(SuperviseEvent::CheckFailed(err)),
+            }
+        }
+        thread::sleep(StdDuration::from_millis(200));
+    }
+}
+
+/// How a supervised Codex run ended: its exit status and the
+/// `[codex].on_gate_trip` action if spp stopped it.
+pub type SuperviseOutcome = Result<(ExitStatus, Option<GateTripAction>)>;
+
+/// Uncommitted changes to tracked files; empty before the first commit.
+pub fn worktree_diff(git: &dyn GitBackend) -> Result<Vec<FileChange>> {
+    if git.current_commit().is_none() {
+        return Ok(Vec::new());
+    }
+    git.worktree_file_changes()
+}
+
+/// Appends the `session_end` record of `session`. A failed `outcome` or final
+/// worktree diff is recorded in `error` rather than skipping the record; the
+/// diff error is returned once the record is written.
+pub fn write_codex_session_end(
+    git: &dyn GitBackend,
+    config: &AppConfig,
+    session: &SupervisedSession<'_>,
+    commit_before: Option<String>,
+    diff_before: Vec<FileChange>,
+    outcome: &SuperviseOutcome,
+    transcript_path: Option<String>,
+) -> Result<()> {
+    let ended_at = Utc::now();
+    let (diff_after, diff_error) = match worktree_diff(git) {
+        Ok(diff) => (diff, None),
+        Err(err) => (Vec::new(), Some(err)),
+    };
+    let errors = [
+        outcome.as_ref().err().map(|err| format!("{err:#}")),
+        diff_error
+            .as_ref()
+            .map(|err| format!("failed to diff the worktree after codex: {err:#}")),
+    ];
+    let error = errors.into_iter().flatten().collect::<Vec<_>>();
+    let entry = SessionEndEntry {
+        log_schema_version: config.log_schema_version.clone(),
+        event: SESSION_END_EVENT.to_string(),
+        session_id: session.session_id.to_string(),
+        timestamp: ended_at,
+        started_at: session.started_at,
+        duration_sec: (ended_at - session.started_at).num_seconds().max(0),
+        exit_code: outcome.as_ref().ok().and_then(|(status, _)| status.code()),
+        error: (!error.is_empty()).then(|| error.join("; ")),
+        git_commit_before: commit_before,
+        git_commit_after: git.current_commit(),
+        ai_touched: touched_during_session(&diff_before, &diff_after),
+        diff_before,
+        diff_after,
+        transcript_path,
+    };
+    let repo_root = git.repo_root();
+    write_session_end(repo_root, &entry)?;
+    enforce_log_size(repo_root, config.max_log_bytes)?;
+    match diff_error {
+        Some(err) => Err(err.context("failed to diff the worktree after codex exited")),
+        None => Ok(()),
+    }
+}
+
+/// Re-evaluates the gate for `session`. When it now enforces a mode stricter
+/// than `reported` (the launch mode, or the last trip already reported), saves
+/// the state and weekly report and logs `gate_tripped`.
+pub fn check_gate_trip(
+    repo_root: &Path,
+    config: &AppConfig,
+    session: &SupervisedSession<'_>,
+    reported: &Mode,
+) -> Result<Option<GateTrippedEntry>> {
+    let mut state = load_state(repo_root)?;
+    refresh_pause(&mut state);
+    let pause = pause_active(&state);
+    let mut report = compute_weekly_report(repo_root, config, &state)?;
+    apply_gate(&mut state, &mut report, &config.gate, pause);
+    let Some(enforced) = gate_escalation(reported, &state) else {
+        return Ok(None);
+    };
+
+    save_state(repo_root, &state)?;
+    write_weekly_report(repo_root, &report)?;
+    let entry = GateTrippedEntry {
+        log_schema_version: config.log_schema_version.clone(),
+        event: GATE_TRIPPED_EVENT.to_string(),
+        session_id: session.session_id.to_string(),
+        timestamp: Utc::now(),
+        started_at: session.started_at,
+        mode_before: session.launched.clone(),
+        mode_after: enforced,
+        gate_ratio: report.gate_ratio,
+        gate_target: report.target_ratio,
+        action: config.codex.on_gate_trip.as_str().to_string(),
+    };
+    write_gate_tripped(repo_root, &entry)?;
+    Ok(Some(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::SESSION_DIR;
+    use crate::test_support::{commit_file, init_git_repo, MemoryRepo};
+
+    fn session_log(repo_root: &Path) -> Result<String> {
+        Ok(std::fs::read_dir(repo_root.join(SESSION_DIR))?
+            .map(|entry| std::fs::read_to_string(entry?.path()))
+            .collect::<std::io::Result<String>>()?)
+    }
+
+    #[test]
+    fn session_end_is_logged_when_supervision_and_the_final_diff_fail() -> Result<()> {
+        let mut repo = MemoryRepo::new("spp-supervise-end")?;
+        repo.commit("dev@example.com", "feat: a", &[("a.rs", 1)], Utc::now());
+        repo.fail_worktree = true;
+        let session = SupervisedSession {
+            session_id: "session",
+            started_at: Utc::now(),
+            launched: Mode::Normal,
+        };
+        let outcome: SuperviseOutcome = Err(anyhow::anyhow!("failed to wait for codex"));
+
+        let written = write_codex_session_end(
+            &repo,
+            &AppConfig::default(),
+            &session,
+            None,
+            Vec::new(),
+            &outcome,
+            None,
+        );
+        assert!(written.is_err());
+
+        let log = session_log(repo.repo_root())?;
+        let entry: SessionEndEntry = serde_json::from_str(log.trim())?;
+        assert_eq!(entry.session_id, "session");
+        assert_eq!(entry.exit_code, None);
+        let error = entry.error.expect("error recorded");
+        assert!(error.starts_with("failed to wait for codex; failed to diff the worktree"));
+        assert!(entry.diff_after.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn check_gate_trip_reports_a_later_drive_after_a_coach_warning() -> Result<()> {
+        let repo = init_git_repo("spp-supervise")?;
+        let root = repo.path();
+        let mut config = AppConfig::default();
+        config.gate.drive_below = Some(0.5);
+        config.codex.on_gate_trip = GateTripAction::Warn;
+        let session = SupervisedSession {
+            session_id: "session",
+            started_at: Utc::now(),
+            launched: Mode::Normal,
+        };
+
+        commit_file(root, "human.txt", &"h\n".repeat(60), "feat: human")?;
+        assert!(check_gate_trip(root, &config, &session, &Mode::Normal)?.is_none());
+
+        let codex = "feat: codex\n\nCo-authored-by: Codex <codex@openai.com>";
+        commit_file(root, "ai.txt", &"a\n".repeat(40), codex)?;
+        let coach = check_gate_trip(root, &config, &session, &Mode::Normal)?.expect("coach trip");
+        assert_eq!(coach.mode_after, Mode::Coach);
+        assert_eq!(coach.action, "warn");
+        assert!(check_gate_trip(root, &config, &session, &coach.mode_after)?.is_none());
+
+        commit_file(root, "ai2.txt", &"a\n".repeat(100), codex)?;
+        let drive = check_gate_trip(root, &config, &session, &coach.mode_after)?
+            .expect("drive trip after the warning");
+        assert_eq!(drive.mode_before, Mode::Normal);
+        assert_eq!(drive.mode_after, Mode::Drive);
+        assert_eq!(load_state(root)?.mode, Mode::Drive);
+
+        let log = session_log(root)?;
+        assert_eq!(log.matches(GATE_TRIPPED_EVENT).count(), 2);
+        Ok(())
+    }
+}
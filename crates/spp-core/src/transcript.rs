@@ -0,0 +1,386 @@
+//! Drive transcript events and Codex history ingestion.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::{TranscriptConfig, DEFAULT_CHAT_SOURCE, DEFAULT_HISTORY_PATH};
+use crate::logs::append_jsonl;
+use crate::state::Mode;
+
+static EVENT_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// One line of a `.codex-spp/transcripts/<session-id>.jsonl` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEvent {
+    pub log_schema_version: String,
+    pub event_id: String,
+    pub session_id: String,
+    pub event_type: String,
+    pub timestamp: DateTime<Utc>,
+    pub mode: Mode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// A chat message extracted from a Codex history entry.
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    pub message_id: Option<String>,
+    pub raw: Option<Value>,
+}
+
+/// Fails unless `chat_source` is the supported `history_jsonl`.
+pub fn validate_transcript_source(config: &TranscriptConfig) -> Result<()> {
+    if config.chat_source == DEFAULT_CHAT_SOURCE {
+        return Ok(());
+    }
+    bail!(
+        "unsupported transcript chat_source `{}` (supported: `{}`)",
+        config.chat_source,
+        DEFAULT_CHAT_SOURCE
+    );
+}
+
+/// Resolves `[transcript].history_path`; `auto` maps to `$CODEX_HOME/history.jsonl`
+/// (or `~/.codex/history.jsonl`), relative paths are taken from `repo_root`.
+pub fn resolve_history_path(repo_root: &Path, config: &TranscriptConfig) -> Result<PathBuf> {
+    if config.history_path == DEFAULT_HISTORY_PATH {
+        let codex_home = env::var("CODEX_HOME").ok().map(PathBuf::from).or_else(|| {
+            env::var("HOME")
+                .ok()
+                .map(|home| PathBuf::from(home).join(".codex"))
+        });
+        if let Some(codex_home) = codex_home {
+            return Ok(codex_home.join("history.jsonl"));
+        }
+        bail!("failed to resolve history path: set CODEX_HOME or HOME");
+    }
+
+    let path = expand_tilde_path(&config.history_path);
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    Ok(repo_root.join(path))
+}
+
+/// Expands a leading `~` to `$HOME`.
+pub fn expand_tilde_path(raw: &str) -> PathBuf {
+    if raw == "~" {
+        if let Ok(home) = env::var("HOME") {
+            return PathBuf::from(home);
+        }
+    }
+    if let Some(rest) = raw.strip_prefix("~/") {
+        if let Ok(home) = env::var("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    PathBuf::from(raw)
+}
+
+/// Reads complete JSON lines from `path` starting at byte `offset`.
+/// Returns the offset after the last consumed line; a partial or unparsable
+/// trailing line is left for the next poll.
+pub fn read_history_values(path: &Path, offset: u64) -> Result<(u64, Vec<Value>)> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    file.seek(SeekFrom::Start(offset))
+        .with_context(|| format!("failed to seek {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut current_offset = offset;
+    let mut values = Vec::new();
+
+    loop {
+        let mut line = String::new();
+        let offset_before_read = current_offset;
+        let bytes_read = reader.read_line(&mut line)?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        if !line.ends_with('\n') {
+            reader
+                .get_mut()
+                .seek(SeekFrom::Start(offset_before_read))
+                .with_context(|| format!("failed to rewind {}", path.display()))?;
+            current_offset = offset_before_read;
+            break;
+        }
+
+        let line = line.trim();
+        current_offset += bytes_read as u64;
+        if line.is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<Value>(line) {
+            Ok(value) => values.push(value),
+            Err(_) => {
+                reader
+                    .get_mut()
+                    .seek(SeekFrom::Start(offset_before_read))
+                    .with_context(|| format!("failed to rewind {}", path.display()))?;
+                current_offset = offset_before_read;
+                break;
+            }
+        }
+    }
+
+    Ok((current_offset, values))
+}
+
+/// Extracts user/assistant messages from a history entry, either a single
+/// message or an object with a `messages` array.
+pub fn extract_chat_messages(value: &Value, max_event_bytes: u64) -> Vec<ChatMessage> {
+    if let Some(messages) = value.get("messages").and_then(Value::as_array) {
+        let mut out = Vec::new();
+        for item in messages {
+            if let Some(msg) = extract_single_chat_message(item, max_event_bytes) {
+                out.push(msg);
+            }
+        }
+        return out;
+    }
+
+    extract_single_chat_message(value, max_event_bytes)
+        .map(|msg| vec![msg])
+        .unwrap_or_default()
+}
+
+fn extract_single_chat_message(value: &Value, max_event_bytes: u64) -> Option<ChatMessage> {
+    let role = resolve_chat_role(value)?;
+    let content_value = value
+        .pointer("/message/content")
+        .or_else(|| value.get("content"))
+        .or_else(|| value.pointer("/message/text"))
+        .or_else(|| value.get("text"))?;
+    let content = flatten_content(content_value)?;
+    if content.trim().is_empty() {
+        return None;
+    }
+    let content = truncate_to_bytes(&content, max_event_bytes);
+    let message_id = value
+        .pointer("/message/id")
+        .or_else(|| value.get("id"))
+        .and_then(Value::as_str)
+        .map(ToString::to_string);
+    let raw = {
+        let rendered = serde_json::to_string(value).ok();
+        if let Some(rendered) = rendered {
+            if rendered.len() as u64 <= max_event_bytes {
+                Some(value.clone())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    Some(ChatMessage {
+        role: role.to_string(),
+        content,
+        message_id,
+        raw,
+    })
+}
+
+fn resolve_chat_role(value: &Value) -> Option<&'static str> {
+    let candidate = value
+        .pointer("/message/role")
+        .or_else(|| value.get("role"))
+        .or_else(|| value.pointer("/item/role"))
+        .or_else(|| value.get("type"))
+        .and_then(Value::as_str)?;
+    let candidate = candidate.to_lowercase();
+    if candidate.contains("assistant") {
+        return Some("assistant");
+    }
+    if candidate.contains("user") {
+        return Some("user");
+    }
+    None
+}
+
+fn flatten_content(value: &Value) -> Option<String> {
+    if let Some(text) = value.as_str() {
+        return Some(text.to_string());
+    }
+
+    if let Some(arr) = value.as_array() {
+        let mut parts = Vec::new();
+        for item in arr {
+            if let Some(text) = item.as_str() {
+                parts.push(text.to_string());
+                continue;
+            }
+            if let Some(text) = item.get("text").and_then(Value::as_str) {
+                parts.push(text.to_string());
+                continue;
+            }
+            if let Some(text) = item.pointer("/content/text").and_then(Value::as_str) {
+                parts.push(text.to_string());
+            }
+        }
+        if !parts.is_empty() {
+            return Some(parts.join("\n"));
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        if let Some(text) = obj.get("text").and_then(Value::as_str) {
+            return Some(text.to_string());
+        }
+    }
+
+    None
+}
+
+/// Collapses whitespace and truncates to a short summary.
+pub fn summarize_text(input: &str) -> String {
+    let normalized = input.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_to_bytes(&normalized, 280)
+}
+
+/// Truncates `input` on a char boundary so the result (including the
+/// `...[truncated]` marker) stays within `max_bytes`.
+pub fn truncate_to_bytes(input: &str, max_bytes: u64) -> String {
+    if input.len() as u64 <= max_bytes {
+        return input.to_string();
+    }
+
+    let mut end = 0usize;
+    for (idx, _) in input.char_indices() {
+        if idx as u64 > max_bytes.saturating_sub(14) {
+            break;
+        }
+        end = idx;
+    }
+    let mut out = input[..end].to_string();
+    out.push_str("...[truncated]");
+    out
+}
+
+/// Unique, time-ordered session identifier.
+pub fn generate_session_id() -> String {
+    format!(
+        "{}-{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        std::process::id(),
+        EVENT_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Unique transcript event identifier.
+pub fn generate_event_id() -> String {
+    format!(
+        "evt-{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        EVENT_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Appends `event` to the transcript at `path`.
+pub fn write_transcript_event(path: &Path, event: &TranscriptEvent) -> Result<()> {
+    append_jsonl(path, event)
+}
+
+/// Inode number on Unix, `None` elsewhere.
+pub fn file_inode(metadata: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        Some(metadata.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Closes a transcript whose recorder failed to start with a `session_end`
+/// event carrying reason `start_failed`.
+pub fn append_start_failed_session_end(
+    transcript_path: &Path,
+    log_schema_version: &str,
+    session_id: &str,
+    mode: Mode,
+    error_message: &str,
+) -> Result<()> {
+    let end_event = TranscriptEvent {
+        log_schema_version: log_schema_version.to_string(),
+        event_id: generate_event_id(),
+        session_id: session_id.to_string(),
+        event_type: "session_end".to_string(),
+        timestamp: Utc::now(),
+        mode,
+        payload: Some(json!({
+            "stats": {
+                "chat_events": 0,
+                "diff_events": 0,
+                "duration_sec": 0,
+                "history_offset": 0
+            },
+            "reason": "start_failed",
+            "errors": [error_message]
+        })),
+        notes: None,
+    };
+    write_transcript_event(transcript_path, &end_event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDirGuard;
+
+    #[test]
+    fn append_start_failed_session_end_writes_recovery_event() -> Result<()> {
+        let temp = TempDirGuard::new("spp-start-failed")?;
+        let transcript_path = temp.path().join("transcript.jsonl");
+        let start_event = TranscriptEvent {
+            log_schema_version: "1.1".to_string(),
+            event_id: "e1".to_string(),
+            session_id: "session-a".to_string(),
+            event_type: "session_start".to_string(),
+            timestamp: Utc::now(),
+            mode: Mode::Drive,
+            payload: None,
+            notes: None,
+        };
+        write_transcript_event(&transcript_path, &start_event)?;
+
+        append_start_failed_session_end(
+            &transcript_path,
+            "1.1",
+            "session-a",
+            Mode::Drive,
+            "spawn failed",
+        )?;
+
+        let raw = fs::read_to_string(&transcript_path)?;
+        let events = raw
+            .lines()
+            .map(serde_json::from_str::<Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1]["event_type"], "session_end");
+        assert_eq!(events[1]["payload"]["reason"], "start_failed");
+        assert_eq!(events[1]["payload"]["errors"][0], "spawn failed");
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+//! Multi-week trend of the human:ai ratio.
+
+use serde::{Deserialize, Serialize};
+
+use crate::gate::{commit_ratio, WeeklyReport};
+
+/// One week of a trend, with the rolling average and week-over-week change.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrendPoint {
+    pub year: i32,
+    pub iso_week: u32,
+    pub ratio: f64,
+    pub commit_ratio: f64,
+    pub human_lines_added: u64,
+    pub ai_lines_added: u64,
+    /// Mean of `ratio` over this week and up to `window - 1` preceding weeks.
+    pub rolling_ratio: f64,
+    /// `ratio` minus the previous week's ratio; `None` for the first week.
+    pub delta: Option<f64>,
+}
+
+/// Builds trend points from reports ordered oldest first.
+pub fn compute_trend(reports: &[WeeklyReport], window: usize) -> Vec<TrendPoint> {
+    let window = window.max(1);
+    reports
+        .iter()
+        .enumerate()
+        .map(|(idx, report)| {
+            let start = (idx + 1).saturating_sub(window);
+            let span = &reports[start..=idx];
+            let rolling_ratio = span.iter().map(|r| r.ratio).sum::<f64>() / span.len() as f64;
+            let delta = idx
+                .checked_sub(1)
+                .map(|prev| report.ratio - reports[prev].ratio);
+            TrendPoint {
+                year: report.year,
+                iso_week: report.iso_week,
+                ratio: report.ratio,
+                commit_ratio: commit_ratio(report),
+                human_lines_added: report.human_lines_added,
+                ai_lines_added: report.ai_lines_added,
+                rolling_ratio,
+                delta,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_weekly_report;
+
+    fn report_with_ratio(iso_week: u32, ratio: f64) -> WeeklyReport {
+        let mut report = sample_weekly_report();
+        report.iso_week = iso_week;
+        report.ratio = ratio;
+        report
+    }
+
+    #[test]
+    fn compute_trend_rolls_over_window_and_tracks_delta() {
+        let reports = vec![
+            report_with_ratio(1, 0.9),
+            report_with_ratio(2, 0.6),
+            report_with_ratio(3, 0.3),
+        ];
+        let trend = compute_trend(&reports, 2);
+
+        assert_eq!(trend.len(), 3);
+        assert_eq!(trend[0].delta, None);
+        assert!((trend[0].rolling_ratio - 0.9).abs() < 1e-9);
+        assert!((trend[1].rolling_ratio - 0.75).abs() < 1e-9);
+        assert!((trend[2].rolling_ratio - 0.45).abs() < 1e-9);
+        assert!((trend[2].delta.unwrap_or_default() + 0.3).abs() < 1e-9);
+        assert!((trend[2].commit_ratio - 0.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,160 @@
+//! ISO week identifiers and period selection for historical reports.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// An ISO 8601 week, written as `2026-W40`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IsoWeek {
+    pub year: i32,
+    pub week: u32,
+}
+
+impl IsoWeek {
+    /// Validates that `week` exists in `year`.
+    pub fn new(year: i32, week: u32) -> Result<Self> {
+        if NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).is_none() {
+            bail!("invalid ISO week: {year}-W{week:02}");
+        }
+        Ok(Self { year, week })
+    }
+
+    /// The ISO week containing `date`.
+    pub fn containing(date: NaiveDate) -> Self {
+        let iso = date.iso_week();
+        Self {
+            year: iso.year(),
+            week: iso.week(),
+        }
+    }
+
+    /// Monday of this week.
+    pub fn monday(&self) -> NaiveDate {
+        NaiveDate::from_isoywd_opt(self.year, self.week, Weekday::Mon)
+            .expect("IsoWeek is validated on construction")
+    }
+
+    /// The following ISO week.
+    pub fn next(&self) -> Self {
+        Self::containing(self.monday() + Duration::days(7))
+    }
+
+    /// The preceding ISO week.
+    pub fn previous(&self) -> Self {
+        Self::containing(self.monday() - Duration::days(7))
+    }
+}
+
+impl fmt::Display for IsoWeek {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-W{:02}", self.year, self.week)
+    }
+}
+
+impl FromStr for IsoWeek {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let (year, week) = raw
+            .split_once("-W")
+            .or_else(|| raw.split_once("-w"))
+            .with_context(|| format!("invalid ISO week `{raw}` (expected YYYY-Www)"))?;
+        let year = year
+            .parse::<i32>()
+            .with_context(|| format!("invalid year in ISO week `{raw}`"))?;
+        let week = week
+            .parse::<u32>()
+            .with_context(|| format!("invalid week number in ISO week `{raw}`"))?;
+        Self::new(year, week)
+    }
+}
+
+/// Every ISO week from `first` to `last`, inclusive.
+pub fn iso_weeks_between(first: IsoWeek, last: IsoWeek) -> Vec<IsoWeek> {
+    let mut weeks = Vec::new();
+    let mut current = first;
+    while current <= last {
+        weeks.push(current);
+        current = current.next();
+    }
+    weeks
+}
+
+/// The `count` most recent ISO weeks ending with the week containing `today`,
+/// oldest first.
+pub fn last_iso_weeks(today: NaiveDate, count: u32) -> Vec<IsoWeek> {
+    let mut weeks = Vec::new();
+    let mut current = IsoWeek::containing(today);
+    for _ in 0..count {
+        weeks.push(current);
+        current = current.previous();
+    }
+    weeks.reverse();
+    weeks
+}
+
+/// Parses `FROM..TO`, where each bound is a date (`2026-09-01`) or an ISO week
+/// (`2026-W36`), into the inclusive list of ISO weeks it covers.
+pub fn parse_week_range(raw: &str) -> Result<Vec<IsoWeek>> {
+    let (from, to) = raw
+        .split_once("..")
+        .with_context(|| format!("invalid range `{raw}` (expected FROM..TO)"))?;
+    let first = parse_range_bound(from)?;
+    let last = parse_range_bound(to)?;
+    if first > last {
+        bail!("invalid range `{raw}`: start is after end");
+    }
+    Ok(iso_weeks_between(first, last))
+}
+
+fn parse_range_bound(raw: &str) -> Result<IsoWeek> {
+    let raw = raw.trim();
+    if raw.contains(['W', 'w']) {
+        return raw.parse();
+    }
+    let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .with_context(|| format!("invalid date `{raw}` (expected YYYY-MM-DD or YYYY-Www)"))?;
+    Ok(IsoWeek::containing(date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_week_parses_and_displays_round_trip() -> Result<()> {
+        let week: IsoWeek = "2026-W40".parse()?;
+        assert_eq!(
+            week,
+            IsoWeek {
+                year: 2026,
+                week: 40
+            }
+        );
+        assert_eq!(week.to_string(), "2026-W40");
+        assert!("2026-W54".parse::<IsoWeek>().is_err());
+        assert!("2026-40".parse::<IsoWeek>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_week_range_accepts_dates_and_weeks_across_year_boundary() -> Result<()> {
+        let weeks = parse_week_range("2025-12-22..2026-W02")?;
+        let rendered = weeks.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(rendered, vec!["2025-W52", "2026-W01", "2026-W02"]);
+        assert!(parse_week_range("2026-W10..2026-W09").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn last_iso_weeks_ends_with_current_week() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 7).expect("valid date");
+        let weeks = last_iso_weeks(today, 3);
+        let rendered = weeks.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(rendered, vec!["2026-W39", "2026-W40", "2026-W41"]);
+    }
+}
@@ -0,0 +1,196 @@
+//! Gate evaluation windows: ISO week, rolling days or sprint cadence.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+
+use crate::config::{GateConfig, GateWindowKind};
+use crate::week::IsoWeek;
+
+/// Half-open time range `[start, end)` the gate counts commits in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// ISO week the window is filed under in `.codex-spp/weekly/`.
+    pub week: IsoWeek,
+}
+
+/// Timezone used to place day and week boundaries (`[gate].timezone`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateTimezone {
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl FromStr for GateTimezone {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        if raw.eq_ignore_ascii_case("utc") || raw == "Z" {
+            return Ok(Self::Utc);
+        }
+        if raw.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        let offset = raw.parse::<FixedOffset>().with_context(|| {
+            format!("invalid gate timezone `{raw}` (expected `UTC`, `local` or an offset like `+09:00`)")
+        })?;
+        Ok(Self::Fixed(offset))
+    }
+}
+
+impl GateTimezone {
+    /// Calendar date of `instant` in this timezone.
+    pub fn date_of(&self, instant: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Self::Utc => instant.date_naive(),
+            Self::Local => instant.with_timezone(&Local).date_naive(),
+            Self::Fixed(offset) => instant.with_timezone(offset).date_naive(),
+        }
+    }
+
+    /// Midnight at the start of `date` in this timezone, as UTC.
+    pub fn start_of_day(&self, date: NaiveDate) -> Result<DateTime<Utc>> {
+        let midnight = date
+            .and_hms_opt(0, 0, 0)
+            .with_context(|| format!("invalid date {date}"))?;
+        let resolved = match self {
+            Self::Utc => Some(midnight.and_utc()),
+            Self::Local => local_to_utc(&Local, midnight),
+            Self::Fixed(offset) => local_to_utc(offset, midnight),
+        };
+        resolved.with_context(|| format!("midnight of {date} does not exist in gate timezone"))
+    }
+}
+
+fn local_to_utc<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Calendar date of `now` in the gate timezone, which decides the current ISO week.
+pub fn gate_today(gate: &GateConfig, now: DateTime<Utc>) -> Result<NaiveDate> {
+    let tz: GateTimezone = gate.timezone.parse()?;
+    Ok(tz.date_of(now))
+}
+
+/// The window the gate evaluates at `now` according to `[gate]`.
+pub fn current_gate_window(gate: &GateConfig, now: DateTime<Utc>) -> Result<GateWindow> {
+    let tz: GateTimezone = gate.timezone.parse()?;
+    let today = tz.date_of(now);
+    let week = IsoWeek::containing(today);
+    match gate.window {
+        GateWindowKind::IsoWeek => iso_week_window(gate, week),
+        GateWindowKind::RollingDays => {
+            if gate.rolling_days == 0 {
+                bail!("[gate].rolling_days must be at least 1");
+            }
+            Ok(GateWindow {
+                start: now - Duration::days(i64::from(gate.rolling_days)),
+                end: now,
+                week,
+            })
+        }
+        GateWindowKind::Sprint => {
+            let anchor = gate
+                .sprint_anchor
+                .with_context(|| "[gate].sprint_anchor is required for window = \"sprint\"")?;
+            if gate.sprint_length_days == 0 {
+                bail!("[gate].sprint_length_days must be at least 1");
+            }
+            let length = i64::from(gate.sprint_length_days);
+            let elapsed = (today - anchor).num_days();
+            let sprint_start = anchor + Duration::days(elapsed.div_euclid(length) * length);
+            Ok(GateWindow {
+                start: tz.start_of_day(sprint_start)?,
+                end: tz.start_of_day(sprint_start + Duration::days(length))?,
+                week,
+            })
+        }
+    }
+}
+
+/// Monday 00:00 to the following Monday 00:00 of `week` in the gate timezone.
+pub fn iso_week_window(gate: &GateConfig, week: IsoWeek) -> Result<GateWindow> {
+    let tz: GateTimezone = gate.timezone.parse()?;
+    let monday = NaiveDate::from_isoywd_opt(week.year, week.week, Weekday::Mon)
+        .with_context(|| "failed to compute start of ISO week")?;
+    Ok(GateWindow {
+        start: tz.start_of_day(monday)?,
+        end: tz.start_of_day(monday + Duration::days(7))?,
+        week,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn iso_week_window_respects_fixed_offset() -> Result<()> {
+        let gate = GateConfig {
+            timezone: "+09:00".to_string(),
+            ..GateConfig::default()
+        };
+        // Monday 2026-10-12 08:00 in Tokyo is still Sunday in UTC.
+        let window = current_gate_window(&gate, utc("2026-10-11T23:00:00Z"))?;
+        assert_eq!(window.week.to_string(), "2026-W42");
+        assert_eq!(window.start, utc("2026-10-11T15:00:00Z"));
+        assert_eq!(window.end, utc("2026-10-18T15:00:00Z"));
+        assert_eq!(
+            gate_today(&gate, utc("2026-10-11T23:00:00Z"))?,
+            NaiveDate::from_ymd_opt(2026, 10, 12).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rolling_window_ends_now() -> Result<()> {
+        let gate = GateConfig {
+            window: GateWindowKind::RollingDays,
+            rolling_days: 7,
+            ..GateConfig::default()
+        };
+        let now = utc("2026-10-15T12:00:00Z");
+        let window = current_gate_window(&gate, now)?;
+        assert_eq!(window.start, utc("2026-10-08T12:00:00Z"));
+        assert_eq!(window.end, now);
+        Ok(())
+    }
+
+    #[test]
+    fn sprint_window_is_aligned_to_anchor() -> Result<()> {
+        let gate = GateConfig {
+            window: GateWindowKind::Sprint,
+            sprint_anchor: NaiveDate::from_ymd_opt(2026, 1, 5),
+            sprint_length_days: 14,
+            ..GateConfig::default()
+        };
+        let window = current_gate_window(&gate, utc("2026-01-20T10:00:00Z"))?;
+        assert_eq!(window.start, utc("2026-01-19T00:00:00Z"));
+        assert_eq!(window.end, utc("2026-02-02T00:00:00Z"));
+
+        let before_anchor = current_gate_window(&gate, utc("2026-01-01T10:00:00Z"))?;
+        assert_eq!(before_anchor.start, utc("2025-12-22T00:00:00Z"));
+        Ok(())
+    }
+
+    #[test]
+    fn gate_timezone_rejects_unknown_names() {
+        assert!("Asia/Tokyo".parse::<GateTimezone>().is_err());
+        assert_eq!("utc".parse::<GateTimezone>().ok(), Some(GateTimezone::Utc));
+    }
+}
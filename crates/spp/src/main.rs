@@ -1,102 +1,59 @@
-use std::collections::{HashMap, HashSet};
 use std::env;
-use std::ffi::OsStr;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
-#[cfg(unix)]
-use std::os::unix::fs::MetadataExt;
+use std::fs;
+use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration as StdDuration, Instant, SystemTime};
+use std::process::{Command, ExitStatus};
 
 use anyhow::{bail, Context, Result};
-use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use chrono::{Duration, Utc};
 use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use similar::TextDiff;
-use walkdir::WalkDir;
-
-const RUNTIME_CONFIG: &str = ".codex-spp/config.toml";
-const STATE_FILE: &str = ".codex-spp/state.json";
-const SESSION_DIR: &str = ".codex-spp/sessions";
-const WEEKLY_DIR: &str = ".codex-spp/weekly";
-const TRANSCRIPT_DIR: &str = ".codex-spp/transcripts";
-const RUNTIME_DIR: &str = ".codex-spp/runtime";
-const TEMPLATE_CONFIG: &str = "template_spp.config.toml";
-const PROJECT_RUNTIME_CONFIG_FILE: &str = ".codex-spp/config.toml";
-const PROJECT_CODEX_CONFIG_FILE: &str = ".codex/config.toml";
-const GITIGNORE_RULE_CODEX_SPP: &str = "/.codex-spp/";
-const DEFAULT_HISTORY_PATH: &str = "auto";
-const DEFAULT_CHAT_SOURCE: &str = "history_jsonl";
-const DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES: u64 = 64_000;
-const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
-const MAX_RECORDER_ERRORS: usize = 50;
-const FILE_STATE_REVALIDATE_INTERVAL_POLLS: u64 = 30;
-
-static EVENT_COUNTER: AtomicU64 = AtomicU64::new(1);
-
-const PROJECT_ASSETS: &[(&str, &str)] = &[
-    ("AGENTS.md", include_str!("../../../AGENTS.md")),
-    (
-        ".agents/policy.md",
-        include_str!("../../../.agents/policy.md"),
-    ),
-    (
-        ".agents/attribution.md",
-        include_str!("../../../.agents/attribution.md"),
-    ),
-    (
-        ".agents/modes/drive.md",
-        include_str!("../../../.agents/modes/drive.md"),
-    ),
-    (
-        ".agents/modes/normal.md",
-        include_str!("../../../.agents/modes/normal.md"),
-    ),
-    (
-        ".agents/schemas/template_spp.session.schema.json",
-        include_str!("../../../.agents/schemas/template_spp.session.schema.json"),
-    ),
-    (
-        ".agents/schemas/template_spp.weekly_report.schema.json",
-        include_str!("../../../.agents/schemas/template_spp.weekly_report.schema.json"),
-    ),
-    (
-        ".agents/schemas/template_spp.transcript_event.schema.json",
-        include_str!("../../../.agents/schemas/template_spp.transcript_event.schema.json"),
-    ),
-    (
-        ".agents/skills/spp-drive/SKILL.md",
-        include_str!("../../../.agents/skills/spp-drive/SKILL.md"),
-    ),
-    (
-        ".agents/skills/spp-coach/SKILL.md",
-        include_str!("../../../.agents/skills/spp-coach/SKILL.md"),
-    ),
-    (
-        ".agents/skills/spp-stats/SKILL.md",
-        include_str!("../../../.agents/skills/spp-stats/SKILL.md"),
-    ),
-    (
-        "skills/spp-drive/SKILL.md",
-        include_str!("../../../.agents/skills/spp-drive/SKILL.md"),
-    ),
-    (
-        "skills/spp-coach/SKILL.md",
-        include_str!("../../../.agents/skills/spp-coach/SKILL.md"),
-    ),
-    (
-        "skills/spp-stats/SKILL.md",
-        include_str!("../../../.agents/skills/spp-stats/SKILL.md"),
-    ),
-];
-
-const PROJECT_RUNTIME_CONFIG_ASSET: &str = include_str!("../../../template_spp.config.toml");
-const PROJECT_CODEX_CONFIG_ASSET: &str = include_str!("../../../template_spp.codex.config.toml");
+use spp_core::attribution::{
+    orphaned_overrides, pull_override_notes, push_override_notes, store_override, Attributor,
+};
+use spp_core::backend::{open_backend, GitBackend};
+use spp_core::codex::{
+    build_codex_args, codex_mode_config, config_overrides, load_mode_instructions,
+    validate_codex_extra_args, ModeInstructions, INSTRUCTIONS_KEY,
+};
+use spp_core::config::{
+    init_runtime_config, load_config, DEFAULT_POLL_INTERVAL_MS, DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES,
+};
+use spp_core::config::{AppConfig, AttributionConfig, GateTripAction};
+use spp_core::gate::{
+    apply_gate, compute_report_for_week, compute_weekly_report, project_worktree,
+    resolve_gate_refs, start_manual_drive, stop_manual_drive, window_commits, WeeklyReport,
+};
+use spp_core::git::detect_repo_root;
+use spp_core::history::{clear_commit_cache, load_commit_records};
+use spp_core::infer::infer_attributions;
+use spp_core::logs::{enforce_log_size, write_session_log, write_weekly_report, SessionLogEntry};
+use spp_core::paths::{
+    ensure_runtime_dirs, GITIGNORE_RULE_CODEX_SPP, PROJECT_CODEX_CONFIG_FILE,
+    PROJECT_RUNTIME_CONFIG_FILE, RUNTIME_DIR, SESSION_DIR, STATE_FILE, TRANSCRIPT_DIR, WEEKLY_DIR,
+};
+use spp_core::project::{
+    ensure_gitignore_rule, write_text_asset, WriteOutcome, PROJECT_ASSETS,
+    PROJECT_CODEX_CONFIG_ASSET, PROJECT_RUNTIME_CONFIG_ASSET,
+};
+use spp_core::recorder::{
+    missing_history_file, run_drive_recorder_loop, start_transcript_recorder,
+    stop_transcript_recorder, write_recorder_done, IgnoreInterrupts, RecorderArgs, RecorderDone,
+};
+use spp_core::render::{
+    percent_1dp, render_inferred_table, render_report_summary, render_status_plain,
+    render_status_rich, render_trend_csv, render_trend_table,
+};
+use spp_core::state::{
+    load_state, parse_ai_share, pause_active, refresh_pause, save_state, Actor,
+    AttributionOverride, Mode, State,
+};
+use spp_core::supervise::{
+    supervise_codex, worktree_diff, write_codex_session_end, SuperviseEvent, SupervisedSession,
+};
+use spp_core::transcript::{generate_session_id, validate_transcript_source};
+use spp_core::trend::compute_trend;
+use spp_core::week::{last_iso_weeks, parse_week_range, IsoWeek};
+use spp_core::window::{current_gate_window, gate_today, iso_week_window};
 
 #[derive(Parser, Debug)]
 #[command(name = "spp", version, about = "codex-spp wrapper CLI")]
@@ -114,6 +71,8 @@ enum Commands {
     Resume,
     Reset,
     Codex(CodexArgs),
+    Report(ReportArgs),
+    Trend(TrendArgs),
     Project {
         #[command(subcommand)]
         command: ProjectCommands,
@@ -128,6 +87,40 @@ enum Commands {
 struct StatusArgs {
     #[arg(long, default_value_t = false)]
     plain: bool,
+    /// Also show a provisional gate projection including uncommitted changes.
+    #[arg(long, default_value_t = false, conflicts_with = "plain")]
+    projected: bool,
+}
+
+#[derive(Args, Debug)]
+struct ReportArgs {
+    #[arg(long, value_name = "YYYY-Www", conflicts_with_all = ["range", "last"])]
+    week: Option<IsoWeek>,
+    #[arg(long, value_name = "FROM..TO", conflicts_with = "last")]
+    range: Option<String>,
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    last: Option<u32>,
+    #[arg(long, default_value_t = false)]
+    json: bool,
+    #[arg(long, default_value_t = false)]
+    write: bool,
+}
+
+#[derive(Args, Debug)]
+struct TrendArgs {
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    weeks: u32,
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    window: u32,
+    #[arg(long, value_enum, default_value_t = TrendFormat::Table)]
+    format: TrendFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TrendFormat {
+    Table,
+    Json,
+    Csv,
 }
 
 #[derive(Args, Debug)]
@@ -140,6 +133,12 @@ struct PauseArgs {
 struct CodexArgs {
     #[arg(long, default_value_t = false)]
     dry_run: bool,
+    /// Record a Drive transcript while Codex runs (`[codex] record_transcript`).
+    #[arg(long, default_value_t = false, conflicts_with = "no_record")]
+    record: bool,
+    /// Do not record a transcript even if `[codex] record_transcript` is set.
+    #[arg(long, default_value_t = false)]
+    no_record: bool,
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     extra: Vec<String>,
 }
@@ -205,289 +204,112 @@ struct ProjectInitArgs {
 #[derive(Subcommand, Debug)]
 enum AttribCommands {
     Fix(AttribFixArgs),
+    Explain(AttribExplainArgs),
+    Infer(AttribInferArgs),
+    Accept(AttribAcceptArgs),
+    Review(AttribReviewArgs),
+    Push(AttribSyncArgs),
+    Pull(AttribSyncArgs),
+    Orphans(AttribOrphansArgs),
 }
 
 #[derive(Args, Debug)]
 struct AttribFixArgs {
+    /// A commit, or a range such as `main..HEAD` for every commit in it.
+    #[arg(value_name = "COMMIT|RANGE")]
     commit: String,
-    #[arg(long)]
-    actor: Actor,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
-#[serde(rename_all = "lowercase")]
-enum Mode {
-    #[default]
-    Normal,
-    Drive,
-}
-
-#[derive(
-    Debug,
-    Clone,
-    Copy,
-    Serialize,
-    Deserialize,
-    PartialEq,
-    Eq,
-    ValueEnum,
-    Default,
-    Hash,
-    PartialOrd,
-    Ord,
-)]
-#[serde(rename_all = "lowercase")]
-enum Actor {
-    #[default]
-    Human,
-    Ai,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-struct AppConfig {
-    log_schema_version: String,
-    weekly_ratio_target: f64,
-    max_log_bytes: u64,
-    diff_snapshot_enabled: bool,
-    codex: CodexConfig,
-    transcript: TranscriptConfig,
-    attribution: AttributionConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-struct CodexConfig {
-    #[serde(default = "default_codex_mode_normal")]
-    normal: CodexModeConfig,
-    #[serde(default = "default_codex_mode_drive")]
-    drive: CodexModeConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-struct CodexModeConfig {
-    sandbox: String,
-    approval: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-struct TranscriptConfig {
-    chat_source: String,
-    history_path: String,
-    capture_full_text: bool,
-    max_event_bytes: u64,
-    include_file_diff: bool,
-    watch_exclude: Vec<String>,
-    poll_interval_ms: u64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-struct AttributionConfig {
-    codex_author_emails: Vec<String>,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            log_schema_version: "1.1".to_string(),
-            weekly_ratio_target: 0.70,
-            max_log_bytes: 524_288_000,
-            diff_snapshot_enabled: false,
-            codex: CodexConfig::default(),
-            transcript: TranscriptConfig::default(),
-            attribution: AttributionConfig::default(),
-        }
-    }
+    #[arg(long, required_unless_present = "share", conflicts_with = "share")]
+    actor: Option<ActorArg>,
+    /// AI share of the commit's added lines, e.g. `0.4` or `40%`.
+    #[arg(long, value_parser = parse_ai_share)]
+    share: Option<f64>,
+    /// Save to the shared `[attribution].notes_ref` instead of `state.json`.
+    #[arg(long, default_value_t = false)]
+    notes: bool,
 }
 
-impl Default for CodexConfig {
-    fn default() -> Self {
-        Self {
-            normal: default_codex_mode_normal(),
-            drive: default_codex_mode_drive(),
-        }
-    }
+#[derive(Args, Debug)]
+struct AttribOrphansArgs {
+    /// How far back to look for rewritten successors.
+    #[arg(long, default_value_t = 90)]
+    days: u32,
+    /// Move inherited overrides to their successor and drop the rest.
+    #[arg(long, default_value_t = false)]
+    prune: bool,
 }
 
-impl Default for CodexModeConfig {
-    fn default() -> Self {
-        Self {
-            sandbox: "read-only".to_string(),
-            approval: "on-request".to_string(),
-        }
-    }
+#[derive(Args, Debug)]
+struct AttribSyncArgs {
+    #[arg(long, default_value = "origin")]
+    remote: String,
 }
 
-impl Default for TranscriptConfig {
-    fn default() -> Self {
-        Self {
-            chat_source: DEFAULT_CHAT_SOURCE.to_string(),
-            history_path: DEFAULT_HISTORY_PATH.to_string(),
-            capture_full_text: true,
-            max_event_bytes: DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES,
-            include_file_diff: true,
-            watch_exclude: vec![
-                ".git/".to_string(),
-                ".codex-spp/".to_string(),
-                "target/".to_string(),
-            ],
-            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
-        }
-    }
+#[derive(Args, Debug)]
+struct AttribExplainArgs {
+    commit: String,
 }
 
-fn default_codex_mode_normal() -> CodexModeConfig {
-    CodexModeConfig {
-        sandbox: "workspace-write".to_string(),
-        approval: "on-request".to_string(),
-    }
+#[derive(Args, Debug)]
+struct AttribInferArgs {
+    /// ISO week to analyse instead of the current gate window.
+    #[arg(long)]
+    week: Option<IsoWeek>,
+    /// Smallest inferred AI share saved as a suggestion.
+    #[arg(long, default_value = "0.1", value_parser = parse_ai_share)]
+    min_share: f64,
 }
 
-fn default_codex_mode_drive() -> CodexModeConfig {
-    CodexModeConfig {
-        sandbox: "read-only".to_string(),
-        approval: "on-request".to_string(),
-    }
+#[derive(Args, Debug)]
+struct AttribReviewArgs {
+    /// ISO week to review instead of the current gate window.
+    #[arg(long)]
+    week: Option<IsoWeek>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-struct State {
-    mode: Mode,
-    drive_reason: Option<String>,
-    pause_until: Option<DateTime<Utc>>,
-    attribution_overrides: HashMap<String, Actor>,
-    active_drive_session: Option<ActiveDriveSession>,
-    updated_at: DateTime<Utc>,
+#[derive(Args, Debug)]
+struct AttribAcceptArgs {
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    commits: Vec<String>,
+    #[arg(long, default_value_t = false)]
+    all: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ActiveDriveSession {
-    session_id: String,
-    started_at: DateTime<Utc>,
-    history_path: String,
-    history_offset: u64,
-    transcript_path: String,
-    control_path: String,
-    done_path: String,
-    recorder_pid: Option<u32>,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ActorArg {
+    Human,
+    Ai,
+    Mixed,
 }
 
-impl Default for State {
-    fn default() -> Self {
-        Self {
-            mode: Mode::Normal,
-            drive_reason: None,
-            pause_until: None,
-            attribution_overrides: HashMap::new(),
-            active_drive_session: None,
-            updated_at: Utc::now(),
+impl From<ActorArg> for Actor {
+    fn from(value: ActorArg) -> Self {
+        match value {
+            ActorArg::Human => Actor::Human,
+            ActorArg::Ai => Actor::Ai,
+            ActorArg::Mixed => Actor::Mixed,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct TranscriptEvent {
-    log_schema_version: String,
-    event_id: String,
-    session_id: String,
-    event_type: String,
-    timestamp: DateTime<Utc>,
-    mode: Mode,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    payload: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    notes: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct RecorderDone {
-    session_id: String,
-    finished_at: DateTime<Utc>,
-    history_offset: u64,
-    chat_events: u64,
-    diff_events: u64,
-    errors: Vec<String>,
-}
-
-#[derive(Debug, Clone)]
-struct ChatMessage {
-    role: String,
-    content: String,
-    message_id: Option<String>,
-    raw: Option<Value>,
-}
-
-#[derive(Debug, Clone)]
-struct FileState {
-    len: u64,
-    modified: Option<SystemTime>,
-    content: Arc<str>,
-}
-
-impl Default for RecorderDone {
-    fn default() -> Self {
+impl From<DriveRecordArgs> for RecorderArgs {
+    fn from(args: DriveRecordArgs) -> Self {
         Self {
-            session_id: String::new(),
-            finished_at: Utc::now(),
-            history_offset: 0,
-            chat_events: 0,
-            diff_events: 0,
-            errors: Vec::new(),
+            session_id: args.session_id,
+            log_schema_version: args.log_schema_version,
+            transcript_path: args.transcript_path,
+            history_path: args.history_path,
+            history_offset: args.history_offset,
+            control_path: args.control_path,
+            done_path: args.done_path,
+            include_file_diff: args.include_file_diff,
+            capture_full_text: args.capture_full_text,
+            max_event_bytes: args.max_event_bytes,
+            poll_interval_ms: args.poll_interval_ms,
+            exclude: args.exclude,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct WeeklyReport {
-    log_schema_version: String,
-    generated_at: DateTime<Utc>,
-    year: i32,
-    iso_week: u32,
-    human_lines_added: u64,
-    ai_lines_added: u64,
-    human_commit_count: u64,
-    ai_commit_count: u64,
-    ratio: f64,
-    target_ratio: f64,
-    gate_passed: bool,
-    mode_after_evaluation: Mode,
-    notes: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct SessionLogEntry {
-    log_schema_version: String,
-    timestamp: DateTime<Utc>,
-    command: String,
-    mode: Mode,
-    sandbox: String,
-    approval: String,
-    git_branch: String,
-    git_commit: Option<String>,
-    gate_ratio: Option<f64>,
-    gate_target: Option<f64>,
-    notes: Option<String>,
-}
-
-#[derive(Debug, Default)]
-struct WeeklyMetrics {
-    human_lines_added: u64,
-    ai_lines_added: u64,
-    human_commit_count: u64,
-    ai_commit_count: u64,
-    notes: Vec<String>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum WriteOutcome {
-    Created,
-    Overwritten,
-    Skipped,
-}
-
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -505,8 +327,17 @@ fn main() -> Result<()> {
                 Commands::Resume => cmd_resume(&repo_root),
                 Commands::Reset => cmd_reset(&repo_root),
                 Commands::Codex(args) => cmd_codex(&repo_root, args),
+                Commands::Report(args) => cmd_report(&repo_root, args),
+                Commands::Trend(args) => cmd_trend(&repo_root, args),
                 Commands::Attrib { command } => match command {
                     AttribCommands::Fix(args) => cmd_attrib_fix(&repo_root, args),
+                    AttribCommands::Explain(args) => cmd_attrib_explain(&repo_root, args),
+                    AttribCommands::Infer(args) => cmd_attrib_infer(&repo_root, args),
+                    AttribCommands::Accept(args) => cmd_attrib_accept(&repo_root, args),
+                    AttribCommands::Review(args) => cmd_attrib_review(&repo_root, args),
+                    AttribCommands::Push(args) => cmd_attrib_push(&repo_root, args),
+                    AttribCommands::Pull(args) => cmd_attrib_pull(&repo_root, args),
+                    AttribCommands::Orphans(args) => cmd_attrib_orphans(&repo_root, args),
                 },
                 Commands::Project { .. } => unreachable!("project command handled above"),
             }
@@ -592,25 +423,7 @@ fn cmd_init(repo_root: &Path) -> Result<()> {
     fs::create_dir_all(repo_root.join(RUNTIME_DIR))
         .with_context(|| "failed to create runtime directory")?;
 
-    let runtime_cfg_path = repo_root.join(RUNTIME_CONFIG);
-    if !runtime_cfg_path.exists() {
-        if let Some(parent) = runtime_cfg_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let template_cfg = repo_root.join(TEMPLATE_CONFIG);
-        if template_cfg.exists() {
-            fs::copy(&template_cfg, &runtime_cfg_path).with_context(|| {
-                format!(
-                    "failed to copy template config from {}",
-                    template_cfg.display()
-                )
-            })?;
-        } else {
-            let cfg_text = toml::to_string_pretty(&AppConfig::default())?;
-            fs::write(&runtime_cfg_path, cfg_text)
-                .with_context(|| "failed to write default runtime config")?;
-        }
-    }
+    init_runtime_config(repo_root)?;
 
     let state_path = repo_root.join(STATE_FILE);
     if !state_path.exists() {
@@ -629,7 +442,7 @@ fn cmd_status(repo_root: &Path, args: StatusArgs) -> Result<()> {
 
     let mut report = compute_weekly_report(repo_root, &config, &state)?;
     let pause = pause_active(&state);
-    apply_gate(&mut state, &mut report, pause);
+    apply_gate(&mut state, &mut report, &config.gate, pause);
 
     save_state(repo_root, &state)?;
     write_weekly_report(repo_root, &report)?;
@@ -638,113 +451,81 @@ fn cmd_status(repo_root: &Path, args: StatusArgs) -> Result<()> {
     if args.plain {
         print!("{}", render_status_plain(&state, &report));
     } else {
-        print!("{}", render_status_rich(&state, &report, pause));
+        let projection = if args.projected {
+            project_worktree(repo_root, &config, &report)?
+        } else {
+            None
+        };
+        print!(
+            "{}",
+            render_status_rich(&state, &report, pause, projection.as_ref())
+        );
     }
 
     Ok(())
 }
 
-fn render_status_plain(state: &State, report: &WeeklyReport) -> String {
-    let mut lines = vec![
-        format!("mode: {:?}", state.mode),
-        format!(
-            "ratio: {:.3} (target: {:.3}) gate_passed: {}",
-            report.ratio, report.target_ratio, report.gate_passed
-        ),
-    ];
-    if let Some(pause_until) = state.pause_until {
-        lines.push(format!("pause_until: {}", pause_until.to_rfc3339()));
-    }
-    lines.join("\n") + "\n"
-}
-
-fn render_status_rich(state: &State, report: &WeeklyReport, pause_active: bool) -> String {
-    use std::fmt::Write as _;
-
-    let gate = if report.gate_passed { "PASS" } else { "FAIL" };
-    let mode = match state.mode {
-        Mode::Normal => "Normal",
-        Mode::Drive => "Drive",
-    };
-    let reason = status_gate_reason(state, report, pause_active);
-    let lines_delta = report.ratio - report.target_ratio;
-    let commits_ratio = commit_ratio(report);
-    let pause_text = state
-        .pause_until
-        .map(|pause_until| pause_until.to_rfc3339())
-        .unwrap_or_else(|| "none".to_string());
-
-    let mut out = String::new();
-    let _ = writeln!(
-        &mut out,
-        "Mode: {mode} | Gate: {gate} | Week: {}-W{:02}",
-        report.year, report.iso_week
-    );
-    let _ = writeln!(&mut out, "Gate reason: {reason}");
-    let _ = writeln!(
-        &mut out,
-        "Lines: {} | Target: {} | Delta: {}",
-        percent_1dp(report.ratio),
-        percent_1dp(report.target_ratio),
-        points_delta(lines_delta)
-    );
-    let _ = writeln!(&mut out, "Commits: {}", percent_1dp(commits_ratio));
-    let _ = writeln!(&mut out);
-    let _ = writeln!(
-        &mut out,
-        "Human: {} commits / {} lines",
-        report.human_commit_count, report.human_lines_added
-    );
-    let _ = writeln!(
-        &mut out,
-        "AI: {} commits / {} lines",
-        report.ai_commit_count, report.ai_lines_added
-    );
-    let _ = writeln!(&mut out, "Pause until: {pause_text}");
-    if report.notes.is_empty() {
-        let _ = writeln!(&mut out, "Notes: none");
+fn cmd_report(repo_root: &Path, args: ReportArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let state = load_state(repo_root)?;
+    let weeks = if let Some(week) = args.week {
+        vec![week]
+    } else if let Some(range) = &args.range {
+        parse_week_range(range)?
     } else {
-        let _ = writeln!(&mut out, "Notes:");
-        for note in &report.notes {
-            let _ = writeln!(&mut out, "  - {note}");
-        }
-    }
-
-    out
-}
+        last_iso_weeks(
+            gate_today(&config.gate, Utc::now())?,
+            args.last.unwrap_or(1),
+        )
+    };
 
-fn status_gate_reason(state: &State, report: &WeeklyReport, pause_active: bool) -> &'static str {
-    if report.gate_passed {
-        return "on target";
+    if args.write {
+        ensure_runtime_dirs(repo_root)?;
     }
-    if pause_active {
-        return "below target, gate paused";
+    let mut reports = Vec::with_capacity(weeks.len());
+    for week in weeks {
+        let mut report = compute_report_for_week(repo_root, &config, &state, week)?;
+        report
+            .notes
+            .push("regenerated by `spp report`; gate not applied".to_string());
+        if args.write {
+            write_weekly_report(repo_root, &report)?;
+        }
+        reports.push(report);
     }
-    if state.mode == Mode::Drive && state.drive_reason.as_deref() == Some("gate") {
-        return "below target, drive enforced";
+    if args.write {
+        enforce_log_size(repo_root, config.max_log_bytes)?;
     }
-    "below target"
-}
 
-fn safe_ratio(numerator: u64, denominator: u64) -> f64 {
-    if denominator == 0 {
-        1.0
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
     } else {
-        numerator as f64 / denominator as f64
+        for report in &reports {
+            print!("{}", render_report_summary(report));
+        }
     }
+    Ok(())
 }
 
-fn commit_ratio(report: &WeeklyReport) -> f64 {
-    let total = report.human_commit_count + report.ai_commit_count;
-    safe_ratio(report.human_commit_count, total)
-}
-
-fn percent_1dp(ratio: f64) -> String {
-    format!("{:.1}%", ratio * 100.0)
-}
-
-fn points_delta(delta: f64) -> String {
-    format!("{:+.1}pt", delta * 100.0)
+fn cmd_trend(repo_root: &Path, args: TrendArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let state = load_state(repo_root)?;
+    let reports = last_iso_weeks(gate_today(&config.gate, Utc::now())?, args.weeks)
+        .into_iter()
+        .map(|week| compute_report_for_week(repo_root, &config, &state, week))
+        .collect::<Result<Vec<_>>>()?;
+    let window = args.window as usize;
+    let points = compute_trend(&reports, window);
+
+    match args.format {
+        TrendFormat::Table => print!(
+            "{}",
+            render_trend_table(&points, config.weekly_ratio_target, window)
+        ),
+        TrendFormat::Json => println!("{}", serde_json::to_string_pretty(&points)?),
+        TrendFormat::Csv => print!("{}", render_trend_csv(&points)),
+    }
+    Ok(())
 }
 
 fn cmd_drive(repo_root: &Path, args: DriveArgs) -> Result<()> {
@@ -772,129 +553,30 @@ fn cmd_drive_start(repo_root: &Path) -> Result<()> {
     refresh_pause(&mut state);
     let pause = pause_active(&state);
     let mut report = compute_weekly_report(repo_root, &config, &state)?;
-    apply_gate(&mut state, &mut report, pause);
-    let include_file_diff = effective_include_file_diff(&config);
-
-    let history_path = resolve_history_path(repo_root, &config.transcript)?;
-    if !history_path.exists() {
-        bail!(
-            "history file not found: {}. Ensure Codex history persistence is enabled.",
-            history_path.display()
-        );
-    }
-    let metadata = fs::metadata(&history_path)
-        .with_context(|| format!("failed to stat history file {}", history_path.display()))?;
-    let history_offset = metadata.len();
-
-    let session_id = generate_session_id();
-    let transcript_path = repo_root
-        .join(TRANSCRIPT_DIR)
-        .join(format!("{session_id}.jsonl"));
-    let control_path = repo_root
-        .join(RUNTIME_DIR)
-        .join(format!("{session_id}.control"));
-    let done_path = repo_root
-        .join(RUNTIME_DIR)
-        .join(format!("{session_id}.done"));
-
-    if let Some(parent) = transcript_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    if let Some(parent) = control_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(&control_path, "run\n")
-        .with_context(|| format!("failed to write {}", control_path.display()))?;
-    if done_path.exists() {
-        fs::remove_file(&done_path)
-            .with_context(|| format!("failed to clean stale {}", done_path.display()))?;
-    }
-
-    state.mode = Mode::Drive;
-    if state.drive_reason.as_deref() != Some("gate") {
-        state.drive_reason = Some("manual".to_string());
-    }
+    apply_gate(&mut state, &mut report, &config.gate, pause);
+    let git = open_backend(repo_root, &config.git)?;
 
-    let start_event = TranscriptEvent {
-        log_schema_version: config.log_schema_version.clone(),
-        event_id: generate_event_id(),
-        session_id: session_id.clone(),
-        event_type: "session_start".to_string(),
-        timestamp: Utc::now(),
-        mode: state.mode.clone(),
-        payload: Some(json!({
-            "config_snapshot": {
-                "chat_source": config.transcript.chat_source,
-                "capture_full_text": config.transcript.capture_full_text,
-                "include_file_diff": include_file_diff,
-                "max_event_bytes": config.transcript.max_event_bytes,
-                "poll_interval_ms": config.transcript.poll_interval_ms
-            },
-            "history": {
-                "path": history_path.to_string_lossy(),
-                "offset": history_offset,
-                "inode": file_inode(&metadata)
-            },
-            "git": {
-                "branch": git_output(repo_root, &["rev-parse", "--abbrev-ref", "HEAD"])
-                    .unwrap_or_else(|_| "unknown".to_string())
-                    .trim()
-                    .to_string(),
-                "commit": git_output(repo_root, &["rev-parse", "HEAD"]).ok().map(|s| s.trim().to_string())
-            }
-        })),
-        notes: None,
-    };
-    write_transcript_event(&transcript_path, &start_event)?;
-
-    let recorder_args = build_recorder_args(
-        &session_id,
-        &config.log_schema_version,
-        &transcript_path,
-        &history_path,
-        history_offset,
-        &control_path,
-        &done_path,
-        &config.transcript,
-        include_file_diff,
-    );
-    let recorder_pid = match spawn_recorder(repo_root, &recorder_args) {
-        Ok(pid) => pid,
-        Err(err) => {
-            let spawn_error_message = format!("{err:#}");
-            let _ = append_start_failed_session_end(
-                &transcript_path,
-                &config.log_schema_version,
-                &session_id,
-                state.mode.clone(),
-                &spawn_error_message,
-            );
-            let _ = fs::remove_file(&control_path);
-            if done_path.exists() {
-                let _ = fs::remove_file(&done_path);
-            }
-            return Err(err);
-        }
-    };
+    start_manual_drive(&mut state);
 
-    state.active_drive_session = Some(ActiveDriveSession {
-        session_id: session_id.clone(),
-        started_at: Utc::now(),
-        history_path: history_path.to_string_lossy().to_string(),
-        history_offset,
-        transcript_path: transcript_path.to_string_lossy().to_string(),
-        control_path: control_path.to_string_lossy().to_string(),
-        done_path: done_path.to_string_lossy().to_string(),
-        recorder_pid: Some(recorder_pid),
-    });
+    let active = start_transcript_recorder(
+        &spp_executable()?,
+        &config,
+        git.as_ref(),
+        &state.mode,
+        &generate_session_id(),
+    )?;
+    let session_id = active.session_id.clone();
+    let transcript_path = active.transcript_path.clone();
+    let history_path = active.history_path.clone();
+    state.active_drive_session = Some(active);
 
     save_state(repo_root, &state)?;
     write_weekly_report(repo_root, &report)?;
     enforce_log_size(repo_root, config.max_log_bytes)?;
 
     println!("drive session started: {}", session_id);
-    println!("transcript: {}", transcript_path.display());
-    println!("history source: {}", history_path.display());
+    println!("transcript: {}", transcript_path);
+    println!("history source: {}", history_path);
     Ok(())
 }
 
@@ -908,83 +590,15 @@ fn cmd_drive_stop(repo_root: &Path) -> Result<()> {
         None => bail!("no active drive session"),
     };
 
-    let control_path = PathBuf::from(&active.control_path);
-    let done_path = PathBuf::from(&active.done_path);
-    let transcript_path = PathBuf::from(&active.transcript_path);
-
-    fs::write(&control_path, "stop\n")
-        .with_context(|| format!("failed to write {}", control_path.display()))?;
-
-    let wait_timeout = stop_wait_timeout(&config);
-    let done = wait_for_recorder_done(&done_path, wait_timeout)?;
-    let timeout = done.is_none();
-    let mut timeout_errors = Vec::new();
-    if timeout {
-        if let Some(pid) = active.recorder_pid {
-            if let Err(err) = terminate_recorder_process(pid) {
-                timeout_errors.push(format!(
-                    "recorder did not finish in time; failed to terminate pid {}: {}",
-                    pid, err
-                ));
-            } else {
-                timeout_errors.push(format!(
-                    "recorder did not finish in time; sent termination signal to pid {}",
-                    pid
-                ));
-            }
-        } else {
-            timeout_errors.push("recorder did not finish in time (pid unavailable)".to_string());
-        }
-    }
-    let done = match done {
-        Some(done) => done,
-        None => RecorderDone {
-            session_id: active.session_id.clone(),
-            finished_at: Utc::now(),
-            history_offset: active.history_offset,
-            chat_events: 0,
-            diff_events: 0,
-            errors: if timeout_errors.is_empty() {
-                vec!["recorder did not finish in time".to_string()]
-            } else {
-                timeout_errors
-            },
-        },
-    };
-
-    let end_event = TranscriptEvent {
-        log_schema_version: config.log_schema_version.clone(),
-        event_id: generate_event_id(),
-        session_id: active.session_id.clone(),
-        event_type: "session_end".to_string(),
-        timestamp: Utc::now(),
-        mode: state.mode.clone(),
-        payload: Some(json!({
-            "stats": {
-                "chat_events": done.chat_events,
-                "diff_events": done.diff_events,
-                "duration_sec": (Utc::now() - active.started_at).num_seconds().max(0),
-                "history_offset": done.history_offset
-            },
-            "reason": if timeout { "manual_stop_timeout" } else { "manual_stop" },
-            "errors": done.errors.clone()
-        })),
-        notes: None,
-    };
-    write_transcript_event(&transcript_path, &end_event)?;
+    let (done, ended_at) = stop_transcript_recorder(&config, &active, &state.mode, "manual_stop")?;
 
-    if state.mode == Mode::Drive && state.drive_reason.as_deref() == Some("manual") {
-        state.mode = Mode::Normal;
-        state.drive_reason = None;
+    if let Some(trip) = state.gate_trip.as_mut() {
+        trip.record_drive_session(active.started_at, ended_at);
     }
+    stop_manual_drive(&mut state);
     state.active_drive_session = None;
     save_state(repo_root, &state)?;
 
-    if !timeout {
-        let _ = fs::remove_file(control_path);
-        let _ = fs::remove_file(done_path);
-    }
-
     println!("drive session stopped: {}", active.session_id);
     println!(
         "summary: chat_events={}, diff_events={}",
@@ -1021,6 +635,7 @@ fn cmd_drive_status(repo_root: &Path) -> Result<()> {
 
 fn cmd_drive_record(repo_root: &Path, args: DriveRecordArgs) -> Result<()> {
     ensure_runtime_dirs(repo_root)?;
+    let args = RecorderArgs::from(args);
     let summary = run_drive_recorder_loop(repo_root, &args).unwrap_or_else(|err| RecorderDone {
         session_id: args.session_id.clone(),
         finished_at: Utc::now(),
@@ -1033,1552 +648,613 @@ fn cmd_drive_record(repo_root: &Path, args: DriveRecordArgs) -> Result<()> {
     Ok(())
 }
 
-fn validate_transcript_source(config: &TranscriptConfig) -> Result<()> {
-    if config.chat_source == DEFAULT_CHAT_SOURCE {
-        return Ok(());
-    }
-    bail!(
-        "unsupported transcript chat_source `{}` (supported: `{}`)",
-        config.chat_source,
-        DEFAULT_CHAT_SOURCE
-    );
+fn cmd_pause(repo_root: &Path, args: PauseArgs) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let mut state = load_state(repo_root)?;
+    let hours = args.hours.clamp(1, 24);
+    state.pause_until = Some(Utc::now() + Duration::hours(i64::from(hours)));
+    state.updated_at = Utc::now();
+    save_state(repo_root, &state)?;
+    println!("gate checks paused for {} hour(s)", hours);
+    Ok(())
 }
 
-fn resolve_history_path(repo_root: &Path, config: &TranscriptConfig) -> Result<PathBuf> {
-    if config.history_path == DEFAULT_HISTORY_PATH {
-        let codex_home = env::var("CODEX_HOME").ok().map(PathBuf::from).or_else(|| {
-            env::var("HOME")
-                .ok()
-                .map(|home| PathBuf::from(home).join(".codex"))
-        });
-        if let Some(codex_home) = codex_home {
-            return Ok(codex_home.join("history.jsonl"));
-        }
-        bail!("failed to resolve history path: set CODEX_HOME or HOME");
-    }
-
-    let path = expand_tilde_path(&config.history_path);
-    if path.is_absolute() {
-        return Ok(path);
-    }
-    Ok(repo_root.join(path))
+fn cmd_resume(repo_root: &Path) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let mut state = load_state(repo_root)?;
+    state.pause_until = None;
+    state.updated_at = Utc::now();
+    save_state(repo_root, &state)?;
+    println!("pause cleared");
+    Ok(())
 }
 
-fn expand_tilde_path(raw: &str) -> PathBuf {
-    if raw == "~" {
-        if let Ok(home) = env::var("HOME") {
-            return PathBuf::from(home);
+fn cmd_reset(repo_root: &Path) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let state = State::default();
+    save_state(repo_root, &state)?;
+
+    for dir in [
+        repo_root.join(WEEKLY_DIR),
+        repo_root.join(TRANSCRIPT_DIR),
+        repo_root.join(RUNTIME_DIR),
+    ] {
+        if !dir.exists() {
+            continue;
         }
-    }
-    if let Some(rest) = raw.strip_prefix("~/") {
-        if let Ok(home) = env::var("HOME") {
-            return PathBuf::from(home).join(rest);
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+            }
         }
     }
-    PathBuf::from(raw)
-}
+    clear_commit_cache(repo_root)?;
 
-fn build_recorder_args(
-    session_id: &str,
-    log_schema_version: &str,
-    transcript_path: &Path,
-    history_path: &Path,
-    history_offset: u64,
-    control_path: &Path,
-    done_path: &Path,
-    transcript: &TranscriptConfig,
-    include_file_diff: bool,
-) -> DriveRecordArgs {
-    DriveRecordArgs {
-        session_id: session_id.to_string(),
-        log_schema_version: log_schema_version.to_string(),
-        transcript_path: transcript_path.to_path_buf(),
-        history_path: history_path.to_path_buf(),
-        history_offset,
-        control_path: control_path.to_path_buf(),
-        done_path: done_path.to_path_buf(),
-        include_file_diff,
-        capture_full_text: transcript.capture_full_text,
-        max_event_bytes: transcript.max_event_bytes,
-        poll_interval_ms: transcript.poll_interval_ms,
-        exclude: transcript.watch_exclude.clone(),
-    }
+    println!("weekly state reset");
+    Ok(())
 }
 
-fn effective_include_file_diff(config: &AppConfig) -> bool {
-    config.diff_snapshot_enabled && config.transcript.include_file_diff
+/// This executable, which doubles as the background transcript recorder.
+fn spp_executable() -> Result<PathBuf> {
+    env::current_exe().with_context(|| "failed to resolve current executable")
 }
 
-fn spawn_recorder(repo_root: &Path, args: &DriveRecordArgs) -> Result<u32> {
-    let current_exe = env::current_exe().with_context(|| "failed to resolve current executable")?;
-    let mut command = Command::new(current_exe);
-    command
-        .current_dir(repo_root)
-        .arg("drive")
-        .arg("record")
-        .arg("--session-id")
-        .arg(&args.session_id)
-        .arg("--log-schema-version")
-        .arg(&args.log_schema_version)
-        .arg("--transcript-path")
-        .arg(&args.transcript_path)
-        .arg("--history-path")
-        .arg(&args.history_path)
-        .arg("--history-offset")
-        .arg(args.history_offset.to_string())
-        .arg("--control-path")
-        .arg(&args.control_path)
-        .arg("--done-path")
-        .arg(&args.done_path)
-        .arg("--include-file-diff")
-        .arg(args.include_file_diff.to_string())
-        .arg("--capture-full-text")
-        .arg(args.capture_full_text.to_string())
-        .arg("--max-event-bytes")
-        .arg(args.max_event_bytes.to_string())
-        .arg("--poll-interval-ms")
-        .arg(args.poll_interval_ms.to_string())
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null());
-    for pattern in &args.exclude {
-        command.arg("--exclude").arg(pattern);
-    }
-    let child = command
-        .spawn()
-        .with_context(|| "failed to spawn drive recorder process")?;
-    Ok(child.id())
-}
+fn cmd_codex(repo_root: &Path, args: CodexArgs) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let config = load_config(repo_root)?;
+    let mut restarting = false;
+    loop {
+        let mut state = load_state(repo_root)?;
+        refresh_pause(&mut state);
+
+        let pause = pause_active(&state);
+        let mut report = compute_weekly_report(repo_root, &config, &state)?;
+        apply_gate(&mut state, &mut report, &config.gate, pause);
 
-fn wait_for_recorder_done(path: &Path, timeout: StdDuration) -> Result<Option<RecorderDone>> {
-    let deadline = Instant::now() + timeout;
-    while Instant::now() < deadline {
-        if path.exists() {
-            let raw = fs::read_to_string(path)
-                .with_context(|| format!("failed to read {}", path.display()))?;
-            let done: RecorderDone =
-                serde_json::from_str(&raw).with_context(|| "failed to parse recorder done file")?;
-            return Ok(Some(done));
+        let run = launch_codex(repo_root, &config, &args, &state, &report, pause);
+        let run = if restarting {
+            run.with_context(|| format!("failed to restart codex in {} mode", state.mode))?
+        } else {
+            run?
+        };
+        match run {
+            CodexRun::DryRun => return Ok(()),
+            CodexRun::Exited(status) if !status.success() => {
+                bail!("codex exited with status {}", status)
+            }
+            CodexRun::Exited(_) => return Ok(()),
+            CodexRun::Stopped(GateTripAction::Restart) => restarting = true,
+            CodexRun::Stopped(_) => bail!("codex stopped: the gate tripped during the session"),
         }
-        sleep(StdDuration::from_millis(200));
     }
-    Ok(None)
 }
 
-fn stop_wait_timeout(config: &AppConfig) -> StdDuration {
-    let poll_ms = config.transcript.poll_interval_ms.max(100);
-    let dynamic_ms = poll_ms.saturating_mul(3);
-    let wait_ms = dynamic_ms.max(15_000);
-    StdDuration::from_millis(wait_ms)
+/// How one [`launch_codex`] run ended.
+enum CodexRun {
+    DryRun,
+    Exited(ExitStatus),
+    /// spp stopped Codex after a gate trip, applying this action.
+    Stopped(GateTripAction),
 }
 
-fn terminate_recorder_process(pid: u32) -> Result<()> {
-    #[cfg(unix)]
-    {
-        let status = Command::new("kill")
-            .arg("-TERM")
-            .arg(pid.to_string())
-            .status()
-            .with_context(|| "failed to execute kill command")?;
-        if !status.success() {
-            bail!("kill -TERM {} exited with status {}", pid, status);
-        }
-        return Ok(());
-    }
-
-    #[cfg(windows)]
-    {
-        let status = Command::new("taskkill")
-            .args(["/PID", &pid.to_string(), "/T", "/F"])
-            .status()
-            .with_context(|| "failed to execute taskkill command")?;
-        if !status.success() {
-            bail!("taskkill {} exited with status {}", pid, status);
-        }
-        return Ok(());
-    }
-
-    #[cfg(not(any(unix, windows)))]
-    {
-        let _ = pid;
-        bail!("recorder termination is not supported on this platform");
+/// Launches Codex once in `state.mode`, logging the launch and its outcome, and
+/// supervises it until it exits.
+fn launch_codex(
+    repo_root: &Path,
+    config: &AppConfig,
+    args: &CodexArgs,
+    state: &State,
+    report: &WeeklyReport,
+    pause: bool,
+) -> Result<CodexRun> {
+    let codex_mode = codex_mode_config(config, &state.mode);
+
+    validate_codex_extra_args(&args.extra, &state.mode, codex_mode)?;
+
+    let instructions = load_mode_instructions(repo_root, codex_mode)?;
+    if let ModeInstructions::MissingDefault(path) = &instructions {
+        eprintln!(
+            "spp: {} not found; launching codex without {} mode instructions",
+            path.display(),
+            state.mode
+        );
     }
-}
+    let instructions_file = instructions.text().and(codex_mode.instructions.clone());
+    let codex_args = build_codex_args(&args.extra, codex_mode, instructions.text());
+    // Logged and printed without the instruction text; the file is recorded instead.
+    let shown_args = build_codex_args(&args.extra, codex_mode, None);
 
-fn write_recorder_done(path: &Path, done: &RecorderDone) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let content = serde_json::to_string_pretty(done)?;
-    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
-}
+    let git = open_backend(repo_root, &config.git)?;
+    let branch = git.current_branch();
+    let commit = git.current_commit();
+    let session_id = generate_session_id();
+    let started_at = Utc::now();
 
-fn run_drive_recorder_loop(repo_root: &Path, args: &DriveRecordArgs) -> Result<RecorderDone> {
-    let mut history_offset = args.history_offset;
-    let mut snapshot = if args.include_file_diff {
-        capture_workspace_text_files(repo_root, &args.exclude, None, false)?
-    } else {
-        HashMap::new()
+    let session_entry = SessionLogEntry {
+        log_schema_version: config.log_schema_version.clone(),
+        session_id: Some(session_id.clone()),
+        timestamp: started_at,
+        command: format!("codex {}", shown_args.join(" ")),
+        mode: state.mode.clone(),
+        sandbox: codex_mode.sandbox.clone(),
+        approval: codex_mode.approval.clone(),
+        profile: codex_mode.profile.clone(),
+        config_overrides: config_overrides(codex_mode),
+        instructions_file: instructions_file.clone(),
+        git_branch: branch,
+        git_commit: commit.clone(),
+        gate_ratio: Some(report.gate_ratio),
+        gate_target: Some(report.target_ratio),
+        notes: Some(format!(
+            "gate_passed={}, pause_active={}",
+            report.gate_passed, pause
+        )),
     };
-    let mut chat_events = 0_u64;
-    let mut diff_events = 0_u64;
-    let mut errors = Vec::new();
-    let mut error_seen = HashSet::new();
-    let mut poll_count = 0_u64;
-    let poll_interval = StdDuration::from_millis(args.poll_interval_ms.max(100));
 
-    loop {
-        if should_stop_recorder(&args.control_path) {
-            break;
-        }
+    save_state(repo_root, state)?;
+    write_weekly_report(repo_root, report)?;
+    write_session_log(repo_root, &session_entry)?;
+    enforce_log_size(repo_root, config.max_log_bytes)?;
 
-        match read_history_values(&args.history_path, history_offset) {
-            Ok((next_offset, values)) => {
-                history_offset = next_offset;
-                for value in values {
-                    let messages = extract_chat_messages(&value, args.max_event_bytes);
-                    for message in messages {
-                        let event_type = if message.role == "assistant" {
-                            "chat_assistant"
-                        } else {
-                            "chat_user"
-                        };
-                        let content = if args.capture_full_text {
-                            truncate_to_bytes(&message.content, args.max_event_bytes)
-                        } else {
-                            truncate_to_bytes(
-                                &summarize_text(&message.content),
-                                args.max_event_bytes,
-                            )
-                        };
-                        let payload = json!({
-                            "role": message.role,
-                            "message_id": message.message_id,
-                            "content": content,
-                            "raw": message.raw
-                        });
-                        let event = TranscriptEvent {
-                            log_schema_version: args.log_schema_version.clone(),
-                            event_id: generate_event_id(),
-                            session_id: args.session_id.clone(),
-                            event_type: event_type.to_string(),
-                            timestamp: Utc::now(),
-                            mode: Mode::Drive,
-                            payload: Some(payload),
-                            notes: None,
-                        };
-                        if let Err(err) = write_transcript_event(&args.transcript_path, &event) {
-                            push_recorder_error(
-                                &mut errors,
-                                &mut error_seen,
-                                format!("failed to write chat event: {err:#}"),
-                            );
-                        } else {
-                            chat_events += 1;
-                        }
-                    }
-                }
-            }
-            Err(err) => push_recorder_error(
-                &mut errors,
-                &mut error_seen,
-                format!("failed to read history stream: {err:#}"),
-            ),
-        }
-
-        if should_stop_recorder(&args.control_path) {
-            break;
-        }
-
-        if args.include_file_diff {
-            poll_count = poll_count.saturating_add(1);
-            let force_content_refresh = should_force_refresh_file_state(poll_count);
-            match capture_workspace_text_files(
-                repo_root,
-                &args.exclude,
-                Some(&snapshot),
-                force_content_refresh,
-            ) {
-                Ok(next_snapshot) => {
-                    let mut paths: HashSet<String> = HashSet::new();
-                    paths.extend(snapshot.keys().cloned());
-                    paths.extend(next_snapshot.keys().cloned());
-                    let mut paths = paths.into_iter().collect::<Vec<_>>();
-                    paths.sort();
-                    for path in paths {
-                        let before = snapshot.get(&path).map(|state| state.content.as_ref());
-                        let after = next_snapshot.get(&path).map(|state| state.content.as_ref());
-                        if before == after {
-                            continue;
-                        }
-                        if let Some(diff) = build_unified_diff(before, after, &path) {
-                            let payload = json!({
-                                "path": path,
-                                "diff_unified": truncate_to_bytes(&diff, args.max_event_bytes),
-                                "bytes": diff.len(),
-                                "language": guess_language(&path),
-                            });
-                            let event = TranscriptEvent {
-                                log_schema_version: args.log_schema_version.clone(),
-                                event_id: generate_event_id(),
-                                session_id: args.session_id.clone(),
-                                event_type: "file_diff".to_string(),
-                                timestamp: Utc::now(),
-                                mode: Mode::Drive,
-                                payload: Some(payload),
-                                notes: None,
-                            };
-                            if let Err(err) = write_transcript_event(&args.transcript_path, &event)
-                            {
-                                push_recorder_error(
-                                    &mut errors,
-                                    &mut error_seen,
-                                    format!("failed to write diff event: {err:#}"),
-                                );
-                            } else {
-                                diff_events += 1;
-                            }
-                        }
-                    }
-                    snapshot = next_snapshot;
-                }
-                Err(err) => push_recorder_error(
-                    &mut errors,
-                    &mut error_seen,
-                    format!("failed to capture workspace snapshot: {err:#}"),
-                ),
-            }
-        }
-
-        sleep(poll_interval);
-    }
-
-    Ok(RecorderDone {
-        session_id: args.session_id.clone(),
-        finished_at: Utc::now(),
-        history_offset,
-        chat_events,
-        diff_events,
-        errors,
-    })
-}
-
-fn push_recorder_error(errors: &mut Vec<String>, seen: &mut HashSet<String>, message: String) {
-    let truncated_marker = "__truncated__";
-    if errors.len() >= MAX_RECORDER_ERRORS + 1 {
-        return;
-    }
-
-    if !seen.insert(message.clone()) {
-        return;
-    }
-
-    errors.push(message);
-    if errors.len() == MAX_RECORDER_ERRORS && !seen.contains(truncated_marker) {
-        let _ = seen.insert(truncated_marker.to_string());
-        errors.push(format!(
-            "recorder errors truncated at {} unique entries",
-            MAX_RECORDER_ERRORS
-        ));
+    if !codex_mode.banner.is_empty() {
+        println!("{}", codex_mode.banner);
     }
-}
-
-fn should_stop_recorder(control_path: &Path) -> bool {
-    match fs::read_to_string(control_path) {
-        Ok(value) => value.trim() == "stop",
-        Err(err) if err.kind() == ErrorKind::NotFound => true,
-        Err(_) => true,
-    }
-}
-
-fn read_history_values(path: &Path, offset: u64) -> Result<(u64, Vec<Value>)> {
-    let mut file =
-        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
-    file.seek(SeekFrom::Start(offset))
-        .with_context(|| format!("failed to seek {}", path.display()))?;
-    let mut reader = BufReader::new(file);
-    let mut current_offset = offset;
-    let mut values = Vec::new();
-
-    loop {
-        let mut line = String::new();
-        let offset_before_read = current_offset;
-        let bytes_read = reader.read_line(&mut line)?;
-        if bytes_read == 0 {
-            break;
-        }
-
-        if !line.ends_with('\n') {
-            reader
-                .get_mut()
-                .seek(SeekFrom::Start(offset_before_read))
-                .with_context(|| format!("failed to rewind {}", path.display()))?;
-            current_offset = offset_before_read;
-            break;
-        }
-
-        let line = line.trim();
-        current_offset += bytes_read as u64;
-        if line.is_empty() {
-            continue;
-        }
-
-        match serde_json::from_str::<Value>(line) {
-            Ok(value) => values.push(value),
-            Err(_) => {
-                reader
-                    .get_mut()
-                    .seek(SeekFrom::Start(offset_before_read))
-                    .with_context(|| format!("failed to rewind {}", path.display()))?;
-                current_offset = offset_before_read;
-                break;
-            }
+    let mut record = (args.record || config.codex.record_transcript) && !args.no_record;
+    if record {
+        if let Some(active) = &state.active_drive_session {
+            println!(
+                "drive session {} is already recording; no separate transcript started",
+                active.session_id
+            );
+            record = false;
+        } else if let Some(path) = missing_history_file(repo_root, &config.transcript)? {
+            eprintln!(
+                "spp: history file not found: {}; this session is not recorded (enable Codex history persistence to record transcripts)",
+                path.display()
+            );
+            record = false;
         }
     }
-
-    Ok((current_offset, values))
-}
-
-fn should_force_refresh_file_state(poll_count: u64) -> bool {
-    poll_count > 0 && poll_count % FILE_STATE_REVALIDATE_INTERVAL_POLLS == 0
-}
-
-fn extract_chat_messages(value: &Value, max_event_bytes: u64) -> Vec<ChatMessage> {
-    if let Some(messages) = value.get("messages").and_then(Value::as_array) {
-        let mut out = Vec::new();
-        for item in messages {
-            if let Some(msg) = extract_single_chat_message(item, max_event_bytes) {
-                out.push(msg);
-            }
+    if args.dry_run {
+        println!("dry-run: codex {}", shown_args.join(" "));
+        if let Some(path) = &instructions_file {
+            println!("dry-run: -c {INSTRUCTIONS_KEY}=<contents of {path}>");
+        }
+        if record {
+            println!("dry-run: transcript would be recorded while codex runs");
         }
-        return out;
+        return Ok(CodexRun::DryRun);
     }
 
-    extract_single_chat_message(value, max_event_bytes)
-        .map(|msg| vec![msg])
-        .unwrap_or_default()
-}
+    let recording = if record {
+        let active = start_transcript_recorder(
+            &spp_executable()?,
+            config,
+            git.as_ref(),
+            &state.mode,
+            &session_id,
+        )?;
+        println!("transcript: {}", active.transcript_path);
+        Some(active)
+    } else {
+        None
+    };
 
-fn extract_single_chat_message(value: &Value, max_event_bytes: u64) -> Option<ChatMessage> {
-    let role = resolve_chat_role(value)?;
-    let content_value = value
-        .pointer("/message/content")
-        .or_else(|| value.get("content"))
-        .or_else(|| value.pointer("/message/text"))
-        .or_else(|| value.get("text"))?;
-    let content = flatten_content(content_value)?;
-    if content.trim().is_empty() {
-        return None;
-    }
-    let content = truncate_to_bytes(&content, max_event_bytes);
-    let message_id = value
-        .pointer("/message/id")
-        .or_else(|| value.get("id"))
-        .and_then(Value::as_str)
-        .map(ToString::to_string);
-    let raw = {
-        let rendered = serde_json::to_string(value).ok();
-        if let Some(rendered) = rendered {
-            if rendered.len() as u64 <= max_event_bytes {
-                Some(value.clone())
-            } else {
-                None
+    let diff_before = worktree_diff(git.as_ref())?;
+    let mut child = match Command::new("codex").args(&codex_args).spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            if let Some(active) = &recording {
+                stop_transcript_recorder(config, active, &state.mode, "codex_start_failed")?;
             }
-        } else {
-            None
+            return Err(err).with_context(|| "failed to start codex command");
         }
     };
-
-    Some(ChatMessage {
-        role: role.to_string(),
-        content,
-        message_id,
-        raw,
-    })
-}
-
-fn resolve_chat_role(value: &Value) -> Option<&'static str> {
-    let candidate = value
-        .pointer("/message/role")
-        .or_else(|| value.get("role"))
-        .or_else(|| value.pointer("/item/role"))
-        .or_else(|| value.get("type"))
-        .and_then(Value::as_str)?;
-    let candidate = candidate.to_lowercase();
-    if candidate.contains("assistant") {
-        return Some("assistant");
-    }
-    if candidate.contains("user") {
-        return Some("user");
-    }
-    None
-}
-
-fn flatten_content(value: &Value) -> Option<String> {
-    if let Some(text) = value.as_str() {
-        return Some(text.to_string());
-    }
-
-    if let Some(arr) = value.as_array() {
-        let mut parts = Vec::new();
-        for item in arr {
-            if let Some(text) = item.as_str() {
-                parts.push(text.to_string());
-                continue;
-            }
-            if let Some(text) = item.get("text").and_then(Value::as_str) {
-                parts.push(text.to_string());
-                continue;
-            }
-            if let Some(text) = item.pointer("/content/text").and_then(Value::as_str) {
-                parts.push(text.to_string());
+    // Ctrl-C reaches Codex directly; spp stays up to close the session.
+    let interrupts = IgnoreInterrupts::new();
+    let session = SupervisedSession {
+        session_id: &session_id,
+        started_at,
+        launched: state.mode.clone(),
+    };
+    let outcome = supervise_codex(
+        repo_root,
+        config,
+        &mut child,
+        &session,
+        &mut print_supervise_event,
+    );
+    drop(interrupts);
+
+    // Errors below are raised only after `session_end` is written.
+    let recording_closed = recording.as_ref().map_or(Ok(()), |active| -> Result<()> {
+        let reason = match &outcome {
+            Ok((_, Some(_))) => "gate_tripped",
+            Ok((status, None)) if status.code().is_some() => "codex_exit",
+            _ => "codex_interrupted",
+        };
+        let (_, ended_at) = stop_transcript_recorder(config, active, &state.mode, reason)?;
+        if state.mode == Mode::Drive {
+            let mut state = load_state(repo_root)?;
+            if let Some(trip) = state.gate_trip.as_mut() {
+                trip.record_drive_session(active.started_at, ended_at);
+                save_state(repo_root, &state)?;
             }
         }
-        if !parts.is_empty() {
-            return Some(parts.join("\n"));
-        }
-    }
-
-    if let Some(obj) = value.as_object() {
-        if let Some(text) = obj.get("text").and_then(Value::as_str) {
-            return Some(text.to_string());
-        }
-    }
-
-    None
-}
-
-fn summarize_text(input: &str) -> String {
-    let normalized = input.split_whitespace().collect::<Vec<_>>().join(" ");
-    truncate_to_bytes(&normalized, 280)
-}
+        Ok(())
+    });
 
-fn truncate_to_bytes(input: &str, max_bytes: u64) -> String {
-    if input.len() as u64 <= max_bytes {
-        return input.to_string();
-    }
+    let session_end = write_codex_session_end(
+        git.as_ref(),
+        config,
+        &session,
+        commit,
+        diff_before,
+        &outcome,
+        recording.map(|active| active.transcript_path),
+    );
+    let (status, stopped) = outcome?;
+    recording_closed?;
+    session_end?;
 
-    let mut end = 0usize;
-    for (idx, _) in input.char_indices() {
-        if idx as u64 > max_bytes.saturating_sub(14) {
-            break;
-        }
-        end = idx;
-    }
-    let mut out = input[..end].to_string();
-    out.push_str("...[truncated]");
-    out
+    Ok(match stopped {
+        Some(action) => CodexRun::Stopped(action),
+        None => CodexRun::Exited(status),
+    })
 }
 
-fn capture_workspace_text_files(
-    repo_root: &Path,
-    exclude: &[String],
-    previous: Option<&HashMap<String, FileState>>,
-    force_content_refresh: bool,
-) -> Result<HashMap<String, FileState>> {
-    let mut snapshot = HashMap::new();
-    for entry in WalkDir::new(repo_root)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|entry| should_walk_entry(repo_root, entry.path(), exclude))
-        .filter_map(|entry| entry.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        let rel_path = match relative_path_string(repo_root, entry.path()) {
-            Some(rel_path) if !rel_path.is_empty() => rel_path,
-            _ => continue,
-        };
-        if is_excluded_path(&rel_path, exclude) {
-            continue;
-        }
-        let metadata = match entry.metadata() {
-            Ok(metadata) => metadata,
-            Err(_) => continue,
-        };
-        let len = metadata.len();
-        if len > 2_000_000 {
-            continue;
-        }
-        let modified = metadata.modified().ok();
-
-        if let Some(prev) = previous.and_then(|prev| prev.get(&rel_path)) {
-            if should_reuse_previous_file_state(prev, len, modified, force_content_refresh) {
-                snapshot.insert(rel_path, prev.clone());
-                continue;
+/// Prints what the supervisor reports while Codex runs.
+fn print_supervise_event(event: SuperviseEvent) {
+    match event {
+        SuperviseEvent::Tripped { entry, action } => {
+            eprintln!(
+                "spp: gate tripped: human ratio {} is below target {}; {} mode is now enforced",
+                percent_1dp(entry.gate_ratio),
+                percent_1dp(entry.gate_target),
+                entry.mode_after
+            );
+            match action {
+                GateTripAction::Warn => eprintln!(
+                    "spp: this Codex session keeps its {} mode permissions until it exits",
+                    entry.mode_before
+                ),
+                GateTripAction::Terminate => eprintln!("spp: stopping codex"),
+                GateTripAction::Restart => {
+                    eprintln!("spp: restarting codex in {} mode", entry.mode_after)
+                }
             }
         }
-
-        let mut bytes = Vec::new();
-        if File::open(entry.path())
-            .and_then(|mut file| file.read_to_end(&mut bytes))
-            .is_err()
-        {
-            continue;
-        }
-        if bytes.contains(&0) {
-            continue;
-        }
-        let text = match String::from_utf8(bytes) {
-            Ok(text) => text,
-            Err(_) => continue,
-        };
-        snapshot.insert(
-            rel_path,
-            FileState {
-                len,
-                modified,
-                content: Arc::<str>::from(text),
-            },
-        );
+        SuperviseEvent::CheckFailed(err) => eprintln!("spp: gate check failed: {err:#}"),
+        SuperviseEvent::StopFailed(err) => eprintln!("spp: failed to stop codex: {err:#}"),
     }
-    Ok(snapshot)
 }
 
-fn relative_path_string(repo_root: &Path, path: &Path) -> Option<String> {
-    let rel = path.strip_prefix(repo_root).ok()?;
-    Some(rel.to_string_lossy().replace('\\', "/"))
-}
+fn cmd_attrib_fix(repo_root: &Path, args: AttribFixArgs) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let config = load_config(repo_root)?;
+    let git = open_backend(repo_root, &config.git)?;
+    let commits = git.resolve_commits(&args.commit)?;
 
-fn should_walk_entry(repo_root: &Path, entry_path: &Path, excludes: &[String]) -> bool {
-    let Some(rel_path) = relative_path_string(repo_root, entry_path) else {
-        return true;
+    let mut state = load_state(repo_root)?;
+    let entry = match (args.share, args.actor) {
+        (Some(share), _) => AttributionOverride::from_ai_share(share),
+        (None, Some(actor)) => AttributionOverride::from_actor(actor.into()),
+        (None, None) => bail!("either --actor or --share is required"),
     };
-    if rel_path.is_empty() {
-        return true;
+    let to_notes = args.notes || config.attribution.write_notes;
+    for commit in &commits {
+        save_override(
+            git.as_ref(),
+            &config.attribution,
+            &mut state,
+            to_notes,
+            commit,
+            entry.clone(),
+        )?;
     }
-    !is_excluded_path(&rel_path, excludes)
-}
-
-fn should_reuse_previous_file_state(
-    previous: &FileState,
-    len: u64,
-    modified: Option<SystemTime>,
-    force_content_refresh: bool,
-) -> bool {
-    !force_content_refresh && previous.len == len && previous.modified == modified
-}
-
-fn is_excluded_path(path: &str, excludes: &[String]) -> bool {
-    excludes.iter().any(|pattern| {
-        let normalized = pattern.trim_start_matches("./").trim_end_matches('/');
-        path == normalized || path.starts_with(&format!("{normalized}/"))
-    })
+    state.updated_at = Utc::now();
+    save_state(repo_root, &state)
 }
 
-fn build_unified_diff(before: Option<&str>, after: Option<&str>, path: &str) -> Option<String> {
-    let old = before.unwrap_or_default();
-    let new = after.unwrap_or_default();
-    if old == new {
-        return None;
-    }
-    let diff = TextDiff::from_lines(old, new)
-        .unified_diff()
-        .header(&format!("a/{path}"), &format!("b/{path}"))
-        .to_string();
-    if diff.trim().is_empty() {
-        None
+/// [`store_override`], printing where the override went.
+fn save_override(
+    git: &dyn GitBackend,
+    config: &AttributionConfig,
+    state: &mut State,
+    to_notes: bool,
+    commit: &str,
+    entry: AttributionOverride,
+) -> Result<()> {
+    let entry = store_override(git, config, state, to_notes, commit, entry)?;
+    let share = percent_1dp(entry.ai_share());
+    if to_notes {
+        println!(
+            "shared attribution override saved to {}: {} => {} (AI share {})",
+            config.notes_ref, commit, entry.actor, share
+        );
     } else {
-        Some(diff)
-    }
-}
-
-fn guess_language(path: &str) -> String {
-    Path::new(path)
-        .extension()
-        .and_then(OsStr::to_str)
-        .map(|ext| ext.to_lowercase())
-        .unwrap_or_else(|| "unknown".to_string())
-}
-
-fn generate_session_id() -> String {
-    format!(
-        "{}-{}-{}",
-        Utc::now().format("%Y%m%dT%H%M%SZ"),
-        std::process::id(),
-        EVENT_COUNTER.fetch_add(1, Ordering::Relaxed)
-    )
-}
-
-fn generate_event_id() -> String {
-    format!(
-        "evt-{}-{}",
-        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
-        EVENT_COUNTER.fetch_add(1, Ordering::Relaxed)
-    )
-}
-
-fn write_transcript_event(path: &Path, event: &TranscriptEvent) -> Result<()> {
-    append_jsonl(path, event)
-}
-
-fn file_inode(metadata: &fs::Metadata) -> Option<u64> {
-    #[cfg(unix)]
-    {
-        Some(metadata.ino())
-    }
-    #[cfg(not(unix))]
-    {
-        let _ = metadata;
-        None
+        println!(
+            "attribution override saved: {} => {} (AI share {})",
+            commit, entry.actor, share
+        );
     }
-}
-
-fn cmd_pause(repo_root: &Path, args: PauseArgs) -> Result<()> {
-    ensure_runtime_dirs(repo_root)?;
-    let mut state = load_state(repo_root)?;
-    let hours = args.hours.clamp(1, 24);
-    state.pause_until = Some(Utc::now() + Duration::hours(i64::from(hours)));
-    state.updated_at = Utc::now();
-    save_state(repo_root, &state)?;
-    println!("gate checks paused for {} hour(s)", hours);
     Ok(())
 }
 
-fn cmd_resume(repo_root: &Path) -> Result<()> {
-    ensure_runtime_dirs(repo_root)?;
-    let mut state = load_state(repo_root)?;
-    state.pause_until = None;
-    state.updated_at = Utc::now();
-    save_state(repo_root, &state)?;
-    println!("pause cleared");
-    Ok(())
-}
-
-fn cmd_reset(repo_root: &Path) -> Result<()> {
-    ensure_runtime_dirs(repo_root)?;
-    let state = State::default();
-    save_state(repo_root, &state)?;
-
-    for dir in [
-        repo_root.join(WEEKLY_DIR),
-        repo_root.join(TRANSCRIPT_DIR),
-        repo_root.join(RUNTIME_DIR),
-    ] {
-        if !dir.exists() {
-            continue;
-        }
-        for entry in fs::read_dir(&dir)? {
-            let entry = entry?;
-            if entry.file_type()?.is_file() {
-                fs::remove_file(entry.path())?;
-            }
-        }
+fn cmd_attrib_explain(repo_root: &Path, args: AttribExplainArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let state = load_state(repo_root)?;
+    let git = open_backend(repo_root, &config.git)?;
+    let full_commit = git.resolve_commit(&args.commit)?;
+    let subject = git
+        .commit_records(std::slice::from_ref(&full_commit))?
+        .pop()
+        .map(|record| record.subject)
+        .unwrap_or_default();
+    let attribution =
+        Attributor::new(&config.attribution)?.classify(git.as_ref(), &full_commit, &state)?;
+
+    println!("Commit: {} {}", full_commit, subject.trim());
+    println!(
+        "Actor: {} (AI share {})",
+        attribution.actor,
+        percent_1dp(attribution.ai_share)
+    );
+    println!("Decided by: {}", attribution.source);
+    for note in &attribution.notes {
+        println!("Note: {note}");
     }
-
-    println!("weekly state reset");
     Ok(())
 }
 
-fn cmd_codex(repo_root: &Path, args: CodexArgs) -> Result<()> {
+fn cmd_attrib_infer(repo_root: &Path, args: AttribInferArgs) -> Result<()> {
     ensure_runtime_dirs(repo_root)?;
     let config = load_config(repo_root)?;
     let mut state = load_state(repo_root)?;
-    refresh_pause(&mut state);
-
-    let pause = pause_active(&state);
-    let mut report = compute_weekly_report(repo_root, &config, &state)?;
-    apply_gate(&mut state, &mut report, pause);
-
-    let codex_mode = match state.mode {
-        Mode::Normal => &config.codex.normal,
-        Mode::Drive => &config.codex.drive,
+    let window = match args.week {
+        Some(week) => iso_week_window(&config.gate, week)?,
+        None => current_gate_window(&config.gate, Utc::now())?,
     };
 
-    validate_codex_extra_args(&args.extra)?;
-
-    let mut codex_args = args.extra.clone();
-    codex_args.extend([
-        "--sandbox".to_string(),
-        codex_mode.sandbox.clone(),
-        "--ask-for-approval".to_string(),
-        codex_mode.approval.clone(),
-    ]);
-
-    let branch = git_output(repo_root, &["rev-parse", "--abbrev-ref", "HEAD"])
-        .unwrap_or_else(|_| "unknown".to_string())
-        .trim()
-        .to_string();
-    let commit = git_output(repo_root, &["rev-parse", "HEAD"])
-        .ok()
-        .map(|s| s.trim().to_string());
-
-    let session_entry = SessionLogEntry {
-        log_schema_version: config.log_schema_version.clone(),
-        timestamp: Utc::now(),
-        command: format!("codex {}", codex_args.join(" ")),
-        mode: state.mode.clone(),
-        sandbox: codex_mode.sandbox.clone(),
-        approval: codex_mode.approval.clone(),
-        git_branch: branch,
-        git_commit: commit,
-        gate_ratio: Some(report.ratio),
-        gate_target: Some(report.target_ratio),
-        notes: Some(format!(
-            "gate_passed={}, pause_active={}",
-            report.gate_passed, pause
-        )),
-    };
-
-    save_state(repo_root, &state)?;
-    write_weekly_report(repo_root, &report)?;
-    write_session_log(repo_root, &session_entry)?;
-    enforce_log_size(repo_root, config.max_log_bytes)?;
-
-    if args.dry_run {
-        println!("dry-run: codex {}", codex_args.join(" "));
-        return Ok(());
-    }
-
-    let status = Command::new("codex")
-        .args(&codex_args)
-        .status()
-        .with_context(|| "failed to start codex command")?;
-    if !status.success() {
-        bail!("codex exited with status {}", status);
-    }
-
-    Ok(())
-}
-
-fn validate_codex_extra_args(extra: &[String]) -> Result<()> {
-    for arg in extra {
-        if arg == "--full-auto" || arg.starts_with("--full-auto=") {
-            bail!("`--full-auto` is prohibited by SPP policy");
-        }
-        if arg == "--sandbox" || arg.starts_with("--sandbox=") {
-            bail!("`--sandbox` is managed by spp and cannot be overridden");
-        }
-        if arg == "--ask-for-approval" || arg.starts_with("--ask-for-approval=") {
-            bail!("`--ask-for-approval` is managed by spp and cannot be overridden");
+    let git = open_backend(repo_root, &config.git)?;
+    let inferred = infer_attributions(git.as_ref(), &config.gate, &state, &window)?;
+    let mut saved = 0;
+    for row in &inferred {
+        if row.suggestion.ai_share >= args.min_share {
+            state
+                .attribution_suggestions
+                .insert(row.commit.clone(), row.suggestion.clone());
+            saved += 1;
+        } else {
+            state.attribution_suggestions.remove(&row.commit);
         }
     }
-    Ok(())
-}
-
-fn cmd_attrib_fix(repo_root: &Path, args: AttribFixArgs) -> Result<()> {
-    ensure_runtime_dirs(repo_root)?;
-    let full_commit = git_output(repo_root, &["rev-parse", "--verify", &args.commit])?
-        .trim()
-        .to_string();
-    if full_commit.is_empty() {
-        bail!("commit not found: {}", args.commit);
-    }
-
-    let mut state = load_state(repo_root)?;
-    state
-        .attribution_overrides
-        .insert(full_commit.clone(), args.actor);
-    state.updated_at = Utc::now();
     save_state(repo_root, &state)?;
+
     println!(
-        "attribution override saved: {} => {:?}",
-        full_commit, args.actor
+        "Inferred AI share for {} commits in {} ({} -> {})",
+        inferred.len(),
+        window.week,
+        window.start.format("%Y-%m-%d %H:%M UTC"),
+        window.end.format("%Y-%m-%d %H:%M UTC")
     );
-    Ok(())
-}
-
-fn ensure_runtime_dirs(repo_root: &Path) -> Result<()> {
-    fs::create_dir_all(repo_root.join(SESSION_DIR))?;
-    fs::create_dir_all(repo_root.join(WEEKLY_DIR))?;
-    fs::create_dir_all(repo_root.join(TRANSCRIPT_DIR))?;
-    fs::create_dir_all(repo_root.join(RUNTIME_DIR))?;
-    Ok(())
-}
-
-fn detect_repo_root() -> Result<PathBuf> {
-    let out = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
-        .output()
-        .with_context(|| "failed to execute git")?;
-    if !out.status.success() {
-        bail!("current directory is not a git repository");
-    }
-    let root = String::from_utf8(out.stdout)?.trim().to_string();
-    if root.is_empty() {
-        bail!("failed to resolve git repo root");
-    }
-    Ok(PathBuf::from(root))
-}
-
-fn load_config(repo_root: &Path) -> Result<AppConfig> {
-    let runtime_path = repo_root.join(RUNTIME_CONFIG);
-    if runtime_path.exists() {
-        let text = fs::read_to_string(&runtime_path)
-            .with_context(|| format!("failed to read {}", runtime_path.display()))?;
-        let cfg: AppConfig =
-            toml::from_str(&text).with_context(|| "failed to parse runtime config")?;
-        return Ok(cfg);
-    }
-
-    let template_path = repo_root.join(TEMPLATE_CONFIG);
-    if template_path.exists() {
-        let text = fs::read_to_string(&template_path)
-            .with_context(|| format!("failed to read {}", template_path.display()))?;
-        let cfg: AppConfig =
-            toml::from_str(&text).with_context(|| "failed to parse template config")?;
-        return Ok(cfg);
-    }
-
-    Ok(AppConfig::default())
-}
-
-fn load_state(repo_root: &Path) -> Result<State> {
-    let path = repo_root.join(STATE_FILE);
-    if !path.exists() {
-        return Ok(State::default());
+    if !inferred.is_empty() {
+        print!("{}", render_inferred_table(&inferred, args.min_share));
     }
-    let text =
-        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
-    let state: State = serde_json::from_str(&text).with_context(|| "failed to parse state")?;
-    Ok(state)
-}
-
-fn save_state(repo_root: &Path, state: &State) -> Result<()> {
-    let mut state = state.clone();
-    state.updated_at = Utc::now();
-    let path = repo_root.join(STATE_FILE);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let content = serde_json::to_string_pretty(&state)?;
-    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
-}
-
-fn refresh_pause(state: &mut State) {
-    if let Some(until) = state.pause_until {
-        if Utc::now() >= until {
-            state.pause_until = None;
-        }
+    if saved > 0 {
+        println!(
+            "{} suggestion(s) saved; accept with `spp attrib accept <commit>` or `spp attrib accept --all`",
+            saved
+        );
     }
+    Ok(())
 }
 
-fn pause_active(state: &State) -> bool {
-    matches!(state.pause_until, Some(until) if Utc::now() < until)
-}
-
-fn compute_weekly_report(
-    repo_root: &Path,
-    config: &AppConfig,
-    state: &State,
-) -> Result<WeeklyReport> {
-    let now = Utc::now();
-    let iso = now.iso_week();
-    let year = iso.year();
-    let iso_week = iso.week();
-    let metrics = collect_weekly_metrics(repo_root, config, state, year, iso_week)?;
-    let total = metrics.human_lines_added + metrics.ai_lines_added;
-    let ratio = if total == 0 {
-        1.0
+fn cmd_attrib_accept(repo_root: &Path, args: AttribAcceptArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let git = open_backend(repo_root, &config.git)?;
+    let mut state = load_state(repo_root)?;
+    let commits = if args.all {
+        let mut all = state
+            .attribution_suggestions
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        all.sort();
+        all
     } else {
-        metrics.human_lines_added as f64 / total as f64
+        args.commits
+            .iter()
+            .map(|rev| git.resolve_commit(rev))
+            .collect::<Result<Vec<_>>>()?
     };
-    let gate_passed = ratio >= config.weekly_ratio_target;
-    Ok(WeeklyReport {
-        log_schema_version: config.log_schema_version.clone(),
-        generated_at: now,
-        year,
-        iso_week,
-        human_lines_added: metrics.human_lines_added,
-        ai_lines_added: metrics.ai_lines_added,
-        human_commit_count: metrics.human_commit_count,
-        ai_commit_count: metrics.ai_commit_count,
-        ratio,
-        target_ratio: config.weekly_ratio_target,
-        gate_passed,
-        mode_after_evaluation: state.mode.clone(),
-        notes: metrics.notes,
-    })
-}
 
-fn apply_gate(state: &mut State, report: &mut WeeklyReport, pause_active: bool) {
-    if pause_active {
-        report
-            .notes
-            .push("gate evaluation bypassed due to active pause".to_string());
-        report.mode_after_evaluation = state.mode.clone();
-        return;
-    }
-
-    if report.gate_passed {
-        if state.mode == Mode::Drive && state.drive_reason.as_deref() == Some("gate") {
-            state.mode = Mode::Normal;
-            state.drive_reason = None;
-        }
-    } else {
-        state.mode = Mode::Drive;
-        state.drive_reason = Some("gate".to_string());
-        report
-            .notes
-            .push("ratio below target, forced drive mode".to_string());
-    }
-
-    report.mode_after_evaluation = state.mode.clone();
-}
-
-fn collect_weekly_metrics(
-    repo_root: &Path,
-    config: &AppConfig,
-    state: &State,
-    year: i32,
-    iso_week: u32,
-) -> Result<WeeklyMetrics> {
-    let monday = NaiveDate::from_isoywd_opt(year, iso_week, Weekday::Mon)
-        .with_context(|| "failed to compute start of ISO week")?;
-    let start = monday
-        .and_hms_opt(0, 0, 0)
-        .with_context(|| "invalid week start timestamp")?;
-    let end = start + Duration::days(7);
-
-    let since = start.and_utc().to_rfc3339();
-    let until = end.and_utc().to_rfc3339();
-
-    let commits_raw = git_output(
-        repo_root,
-        &[
-            "log",
-            "--since",
-            &since,
-            "--until",
-            &until,
-            "--no-merges",
-            "--pretty=format:%H",
-        ],
-    )?;
-    let mut metrics = WeeklyMetrics::default();
-
-    for commit in commits_raw.lines().map(str::trim).filter(|l| !l.is_empty()) {
-        let commit = commit.to_string();
-        let actor = classify_actor(repo_root, &commit, config, state)?;
-        let added_lines = commit_added_lines(repo_root, &commit)?;
-
-        match actor {
-            Actor::Human => {
-                metrics.human_commit_count += 1;
-                metrics.human_lines_added += added_lines;
-            }
-            Actor::Ai => {
-                metrics.ai_commit_count += 1;
-                metrics.ai_lines_added += added_lines;
-            }
-        }
-    }
-
-    Ok(metrics)
-}
-
-fn classify_actor(
-    repo_root: &Path,
-    commit: &str,
-    config: &AppConfig,
-    state: &State,
-) -> Result<Actor> {
-    if let Some(actor) = state.attribution_overrides.get(commit).copied() {
-        return Ok(actor);
-    }
-
-    let trailer_output = git_output(repo_root, &["show", "-s", "--format=%B", commit])?;
-    let trailer_lc = trailer_output.to_lowercase();
-    if trailer_lc.contains("co-authored-by: codex") {
-        return Ok(Actor::Ai);
-    }
-
-    let email = git_output(repo_root, &["show", "-s", "--format=%ae", commit])?;
-    let email_lc = email.trim().to_lowercase();
-    if config
-        .attribution
-        .codex_author_emails
-        .iter()
-        .any(|candidate| candidate.to_lowercase() == email_lc)
-    {
-        return Ok(Actor::Ai);
-    }
-
-    if let Ok(notes) = git_output(repo_root, &["notes", "show", commit]) {
-        let notes_lc = notes.to_lowercase();
-        if notes_lc.contains("spp:ai") {
-            return Ok(Actor::Ai);
-        }
-        if notes_lc.contains("spp:human") {
-            return Ok(Actor::Human);
-        }
-    }
-
-    Ok(Actor::Human)
-}
-
-fn commit_added_lines(repo_root: &Path, commit: &str) -> Result<u64> {
-    let out = git_output(repo_root, &["show", "--numstat", "--format=", commit])?;
-    let mut sum = 0_u64;
-    for line in out.lines().map(str::trim).filter(|l| !l.is_empty()) {
-        let mut parts = line.split('\t');
-        let added = parts.next().unwrap_or_default();
-        if added == "-" {
-            continue;
-        }
-        if let Ok(v) = added.parse::<u64>() {
-            sum += v;
-        }
-    }
-    Ok(sum)
-}
-
-fn git_output(repo_root: &Path, args: &[&str]) -> Result<String> {
-    let out = Command::new("git")
-        .args(args)
-        .current_dir(repo_root)
-        .output()
-        .with_context(|| format!("failed to execute git {:?}", args))?;
-    if !out.status.success() {
-        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
-        bail!("git {:?} failed: {}", args, stderr);
-    }
-    Ok(String::from_utf8(out.stdout)?)
-}
-
-fn write_weekly_report(repo_root: &Path, report: &WeeklyReport) -> Result<()> {
-    let path = repo_root
-        .join(WEEKLY_DIR)
-        .join(format!("{}-W{:02}.json", report.year, report.iso_week));
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    for commit in &commits {
+        let suggestion = state
+            .attribution_suggestions
+            .remove(commit)
+            .with_context(|| {
+                format!("no inferred suggestion for {commit}; run `spp attrib infer`")
+            })?;
+        let entry = AttributionOverride::from_ai_share(suggestion.ai_share);
+        save_override(
+            git.as_ref(),
+            &config.attribution,
+            &mut state,
+            config.attribution.write_notes,
+            commit,
+            entry,
+        )?;
     }
-    let content = serde_json::to_string_pretty(report)?;
-    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
-}
-
-fn write_session_log(repo_root: &Path, entry: &SessionLogEntry) -> Result<()> {
-    let iso = entry.timestamp.iso_week();
-    let path = repo_root
-        .join(SESSION_DIR)
-        .join(format!("{}-W{:02}.jsonl", iso.year(), iso.week()));
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    if commits.is_empty() {
+        println!("no inferred suggestions to accept");
+        return Ok(());
     }
-    append_jsonl(&path, entry)
+    state.updated_at = Utc::now();
+    save_state(repo_root, &state)
 }
 
-fn append_jsonl<T: Serialize>(path: &Path, value: &T) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let line = serde_json::to_string(value)?;
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .with_context(|| format!("failed to open {}", path.display()))?;
-    writeln!(file, "{line}")?;
-    Ok(())
+enum ReviewAnswer {
+    Keep,
+    Quit,
+    Set(AttributionOverride),
 }
 
-fn write_text_asset(path: &Path, content: &str, force: bool) -> Result<WriteOutcome> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    if path.exists() && !force {
-        return Ok(WriteOutcome::Skipped);
-    }
-
-    let outcome = if path.exists() {
-        WriteOutcome::Overwritten
-    } else {
-        WriteOutcome::Created
-    };
-    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
-    Ok(outcome)
+fn parse_review_answer(raw: &str) -> Result<ReviewAnswer> {
+    Ok(match raw.trim().to_ascii_lowercase().as_str() {
+        "" => ReviewAnswer::Keep,
+        "q" | "quit" => ReviewAnswer::Quit,
+        "h" | "human" => ReviewAnswer::Set(AttributionOverride::from_actor(Actor::Human)),
+        "a" | "ai" => ReviewAnswer::Set(AttributionOverride::from_actor(Actor::Ai)),
+        "m" | "mixed" => ReviewAnswer::Set(AttributionOverride::from_actor(Actor::Mixed)),
+        share => ReviewAnswer::Set(AttributionOverride::from_ai_share(parse_ai_share(share)?)),
+    })
 }
 
-fn ensure_gitignore_rule(path: &Path, rule: &str) -> Result<bool> {
-    let mut content = if path.exists() {
-        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?
-    } else {
-        String::new()
+fn cmd_attrib_review(repo_root: &Path, args: AttribReviewArgs) -> Result<()> {
+    ensure_runtime_dirs(repo_root)?;
+    let config = load_config(repo_root)?;
+    let mut state = load_state(repo_root)?;
+    let window = match args.week {
+        Some(week) => iso_week_window(&config.gate, week)?,
+        None => current_gate_window(&config.gate, Utc::now())?,
     };
-
-    let has_rule = content.lines().any(|line| {
-        let normalized = line.trim();
-        normalized == rule || normalized == ".codex-spp/" || normalized == "/.codex-spp"
-    });
-    if has_rule {
-        return Ok(false);
-    }
-
-    if !content.is_empty() && !content.ends_with('\n') {
-        content.push('\n');
-    }
-    content.push_str(rule);
-    content.push('\n');
-
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
-    Ok(true)
-}
-
-fn enforce_log_size(repo_root: &Path, max_bytes: u64) -> Result<()> {
-    let mut files = collect_log_files(repo_root)?;
-    let mut total: u64 = files.iter().map(|f| f.size).sum();
-    if total <= max_bytes {
+    let git = open_backend(repo_root, &config.git)?;
+    let refs = resolve_gate_refs(git.as_ref(), &config.gate.scope)?;
+    let mut commits = window_commits(git.as_ref(), &config.gate, &window, &refs)?.commits;
+    commits.reverse();
+    if commits.is_empty() {
+        println!("no commits to review in {}", window.week);
         return Ok(());
     }
 
-    files.sort_by_key(|f| f.modified);
-    for file in files {
-        if total <= max_bytes {
-            break;
-        }
-        fs::remove_file(&file.path)
-            .with_context(|| format!("failed to remove {}", file.path.display()))?;
-        total = total.saturating_sub(file.size);
-    }
-    Ok(())
-}
-
-#[derive(Debug)]
-struct SizedFile {
-    path: PathBuf,
-    size: u64,
-    modified: SystemTime,
-}
+    let attributor = Attributor::new(&config.attribution)?;
+    let records = load_commit_records(git.as_ref(), &commits)?;
+    let mut input = io::stdin().lock();
+    let mut saved = 0;
+    println!("Reviewing {} commits in {}", commits.len(), window.week);
+    'commits: for (idx, commit) in commits.iter().enumerate() {
+        let record = records
+            .get(commit)
+            .with_context(|| format!("failed to load commit {commit}"))?;
+        let current = attributor.classify(git.as_ref(), commit, &state)?;
+        println!();
+        println!(
+            "[{}/{}] {} {} | +{} lines | {}",
+            idx + 1,
+            commits.len(),
+            commit.get(..10).unwrap_or(commit),
+            record.author_email,
+            record.added_lines(),
+            record.subject
+        );
+        println!(
+            "  current: {} (AI share {}) via {}",
+            current.actor,
+            percent_1dp(current.ai_share),
+            current.source
+        );
 
-fn collect_log_files(repo_root: &Path) -> Result<Vec<SizedFile>> {
-    let mut files = Vec::new();
-    for rel in [SESSION_DIR, WEEKLY_DIR, TRANSCRIPT_DIR] {
-        let dir = repo_root.join(rel);
-        if !dir.exists() {
-            continue;
-        }
-        for entry in fs::read_dir(&dir)? {
-            let entry = entry?;
-            if !entry.file_type()?.is_file() {
-                continue;
+        loop {
+            print!("  [h]uman, [a]i, [m]ixed, a share (0.4 or 40%), Enter to keep, [q]uit: ");
+            io::stdout().flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                break 'commits;
             }
-            let path = entry.path();
-            if path.extension().and_then(OsStr::to_str).is_none() {
-                continue;
+            match parse_review_answer(&line) {
+                Ok(ReviewAnswer::Keep) => break,
+                Ok(ReviewAnswer::Quit) => break 'commits,
+                Ok(ReviewAnswer::Set(entry)) => {
+                    save_override(
+                        git.as_ref(),
+                        &config.attribution,
+                        &mut state,
+                        config.attribution.write_notes,
+                        commit,
+                        entry,
+                    )?;
+                    saved += 1;
+                    break;
+                }
+                Err(err) => println!("  {err:#}"),
             }
-            let metadata = entry.metadata()?;
-            files.push(SizedFile {
-                path,
-                size: metadata.len(),
-                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-            });
         }
     }
-    Ok(files)
-}
 
-#[allow(dead_code)]
-fn touch(path: &Path) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    if saved > 0 {
+        state.updated_at = Utc::now();
+        save_state(repo_root, &state)?;
     }
-    let _ = File::options().create(true).append(true).open(path)?;
+    println!("{} attribution override(s) saved", saved);
     Ok(())
 }
 
-fn append_start_failed_session_end(
-    transcript_path: &Path,
-    log_schema_version: &str,
-    session_id: &str,
-    mode: Mode,
-    error_message: &str,
-) -> Result<()> {
-    let end_event = TranscriptEvent {
-        log_schema_version: log_schema_version.to_string(),
-        event_id: generate_event_id(),
-        session_id: session_id.to_string(),
-        event_type: "session_end".to_string(),
-        timestamp: Utc::now(),
-        mode,
-        payload: Some(json!({
-            "stats": {
-                "chat_events": 0,
-                "diff_events": 0,
-                "duration_sec": 0,
-                "history_offset": 0
-            },
-            "reason": "start_failed",
-            "errors": [error_message]
-        })),
-        notes: None,
-    };
-    write_transcript_event(transcript_path, &end_event)
+fn cmd_attrib_push(repo_root: &Path, args: AttribSyncArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let notes_ref = &config.attribution.notes_ref;
+    push_override_notes(repo_root, notes_ref, &args.remote)?;
+    println!("pushed {} to {}", notes_ref, args.remote);
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicU64, Ordering};
-
-    static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(1);
-
-    struct TempDirGuard {
-        path: PathBuf,
-    }
-
-    impl TempDirGuard {
-        fn new(prefix: &str) -> Result<Self> {
-            let id = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
-            let path = std::env::temp_dir().join(format!("{prefix}-{}-{id}", std::process::id()));
-            fs::create_dir_all(&path)?;
-            Ok(Self { path })
-        }
-
-        fn path(&self) -> &Path {
-            &self.path
-        }
-    }
-
-    impl Drop for TempDirGuard {
-        fn drop(&mut self) {
-            let _ = fs::remove_dir_all(&self.path);
-        }
-    }
-
-    fn sample_weekly_report() -> WeeklyReport {
-        WeeklyReport {
-            log_schema_version: "1.1".to_string(),
-            generated_at: Utc::now(),
-            year: 2026,
-            iso_week: 7,
-            human_lines_added: 60,
-            ai_lines_added: 40,
-            human_commit_count: 3,
-            ai_commit_count: 1,
-            ratio: 0.6,
-            target_ratio: 0.7,
-            gate_passed: false,
-            mode_after_evaluation: Mode::Normal,
-            notes: Vec::new(),
-        }
-    }
-
-    #[test]
-    fn safe_ratio_returns_one_when_denominator_zero() {
-        assert_eq!(safe_ratio(0, 0), 1.0);
-        assert_eq!(safe_ratio(3, 0), 1.0);
-    }
-
-    #[test]
-    fn commit_ratio_uses_human_commits() {
-        let report = sample_weekly_report();
-        assert!((commit_ratio(&report) - 0.75).abs() < f64::EPSILON);
-    }
-
-    #[test]
-    fn commit_ratio_returns_one_when_total_commit_is_zero() {
-        let mut report = sample_weekly_report();
-        report.human_commit_count = 0;
-        report.ai_commit_count = 0;
-        assert_eq!(commit_ratio(&report), 1.0);
-    }
-
-    #[test]
-    fn render_status_plain_matches_legacy_output_shape() {
-        let state = State::default();
-        let report = sample_weekly_report();
-        let rendered = render_status_plain(&state, &report);
-        assert_eq!(
-            rendered,
-            "mode: Normal\nratio: 0.600 (target: 0.700) gate_passed: false\n"
-        );
-    }
-
-    #[test]
-    fn render_status_rich_always_shows_pause_and_notes() {
-        let state = State::default();
-        let report = sample_weekly_report();
-        let rendered = render_status_rich(&state, &report, false);
-
-        assert!(rendered.contains("Mode: Normal | Gate: FAIL | Week: 2026-W07"));
-        assert!(rendered.contains("Gate reason: below target"));
-        assert!(rendered.contains("Lines: 60.0% | Target: 70.0% | Delta: -10.0pt"));
-        assert!(rendered.contains("Commits: 75.0%"));
-        assert!(rendered.contains("Commits: 75.0%\n\nHuman: 3 commits / 60 lines"));
-        assert!(rendered.contains("Human: 3 commits / 60 lines"));
-        assert!(rendered.contains("AI: 1 commits / 40 lines"));
-        assert!(rendered.contains("Pause until: none"));
-        assert!(rendered.contains("Notes: none"));
-    }
-
-    #[test]
-    fn render_status_rich_shows_pause_and_each_note() {
-        let mut state = State::default();
-        state.pause_until = Some(Utc::now() + Duration::hours(1));
-
-        let mut report = sample_weekly_report();
-        report.notes = vec![
-            "gate evaluation bypassed due to active pause".to_string(),
-            "sample note".to_string(),
-        ];
-
-        let rendered = render_status_rich(&state, &report, true);
-        assert!(rendered.contains("Gate reason: below target, gate paused"));
-        assert!(rendered.contains("Pause until: "));
-        assert!(rendered.contains("Notes:\n  - gate evaluation bypassed due to active pause"));
-        assert!(rendered.contains("  - sample note"));
-    }
-
-    #[test]
-    fn status_gate_reason_covers_all_branches() {
-        let state = State::default();
-        let mut report = sample_weekly_report();
-
-        report.gate_passed = true;
-        assert_eq!(status_gate_reason(&state, &report, false), "on target");
-
-        report.gate_passed = false;
-        assert_eq!(
-            status_gate_reason(&state, &report, true),
-            "below target, gate paused"
-        );
-
-        let mut drive_state = State::default();
-        drive_state.mode = Mode::Drive;
-        drive_state.drive_reason = Some("gate".to_string());
-        assert_eq!(
-            status_gate_reason(&drive_state, &report, false),
-            "below target, drive enforced"
+fn cmd_attrib_pull(repo_root: &Path, args: AttribSyncArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let notes_ref = &config.attribution.notes_ref;
+    if pull_override_notes(repo_root, notes_ref, &args.remote)? {
+        println!(
+            "merged {} from {} (remote wins on conflicts)",
+            notes_ref, args.remote
         );
-
-        assert_eq!(status_gate_reason(&state, &report, false), "below target");
-    }
-
-    #[test]
-    fn should_walk_entry_skips_excluded_dirs_and_keeps_similar_prefixes() {
-        let repo_root = PathBuf::from("/tmp/repo");
-        let excludes = vec![".codex-spp/".to_string(), ".git/".to_string()];
-
-        assert!(!should_walk_entry(
-            &repo_root,
-            &repo_root.join(".codex-spp"),
-            &excludes
-        ));
-        assert!(!should_walk_entry(
-            &repo_root,
-            &repo_root.join(".codex-spp/runtime"),
-            &excludes
-        ));
-        assert!(should_walk_entry(
-            &repo_root,
-            &repo_root.join(".codex-spp-backup"),
-            &excludes
-        ));
-    }
-
-    #[test]
-    fn recorder_loop_stops_before_history_read_when_stop_requested() -> Result<()> {
-        let temp = TempDirGuard::new("spp-recorder-stop")?;
-        let control_path = temp.path().join("control");
-        fs::write(&control_path, "stop\n")?;
-
-        let args = DriveRecordArgs {
-            session_id: "session".to_string(),
-            log_schema_version: "1.1".to_string(),
-            transcript_path: temp.path().join("transcript.jsonl"),
-            history_path: temp.path().join("missing-history.jsonl"),
-            history_offset: 0,
-            control_path,
-            done_path: temp.path().join("done.json"),
-            include_file_diff: true,
-            capture_full_text: true,
-            max_event_bytes: DEFAULT_TRANSCRIPT_EVENT_MAX_BYTES,
-            poll_interval_ms: 100,
-            exclude: Vec::new(),
-        };
-
-        let done = run_drive_recorder_loop(temp.path(), &args)?;
-        assert_eq!(done.chat_events, 0);
-        assert_eq!(done.diff_events, 0);
-        assert!(done.errors.is_empty());
-        Ok(())
+    } else {
+        println!("{} has no {} yet", args.remote, notes_ref);
     }
+    Ok(())
+}
 
-    #[test]
-    fn should_reuse_previous_file_state_respects_force_refresh() {
-        let previous = FileState {
-            len: 10,
-            modified: Some(SystemTime::UNIX_EPOCH),
-            content: Arc::<str>::from("previous"),
-        };
-
-        assert!(should_reuse_previous_file_state(
-            &previous,
-            10,
-            Some(SystemTime::UNIX_EPOCH),
-            false
-        ));
-        assert!(!should_reuse_previous_file_state(
-            &previous,
-            10,
-            Some(SystemTime::UNIX_EPOCH),
-            true
-        ));
+fn cmd_attrib_orphans(repo_root: &Path, args: AttribOrphansArgs) -> Result<()> {
+    let config = load_config(repo_root)?;
+    let git = open_backend(repo_root, &config.git)?;
+    let mut state = load_state(repo_root)?;
+    let orphans = orphaned_overrides(git.as_ref(), &config.attribution, &state, args.days)?;
+    if orphans.is_empty() {
+        println!("no orphaned attribution overrides");
+        return Ok(());
     }
 
-    #[test]
-    fn append_start_failed_session_end_writes_recovery_event() -> Result<()> {
-        let temp = TempDirGuard::new("spp-start-failed")?;
-        let transcript_path = temp.path().join("transcript.jsonl");
-        let start_event = TranscriptEvent {
-            log_schema_version: "1.1".to_string(),
-            event_id: "e1".to_string(),
-            session_id: "session-a".to_string(),
-            event_type: "session_start".to_string(),
-            timestamp: Utc::now(),
-            mode: Mode::Drive,
-            payload: None,
-            notes: None,
+    for orphan in &orphans {
+        let status = match &orphan.successor {
+            Some(successor) => format!("inherited by {successor}"),
+            None => "no successor found".to_string(),
         };
-        write_transcript_event(&transcript_path, &start_event)?;
-
-        append_start_failed_session_end(
-            &transcript_path,
-            "1.1",
-            "session-a",
-            Mode::Drive,
-            "spawn failed",
-        )?;
-
-        let raw = fs::read_to_string(&transcript_path)?;
-        let events = raw
-            .lines()
-            .map(serde_json::from_str::<Value>)
-            .collect::<Result<Vec<_>, _>>()?;
-        assert_eq!(events.len(), 2);
-        assert_eq!(events[1]["event_type"], "session_end");
-        assert_eq!(events[1]["payload"]["reason"], "start_failed");
-        assert_eq!(events[1]["payload"]["errors"][0], "spawn failed");
-        Ok(())
+        println!(
+            "{} {} (AI share {}): {}",
+            orphan.commit,
+            orphan.entry.actor,
+            percent_1dp(orphan.entry.ai_share()),
+            status
+        );
     }
-
-    #[test]
-    fn should_force_refresh_file_state_uses_fixed_interval() {
-        assert!(!should_force_refresh_file_state(1));
-        assert!(!should_force_refresh_file_state(
-            FILE_STATE_REVALIDATE_INTERVAL_POLLS - 1
-        ));
-        assert!(should_force_refresh_file_state(
-            FILE_STATE_REVALIDATE_INTERVAL_POLLS
-        ));
+    if !args.prune {
+        return Ok(());
     }
 
-    #[test]
-    fn effective_include_file_diff_requires_both_flags() {
-        let mut config = AppConfig::default();
-        config.diff_snapshot_enabled = false;
-        config.transcript.include_file_diff = true;
-        assert!(!effective_include_file_diff(&config));
-
-        config.diff_snapshot_enabled = true;
-        assert!(effective_include_file_diff(&config));
-
-        config.transcript.include_file_diff = false;
-        assert!(!effective_include_file_diff(&config));
+    for orphan in orphans {
+        state.attribution_overrides.remove(&orphan.commit);
+        if let Some(successor) = orphan.successor {
+            if !state.attribution_overrides.contains_key(&successor) {
+                save_override(
+                    git.as_ref(),
+                    &config.attribution,
+                    &mut state,
+                    false,
+                    &successor,
+                    orphan.entry,
+                )?;
+            }
+        }
     }
+    state.updated_at = Utc::now();
+    save_state(repo_root, &state)
 }